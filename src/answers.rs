@@ -0,0 +1,319 @@
+use std::{collections::HashMap, fs, sync::OnceLock};
+
+use crate::{cmd, error::InstallerError, ui};
+
+static ANSWERS: OnceLock<Answers> = OnceLock::new();
+
+// ── Answer file ───────────────────────────────────────────────────────────────
+
+/// Pre-seeded answers loaded from `--config <file>`.
+/// Their presence switches the installer into non-interactive mode.
+///
+/// Format: a small TOML subset — `key = value` lines grouped under optional
+/// `[section]` headers. Keys are addressed as `section.key`. Arrays of
+/// strings must fit on one line.
+///
+/// ```toml
+/// disk   = "/dev/sda"
+/// format = true
+/// kernel = "lts"
+///
+/// [packages]
+/// no_install = ["neovim"]
+///
+/// [timeouts]
+/// step   = 1800
+/// global = 7200
+/// ```
+#[derive(Debug, Default)]
+pub struct Answers {
+    values: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Str(String),
+    Int(i64),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl Answers {
+    /// Reads and parses an answer file from disk.
+    pub fn load(path: &str) -> Result<Self, InstallerError> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| InstallerError::Config(format!("{}: {}", path, e)))
+    }
+
+    /// Parses answer-file content; errors carry the offending line number.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut section = String::new();
+
+        for (i, raw) in content.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+
+            let Some((key, val)) = line.split_once('=') else {
+                return Err(format!("line {}: expected `key = value`", i + 1));
+            };
+            let key = key.trim().trim_matches('"');
+            let full_key = if section.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", section, key)
+            };
+            let value = parse_value(val.trim())
+                .ok_or_else(|| format!("line {}: invalid value", i + 1))?;
+            values.insert(full_key, value);
+        }
+
+        Ok(Answers { values })
+    }
+
+    // ── Typed lookups ─────────────────────────────────────────────────────────
+
+    pub fn str(&self, key: &str) -> Option<&str> {
+        match self.values.get(key)? {
+            Value::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self, key: &str) -> Option<bool> {
+        match self.values.get(key)? {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn int(&self, key: &str) -> Option<i64> {
+        match self.values.get(key)? {
+            Value::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn list(&self, key: &str) -> Option<&[String]> {
+        match self.values.get(key)? {
+            Value::List(l) => Some(l),
+            _ => None,
+        }
+    }
+}
+
+// ── Fetching and verification ─────────────────────────────────────────────────
+
+/// Where a downloaded answer file (and its signature) is kept for the run.
+const FETCHED: &str = "/tmp/artix-installer-answers.toml";
+
+/// Loads `--config`, which may be an `http(s)://` URL for PXE setups, and
+/// verifies it before anything is parsed:
+///   `sha256` → the file's SHA-256 must match (e.g. pinned on the kernel
+///              command line next to the URL)
+///   `key`    → `<config>.asc`, a detached armored signature, must verify
+///              with `gpgv` against this public key file
+///
+/// Plain `http://` is refused unless one of the two is given. Call before
+/// `--dry-run` takes effect, so the checks run for real.
+pub fn load_verified(
+    config: &str,
+    sha256: Option<&str>,
+    key: Option<&str>,
+) -> Result<Answers, InstallerError> {
+    let remote = config.starts_with("https://") || config.starts_with("http://");
+    if !remote && sha256.is_none() && key.is_none() {
+        return Answers::load(config);
+    }
+    if config.starts_with("http://") && sha256.is_none() && key.is_none() {
+        return Err(InstallerError::Config(format!(
+            "{}: plain HTTP needs --config-sha256 or --config-key",
+            config
+        )));
+    }
+
+    let path = if remote {
+        fs::write(FETCHED, download(config)?)?;
+        FETCHED.to_string()
+    } else {
+        config.to_string()
+    };
+
+    if let Some(expected) = sha256 {
+        let out = cmd::run_capture("sha256sum", &[&path])?;
+        let actual = out.split_whitespace().next().unwrap_or_default();
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(InstallerError::Config(format!(
+                "{}: SHA-256 is {}, expected {}",
+                config, actual, expected
+            )));
+        }
+        ui::print_success("Answer file checksum verified.");
+    }
+    if let Some(key) = key {
+        let sig = format!("{}.asc", path);
+        if remote {
+            fs::write(&sig, download(&format!("{}.asc", config))?)?;
+        }
+        // gpgv looks up a keyring without a slash under ~/.gnupg.
+        let key = fs::canonicalize(key)?;
+        let key = key.to_string_lossy();
+        cmd::run_capture("gpgv", &["--keyring", &key, &sig, &path]).map_err(|_| {
+            InstallerError::Config(format!("{}: signature check against {} failed", config, key))
+        })?;
+        ui::print_success("Answer file signature verified.");
+    }
+    if remote && sha256.is_none() && key.is_none() {
+        ui::print_warning("Answer file fetched without a checksum or signature check.");
+    }
+
+    let answers = Answers::load(&path)?;
+    if remote {
+        ui::print_info(&format!("Answers fetched from {}.", config));
+    }
+    Ok(answers)
+}
+
+/// The body of `url`; HTTPS URLs may not redirect to plain HTTP.
+fn download(url: &str) -> Result<String, InstallerError> {
+    let mut args = vec!["-fsSL", "--max-time", "60", "--retry", "3"];
+    if url.starts_with("https://") {
+        args.extend(["--proto", "=https"]);
+    }
+    args.push(url);
+    let body = cmd::run_capture("curl", &args)
+        .map_err(|e| InstallerError::Config(format!("{}: download failed ({})", url, e)))?;
+    if body.trim().is_empty() {
+        return Err(InstallerError::Config(format!("{}: downloaded an empty file", url)));
+    }
+    Ok(body)
+}
+
+// ── Global access ─────────────────────────────────────────────────────────────
+
+/// Installs the answers for the rest of the run. Call once, before any prompt.
+pub fn init(answers: Answers) {
+    let _ = ANSWERS.set(answers);
+}
+
+/// Returns the loaded answers, or `None` in interactive mode.
+pub fn get() -> Option<&'static Answers> {
+    ANSWERS.get()
+}
+
+/// `true` when running from an answer file (no prompts are shown).
+pub fn unattended() -> bool {
+    ANSWERS.get().is_some()
+}
+
+// ── Writing ───────────────────────────────────────────────────────────────────
+
+/// A string value, quoted and escaped.
+pub fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A one-line array of strings.
+pub fn list(items: &[&str]) -> String {
+    let quoted: Vec<String> = items.iter().map(|i| quote(i)).collect();
+    format!("[{}]", quoted.join(", "))
+}
+
+// ── Value parser ──────────────────────────────────────────────────────────────
+
+fn parse_value(raw: &str) -> Option<Value> {
+    // Whatever follows a string or an array is a comment.
+    if raw.starts_with('"') {
+        return parse_string(raw).map(|(s, _)| Value::Str(s));
+    }
+    if let Some(mut rest) = raw.strip_prefix('[') {
+        let mut items = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if rest.starts_with(']') {
+                return Some(Value::List(items));
+            }
+            let (item, after) = parse_string(rest)?;
+            items.push(item);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.starts_with(']') {
+                return None;
+            }
+        }
+    }
+    // Unquoted values may carry a trailing `# comment`.
+    let raw = raw.split(" #").next().unwrap_or(raw).trim();
+    match raw {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        _ => raw.parse().ok().map(Value::Int),
+    }
+}
+
+/// The quoted string `raw` starts with, `\\` and `\"` unescaped as `quote`
+/// writes them, and the rest of `raw` after the closing quote.
+fn parse_string(raw: &str) -> Option<(String, &str)> {
+    let rest = raw.strip_prefix('"')?;
+    let mut out = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, &rest[i + 1..])),
+            '\\' => match chars.next()? {
+                (_, c @ ('\\' | '"')) => out.push(c),
+                // Any other escape is kept as written.
+                (_, c) => {
+                    out.push('\\');
+                    out.push(c);
+                }
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AWKWARD: &[&str] = &[r#"say "hi""#, r"C:\path\\", "a, b", "# not a comment", ""];
+
+    #[test]
+    fn quoted_strings_round_trip() {
+        for s in AWKWARD {
+            let a = Answers::parse(&format!("key = {}   # comment", quote(s))).unwrap();
+            assert_eq!(a.str("key"), Some(*s));
+        }
+    }
+
+    #[test]
+    fn lists_round_trip() {
+        let a = Answers::parse(&format!("[s]\nkey = {}", list(AWKWARD))).unwrap();
+        assert_eq!(a.list("s.key").unwrap(), AWKWARD);
+        let a = Answers::parse("key = []").unwrap();
+        assert!(a.list("key").unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_escapes_are_kept() {
+        let a = Answers::parse(r#"key = "\d+\n""#).unwrap();
+        assert_eq!(a.str("key"), Some(r"\d+\n"));
+    }
+
+    #[test]
+    fn unterminated_values_are_errors() {
+        assert!(Answers::parse(r#"key = "open"#).is_err());
+        assert!(Answers::parse(r#"key = ["a" "b"]"#).is_err());
+        assert!(Answers::parse(r#"key = ["a", "b""#).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::error::InstallerError;
+
+const USAGE: &str = "\
+Usage: artix-installer [OPTIONS]
+       artix-installer profile save <NAME> <FILE>
+       artix-installer profile load <NAME> [OPTIONS]
+       artix-installer profile list
+       artix-installer clone <FILE>
+
+Profiles are answer files kept in /etc/artix-installer/profiles; `load`
+runs with one like --config, and without --config the saved profiles are
+offered at start. `clone` writes an answer file that rebuilds the running
+Artix system (`-` prints it).
+
+Options:
+  --config <FILE>   Run non-interactively, reading answers from FILE
+                    (or an http(s):// URL)
+  --config-sha256 <HEX>
+                    Refuse the answer file unless its SHA-256 matches
+  --config-key <FILE>
+                    Refuse the answer file unless <config>.asc verifies
+                    against this public key (gpgv)
+  --dry-run         Print every command instead of running it; no root needed
+  --plan            Work out every action like --dry-run, print them as one
+                    numbered list, save the plan and exit
+  --apply           Carry out the plan saved by --plan, without asking again
+  --export-plan <FILE>
+                    With --dry-run or --plan: save the planned operations as Markdown
+                    (or HTML, for a .html FILE) for review
+  --remote          Start sshd with a one-time root password and print how
+                    to run the wizard over SSH
+  --packages-file <FILE>
+                    Install the packages listed in FILE along with base
+  --download-limit <RATE>
+                    Cap pacman's downloads at RATE bytes/s (e.g. 500K, 2M)
+  --cachedir <DIR>  Keep downloaded packages in DIR (e.g. on a USB drive) and
+                    reuse them in the next install
+  --no-color        Disable colored output (also honored: NO_COLOR)
+  --ascii           Plain-ASCII output, for serial consoles and non-UTF-8 terminals
+  --accessible      Screen-reader friendly: no spinners, numbered text prompts
+  -h, --help        Show this help and exit";
+
+/// `profile …` commands that don't install anything.
+#[derive(Debug)]
+pub enum ProfileCommand {
+    Save { name: String, file: String },
+    List,
+}
+
+/// Command-line options.
+#[derive(Debug, Default)]
+pub struct Args {
+    /// `profile save` / `profile list`, handled before anything else.
+    pub profile: Option<ProfileCommand>,
+    /// `profile load <NAME>`: run with that profile as the answer file.
+    pub load_profile: Option<String>,
+    /// `clone <FILE>`: write an answer file describing this system and exit.
+    pub clone_to: Option<String>,
+    /// Answer file for non-interactive mode.
+    pub config: Option<String>,
+    /// Rehearse the installation without changing anything.
+    pub dry_run: bool,
+    /// Expected SHA-256 of the answer file.
+    pub config_sha256: Option<String>,
+    /// Public key that must have signed the answer file.
+    pub config_key: Option<String>,
+    /// Print the planned actions at the end of a dry run.
+    pub plan: bool,
+    /// Carry out the plan a `--plan` run saved.
+    pub apply: bool,
+    /// Where a dry run writes its plan document.
+    pub export_plan: Option<String>,
+    /// Set up SSH access to the live system instead of installing.
+    pub remote: bool,
+    /// Extra packages to install with base, one or more per line.
+    pub packages_file: Option<String>,
+    /// Download rate cap for pacman on the live system, like `2M`.
+    pub download_limit: Option<String>,
+    /// Package cache shared between installs.
+    pub cachedir: Option<String>,
+    /// Disable ANSI colours.
+    pub no_color: bool,
+    /// Replace box drawing, braille and status glyphs with ASCII.
+    pub ascii: bool,
+    /// One line per action and typed answers, for speakup / espeakup.
+    pub accessible: bool,
+}
+
+/// Parses `std::env::args()`. `--help` prints usage and exits immediately.
+pub fn parse() -> Result<Args, InstallerError> {
+    let mut args = Args::default();
+    let mut it = std::env::args().skip(1);
+
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--config" => {
+                let path = it.next().ok_or_else(|| usage("--config requires a file path"))?;
+                args.config = Some(path);
+            }
+            "--config-sha256" => {
+                let hex = it.next().ok_or_else(|| usage("--config-sha256 requires a checksum"))?;
+                args.config_sha256 = Some(hex);
+            }
+            "--config-key" => {
+                let key = it.next().ok_or_else(|| usage("--config-key requires a key file"))?;
+                args.config_key = Some(key);
+            }
+            "--dry-run" => args.dry_run = true,
+            "--plan" => {
+                args.plan = true;
+                args.dry_run = true;
+            }
+            "--apply" => args.apply = true,
+            "--export-plan" => {
+                let path = it.next().ok_or_else(|| usage("--export-plan requires a file path"))?;
+                args.export_plan = Some(path);
+            }
+            "--remote" => args.remote = true,
+            "--packages-file" => {
+                let path =
+                    it.next().ok_or_else(|| usage("--packages-file requires a file path"))?;
+                args.packages_file = Some(path);
+            }
+            "--download-limit" => {
+                let rate = it.next().ok_or_else(|| usage("--download-limit requires a rate"))?;
+                if !is_rate(&rate) {
+                    return Err(usage("--download-limit takes bytes/s, like 500K or 2M"));
+                }
+                args.download_limit = Some(rate);
+            }
+            "--cachedir" => {
+                let dir = it.next().ok_or_else(|| usage("--cachedir requires a directory"))?;
+                args.cachedir = Some(dir);
+            }
+            "--no-color" => args.no_color = true,
+            "--ascii" => args.ascii = true,
+            "--accessible" => args.accessible = true,
+            "profile" => match it.next().as_deref() {
+                Some("save") => {
+                    let (Some(name), Some(file)) = (it.next(), it.next()) else {
+                        return Err(usage("profile save requires a name and an answer file"));
+                    };
+                    args.profile = Some(ProfileCommand::Save { name, file });
+                }
+                Some("load") => {
+                    let name = it.next().ok_or_else(|| usage("profile load requires a name"))?;
+                    args.load_profile = Some(name);
+                }
+                Some("list") => args.profile = Some(ProfileCommand::List),
+                _ => return Err(usage("profile takes save, load or list")),
+            },
+            "clone" => {
+                let file = it.next().ok_or_else(|| usage("clone requires an output file"))?;
+                args.clone_to = Some(file);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => return Err(usage(&format!("Unknown option '{}'", other))),
+        }
+    }
+
+    let verify = args.config_sha256.is_some() || args.config_key.is_some();
+    if verify && args.config.is_none() && args.load_profile.is_none() {
+        return Err(usage("--config-sha256 and --config-key need --config"));
+    }
+    if args.load_profile.is_some() && args.config.is_some() {
+        return Err(usage("profile load and --config both name an answer file"));
+    }
+    if args.apply && args.dry_run {
+        return Err(usage("--apply runs the saved plan for real; it can't be a dry run"));
+    }
+    if args.export_plan.is_some() && !args.dry_run {
+        return Err(usage("--export-plan only works together with --dry-run or --plan"));
+    }
+
+    Ok(args)
+}
+
+/// A non-zero number of bytes, optionally with a `K`, `M` or `G` suffix.
+/// curl reads any zero (`00`, `0K`) as "no limit".
+fn is_rate(rate: &str) -> bool {
+    let digits = rate.strip_suffix(['K', 'k', 'M', 'm', 'G', 'g']).unwrap_or(rate);
+    !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && digits.bytes().any(|b| b != b'0')
+}
+
+fn usage(msg: &str) -> InstallerError {
+    InstallerError::Usage(format!("{}\n\n{}", msg, USAGE))
+}
@@ -0,0 +1,214 @@
+use std::{fs, path::Path};
+
+use crate::{
+    answers::{list, quote},
+    cmd,
+    error::InstallerError,
+    files,
+    steps::{packages, users},
+    ui,
+};
+
+/// Packages the installer puts in on its own; left out of `packages.extra`.
+const INSTALLER_PACKAGES: &[&str] = &[
+    "base", "base-devel", "openrc", "elogind-openrc", "seatd", "seatd-openrc", "linux", "linux-lts",
+    "linux-zen", "linux-aarch64", "linux-headers", "linux-lts-headers", "linux-zen-headers",
+    "linux-aarch64-headers", "linux-firmware", "intel-ucode", "amd-ucode", "grub", "efibootmgr",
+    "openssh", "openssh-openrc", "networkmanager", "networkmanager-openrc", "dhcpcd",
+    "dhcpcd-openrc", "nftables", "nftables-openrc", "ufw", "ufw-openrc", "ntp", "ntp-openrc",
+    "chrony", "chrony-openrc", "openntpd", "openntpd-openrc", "terminus-font", "zsh", "fish",
+    "grml-zsh-config",
+];
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// `clone <FILE>`: reads the running Artix system — explicit packages,
+/// fstab layout, enabled services, locale, user — and writes an answer file
+/// that rebuilds it. Disk paths and passwords differ per machine, so they
+/// are written as comments to fill in. `-` prints to stdout.
+pub fn run(out: &str) -> Result<(), InstallerError> {
+    let services = services();
+    let enabled = |name: &str| services.iter().any(|s| s == name);
+    let fstab = fs::read_to_string("/etc/fstab").unwrap_or_default();
+    let mounts: Vec<Vec<&str>> = fstab
+        .lines()
+        .filter(|l| !l.trim_start().starts_with('#'))
+        .map(|l| l.split_whitespace().collect::<Vec<_>>())
+        .filter(|f| f.len() >= 3)
+        .collect();
+    let mount_of = |dir: &str| mounts.iter().find(|f| f[1] == dir);
+    let hostname = read("/etc/hostname").unwrap_or_else(|| "artix".to_string());
+
+    let mut t = format!("# Cloned from {} with artix-installer clone\n\n", hostname);
+    t.push_str("# Set the target machine's disk before use:\n");
+    t.push_str("# disk = \"/dev/sda\"\n");
+    t.push_str("# efi  = \"/dev/sda1\"\n");
+    let swap = mounts.iter().any(|f| f[2] == "swap");
+    t.push_str(if swap { "# swap = \"/dev/sda2\"\n" } else { "swap = \"none\"\n" });
+    t.push_str("# root = \"/dev/sda3\"\n");
+    if mount_of("/var").is_some() {
+        t.push_str("# var  = \"/dev/sda4\"   # the source has a separate /var\n");
+    }
+    t.push_str("# format = true   # destructive: opt in once the disk above is right\n");
+    if let Some(root) = mount_of("/") {
+        t.push_str(&format!("root_fs = {}\n", quote(root[2])));
+    }
+    let tmpfs_tmp = mount_of("/tmp").is_some_and(|f| f[2] == "tmpfs");
+    t.push_str(&format!("tmpfs_tmp = {}\n", tmpfs_tmp));
+
+    let installed = cmd::run_capture("pacman", &["-Qq"]).unwrap_or_default();
+    let has = |pkg: &str| installed.lines().any(|l| l == pkg);
+    let kernels: Vec<&str> = [("linux", "stable"), ("linux-lts", "lts"), ("linux-zen", "zen")]
+        .into_iter()
+        .filter(|(pkg, _)| has(pkg))
+        .map(|(_, id)| id)
+        .collect();
+    if !kernels.is_empty() {
+        t.push_str(&format!("kernel = {}\n", list(&kernels)));
+    }
+    // The session's main package is the first of its set.
+    let session = packages::SESSIONS.iter().find(|s| has(s.packages[0]));
+    if enabled("seatd") {
+        t.push_str("seat = \"seatd\"\n");
+    }
+    t.push_str(&format!("desktop = {}\n", session.is_some()));
+    if let Some(session) = session {
+        t.push_str(&format!("session = {}\n", quote(session.id)));
+    }
+    t.push_str(&format!("hostname = {}\n", quote(&hostname)));
+    if let Some(font) = conf_value("/etc/vconsole.conf", "FONT=") {
+        t.push_str(&format!("console_font = {}\n", quote(&font)));
+    }
+    let ntp = [("ntpd", "ntpd"), ("chronyd", "chrony"), ("openntpd", "openntpd")]
+        .into_iter()
+        .find(|(service, _)| enabled(service));
+    if let Some((_, daemon)) = ntp {
+        t.push_str(&format!("ntp_daemon = {}\n", quote(daemon)));
+    }
+    let firewall = ["nftables", "ufw"].into_iter().find(|s| enabled(s)).unwrap_or("none");
+    t.push_str(&format!("firewall = {}\n", quote(firewall)));
+    t.push_str("# root_password = \"…\"\n");
+
+    t.push_str("\n[locale]\nbundle = \"us\"\n");
+    let lang = conf_value("/etc/locale.conf", "LANG=");
+    let timezone = fs::read_link("/etc/localtime").ok().and_then(|p| {
+        p.to_string_lossy().split_once("zoneinfo/").map(|(_, tz)| tz.to_string())
+    });
+    let keymap = conf_value("/etc/vconsole.conf", "KEYMAP=");
+    let xkb = fs::read_to_string("/etc/X11/xorg.conf.d/00-keyboard.conf").ok().and_then(|c| {
+        let line = c.lines().find(|l| l.contains("\"XkbLayout\""))?;
+        line.rsplit('"').nth(1).map(str::to_string)
+    });
+    let settings = [("lang", lang), ("timezone", timezone), ("keymap", keymap), ("xkb", xkb)];
+    for (key, value) in settings {
+        if let Some(value) = value {
+            t.push_str(&format!("{} = {}\n", key, quote(&value)));
+        }
+    }
+
+    let backend = if enabled("NetworkManager") {
+        "networkmanager"
+    } else if enabled("dhcpcd") {
+        "dhcpcd"
+    } else if services.iter().any(|s| s.starts_with("net.") && s != "net.lo") {
+        "netifrc"
+    } else {
+        "none"
+    };
+    t.push_str(&format!("\n[network]\nbackend = {}\n", quote(backend)));
+    t.push_str(&format!("\n[ssh]\nenable = {}\n", enabled("sshd")));
+
+    if let Some((name, shell)) = first_user() {
+        t.push_str(&format!("\n[user]\nname = {}\n", quote(&name)));
+        t.push_str("# password = \"…\"\n");
+        let groups = groups_of(&name);
+        let groups: Vec<&str> = groups.iter().map(String::as_str).collect();
+        if !groups.is_empty() {
+            t.push_str(&format!("groups = {}\n", list(&groups)));
+        }
+        if users::SHELLS.iter().any(|(s, ..)| *s == shell) {
+            t.push_str(&format!("shell = {}\n", quote(&shell)));
+        }
+    }
+
+    let explicit = cmd::run_capture("pacman", &["-Qqe"]).unwrap_or_default();
+    let foreign = cmd::run_capture("pacman", &["-Qqm"]).unwrap_or_default();
+    let is_foreign = |p: &str| foreign.lines().any(|l| l == p);
+    let extra: Vec<&str> = explicit
+        .lines()
+        .filter(|p| !INSTALLER_PACKAGES.contains(p) && !is_foreign(p))
+        .filter(|p| !packages::desktop_packages().any(|d| d == *p))
+        .collect();
+    t.push_str(&format!("\n[packages]\nextra = {}\n", list(&extra)));
+    let foreign: Vec<&str> = foreign.lines().collect();
+    if !foreign.is_empty() {
+        t.push_str(&format!("# not in the repositories (AUR?): {}\n", foreign.join(" ")));
+    }
+
+    if out == "-" {
+        print!("{}", t);
+        return Ok(());
+    }
+    files::write_atomic(out, &t)?;
+    ui::print_success(&format!(
+        "Answer file written to {} — {} extra package(s), {} service(s) read.",
+        out,
+        extra.len(),
+        services.len()
+    ));
+    ui::print_info("Fill in the disk and passwords, then run: artix-installer --config <file>");
+    Ok(())
+}
+
+// ── Reading the system ────────────────────────────────────────────────────────
+
+/// Services in the `default` and `boot` runlevels.
+fn services() -> Vec<String> {
+    ["/etc/runlevels/default", "/etc/runlevels/boot"]
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|dir| dir.flatten())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .collect()
+}
+
+/// The first regular account (UID 1000 and up) and its shell's name.
+fn first_user() -> Option<(String, String)> {
+    let passwd = fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|l| {
+        let f: Vec<&str> = l.split(':').collect();
+        let uid: u32 = f.get(2)?.parse().ok()?;
+        (f.len() >= 7 && (1000..60000).contains(&uid)).then(|| {
+            let shell = Path::new(f[6]).file_name().unwrap_or_default();
+            (f[0].to_string(), shell.to_string_lossy().into_owned())
+        })
+    })
+}
+
+/// `user`'s supplementary groups among the ones the installer offers.
+fn groups_of(user: &str) -> Vec<String> {
+    let group = fs::read_to_string("/etc/group").unwrap_or_default();
+    group
+        .lines()
+        .filter_map(|l| {
+            let f: Vec<&str> = l.split(':').collect();
+            let members = f.get(3)?;
+            members.split(',').any(|m| m == user).then(|| f[0].to_string())
+        })
+        .filter(|g| users::GROUPS.iter().any(|(known, _)| known == g))
+        .collect()
+}
+
+fn read(path: &str) -> Option<String> {
+    let s = fs::read_to_string(path).ok()?;
+    let s = s.trim();
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+/// `KEY=value` from a shell-style file, quotes removed.
+fn conf_value(path: &str, prefix: &str) -> Option<String> {
+    let conf = fs::read_to_string(path).ok()?;
+    let value = conf.lines().find_map(|l| l.trim().strip_prefix(prefix))?;
+    let value = value.trim().trim_matches('"');
+    (!value.is_empty()).then(|| value.to_string())
+}
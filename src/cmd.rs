@@ -1,15 +1,305 @@
 use std::{
-    fs::OpenOptions,
-    io,
-    process::{Command, Stdio},
+    cell::Cell,
+    fs,
+    io::{self, Read, Write},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 
-use dialoguer::Confirm;
+use indicatif::ProgressBar;
 
-use crate::{error::InstallerError, ui};
+use crate::{answers, error::InstallerError, interrupt, log, mirrors, plan, prompt, ui};
+
+/// Lines of live output shown under a running spinner.
+const TAIL_LINES: usize = 4;
+
+// ── Deadlines ─────────────────────────────────────────────────────────────────
+
+/// Point in time after which running commands are killed, plus a description
+/// of which limit it is (used in the resulting `Timeout` error).
+static DEADLINE: Mutex<Option<(Instant, String)>> = Mutex::new(None);
+
+/// Sets (or clears, with `None`) the deadline for all subsequent commands.
+pub fn set_deadline(deadline: Option<(Instant, String)>) {
+    *DEADLINE.lock().unwrap() = deadline;
+}
+
+/// `true` once the global deadline has passed — retrying is pointless then.
+fn deadline_passed() -> bool {
+    time_left().is_some_and(|left| left.is_zero())
+}
+
+/// Time until the current deadline, or `None` when there is none.
+pub fn time_left() -> Option<Duration> {
+    DEADLINE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|(at, _)| at.saturating_duration_since(Instant::now()))
+}
+
+/// Waits for `child`, killing it if the global deadline or the per-call
+/// `limit` passes first. `tick` is called every poll interval while waiting.
+fn wait_child(
+    program: &str,
+    child: &mut Child,
+    limit: Option<Duration>,
+    tick: Option<&dyn Fn()>,
+) -> Result<ExitStatus, InstallerError> {
+    let global = DEADLINE.lock().unwrap().clone();
+    let local = limit.map(|d| (Instant::now() + d, format!("{}s command timeout", d.as_secs())));
+    let deadline = match (global, local) {
+        (Some(g), Some(l)) => Some(if g.0 < l.0 { g } else { l }),
+        (g, l) => g.or(l),
+    };
+    if deadline.is_none() && tick.is_none() {
+        return Ok(child.wait()?);
+    }
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if interrupt::interrupted() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(InstallerError::Interrupted);
+        }
+        if let Some((at, ref limit)) = deadline {
+            if Instant::now() >= at {
+                let _ = child.kill();
+                let _ = child.wait();
+                let msg = format!("'{}' killed — {} exceeded", program, limit);
+                log::line(&format!("! {}", msg));
+                return Err(InstallerError::Timeout(msg));
+            }
+        }
+        if let Some(tick) = tick {
+            tick();
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Output collected so far from the child's pipes.
+type Buffer = Arc<Mutex<Vec<u8>>>;
+
+/// Called with the output so far while a child runs; see [`wait_output`].
+type Watch<'a> = &'a dyn Fn(&[u8]);
+
+/// Reads `pipe` to EOF on a background thread, appending to its own buffer
+/// and to `combined` (stdout + stderr interleaved, for the live tail), so
+/// partial output is available even if the child never exits.
+fn drain(
+    pipe: Option<impl Read + Send + 'static>,
+    combined: &Buffer,
+) -> (Buffer, Option<thread::JoinHandle<()>>) {
+    let buf = Buffer::default();
+    let handle = pipe.map(|mut p| {
+        let buf = Arc::clone(&buf);
+        let combined = Arc::clone(combined);
+        thread::spawn(move || {
+            let mut chunk = [0u8; 4096];
+            while let Ok(n) = p.read(&mut chunk) {
+                if n == 0 {
+                    break;
+                }
+                buf.lock().unwrap().extend_from_slice(&chunk[..n]);
+                combined.lock().unwrap().extend_from_slice(&chunk[..n]);
+            }
+        })
+    });
+    (buf, handle)
+}
+
+/// Like `Child::wait_with_output`, but honours the deadlines.
+/// With `watch`, the output so far (stdout + stderr interleaved) is handed
+/// to it every poll while the child runs — to show a live tail or progress.
+/// On timeout, whatever the child printed so far is shown before returning.
+fn wait_output(
+    program: &str,
+    mut child: Child,
+    limit: Option<Duration>,
+    watch: Option<Watch>,
+) -> Result<Output, InstallerError> {
+    let combined = Buffer::default();
+    let (stdout, out_handle) = drain(child.stdout.take(), &combined);
+    let (stderr, err_handle) = drain(child.stderr.take(), &combined);
+
+    let tick = watch.map(|watch| move || watch(&combined.lock().unwrap()));
+    let tick = tick.as_ref().map(|t| t as &dyn Fn());
+
+    let status = match wait_child(program, &mut child, limit, tick) {
+        Ok(status) => status,
+        Err(e) => {
+            // Don't join: a grandchild may still hold the pipes open.
+            print_captured_output(&stdout.lock().unwrap(), &stderr.lock().unwrap());
+            return Err(e);
+        }
+    };
+    for h in [out_handle, err_handle].into_iter().flatten() {
+        let _ = h.join();
+    }
+
+    let take = |b: Buffer| std::mem::take(&mut *b.lock().unwrap());
+    Ok(Output {
+        status,
+        stdout: take(stdout),
+        stderr: take(stderr),
+    })
+}
+
+/// Last `n` non-empty lines of `buf`, reduced to what a terminal would show:
+/// only the text after the final `\r` of each line, without control bytes.
+fn tail_lines(buf: &[u8], n: usize) -> Vec<String> {
+    let window = &buf[buf.len().saturating_sub(8192)..];
+    let text = String::from_utf8_lossy(window);
+
+    let mut lines: Vec<String> = text
+        .split('\n')
+        .map(|l| {
+            l.rsplit('\r')
+                .find(|seg| !seg.trim().is_empty())
+                .unwrap_or("")
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect::<String>()
+        })
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+
+    let skip = lines.len().saturating_sub(n);
+    lines.drain(..skip);
+    lines
+}
+
+// ── Package cache ─────────────────────────────────────────────────────────────
+
+/// Where basestrap and pacman in the chroot download packages to.
+pub const PACMAN_CACHE: &str = "/mnt/var/cache/pacman/pkg";
+
+/// `--cachedir`: a package cache shared between installs.
+static CACHE_DIR: OnceLock<String> = OnceLock::new();
+
+/// Makes every basestrap run use `dir` as its package cache.
+pub fn set_cache_dir(dir: &str) {
+    let _ = CACHE_DIR.set(dir.to_string());
+}
+
+/// The shared package cache, if `--cachedir` named one.
+pub fn cache_dir() -> Option<&'static str> {
+    CACHE_DIR.get().map(String::as_str)
+}
+
+/// Where `program` downloads packages to: the shared cache for basestrap,
+/// the target's own cache for pacman in the chroot.
+fn download_dir(program: &str) -> &'static str {
+    match cache_dir() {
+        Some(dir) if program == "basestrap" => dir,
+        _ => PACMAN_CACHE,
+    }
+}
+
+// ── Transaction progress ──────────────────────────────────────────────────────
+
+/// Progress of a running pacman transaction, read from its output. pacman
+/// without a terminal prints `Packages (N) …` up front, one `… downloading…`
+/// line per package, then `( i/N) installing <pkg>` for each step; the
+/// download speed comes from the package cache growing.
+struct Transaction {
+    cache: &'static str,
+    packages: Cell<u64>,
+    /// Cache size at the last sample, when it was taken, and the speed then.
+    sample: Cell<(u64, Instant, f64)>,
+}
+
+impl Transaction {
+    fn new(cache: &'static str) -> Self {
+        Transaction {
+            cache,
+            packages: Cell::new(0),
+            sample: Cell::new((cache_bytes(cache), Instant::now(), 0.0)),
+        }
+    }
+
+    fn update(&self, pb: &ProgressBar, buf: &[u8]) {
+        let text = String::from_utf8_lossy(buf);
+        if self.packages.get() == 0 {
+            let count = text.lines().find_map(|l| {
+                let rest = l.trim().strip_prefix("Packages (")?;
+                rest.split(')').next()?.parse::<u64>().ok()
+            });
+            self.packages.set(count.unwrap_or(0));
+        }
+
+        let lines = tail_lines(buf, 8);
+        // `( 12/145) installing glibc` — checks, installs and hooks alike.
+        let step = lines.iter().rev().find_map(|l| {
+            let (count, what) = l.trim().strip_prefix('(')?.split_once(')')?;
+            let (done, total) = count.split_once('/')?;
+            Some((done.trim().parse::<u64>().ok()?, total.trim().parse::<u64>().ok()?, what))
+        });
+        if let Some((done, total, what)) = step {
+            pb.set_length(total);
+            pb.set_position(done);
+            pb.set_message(ui::text(what.trim()).into_owned());
+            return;
+        }
+
+        let downloading = lines.iter().rev().find(|l| l.contains("downloading"));
+        if let Some(line) = downloading {
+            let done = text.matches("downloading").count() as u64;
+            pb.set_length(self.packages.get().max(done));
+            pb.set_position(done.saturating_sub(1));
+            let package = line.split_whitespace().find(|w| *w != "downloading...");
+            let speed = self.speed();
+            pb.set_message(format!(
+                "downloading {}{}",
+                package.unwrap_or_default(),
+                speed.map(|s| format!("  ·  {}", s)).unwrap_or_default()
+            ));
+        } else if let Some(line) = lines.last() {
+            pb.set_message(ui::text(line.trim()).into_owned());
+        }
+    }
+
+    /// `4.2 MiB/s`, re-sampled once a second.
+    fn speed(&self) -> Option<String> {
+        let (bytes, at, mut rate) = self.sample.get();
+        let elapsed = at.elapsed().as_secs_f64();
+        if elapsed >= 1.0 {
+            let now = cache_bytes(self.cache);
+            rate = now.saturating_sub(bytes) as f64 / elapsed;
+            self.sample.set((now, Instant::now(), rate));
+        }
+        (rate > 0.0).then(|| format!("{:.1} MiB/s", rate / (1024.0 * 1024.0)))
+    }
+}
+
+/// Total size of the package cache, partial downloads included.
+fn cache_bytes(cache: &str) -> u64 {
+    fs::read_dir(cache)
+        .map(|dir| dir.flatten().filter_map(|e| e.metadata().ok()).map(|m| m.len()).sum())
+        .unwrap_or(0)
+}
 
 // ── Internal helpers ──────────────────────────────────────────────────────────
 
+fn failed(program: &str, status: ExitStatus) -> InstallerError {
+    let code = status.code().unwrap_or(-1);
+    log::line(&format!("! {} exited with code {}", program, code));
+    // A child that died from the user's Ctrl-C isn't a command failure.
+    if interrupt::interrupted() {
+        return InstallerError::Interrupted;
+    }
+    InstallerError::CommandFailed(program.to_string(), code)
+}
+
 fn not_found_or_io(program: &str, err: io::Error) -> InstallerError {
     if err.kind() == io::ErrorKind::NotFound {
         InstallerError::CommandNotFound(program.to_string())
@@ -18,6 +308,26 @@ fn not_found_or_io(program: &str, err: io::Error) -> InstallerError {
     }
 }
 
+/// Records a command line (and later its outcome) in the install log.
+fn log_command(program: &str, args: &[&str]) {
+    log::line(&format!("$ {}", command_line(program, args)));
+}
+
+/// `program arg…`, as the plan records a command.
+fn command_line(program: &str, args: &[&str]) -> String {
+    format!("{} {}", program, args.join(" "))
+}
+
+/// Appends a finished command's full output to the install log.
+fn log_output(output: &Output) {
+    for stream in [&output.stdout, &output.stderr] {
+        let text = String::from_utf8_lossy(stream);
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            log::line(&format!("  {}", line));
+        }
+    }
+}
+
 fn print_captured_output(stdout: &[u8], stderr: &[u8]) {
     let out = String::from_utf8_lossy(stdout);
     let err = String::from_utf8_lossy(stderr);
@@ -38,11 +348,15 @@ fn package_for(program: &str) -> &str {
         }
         "mkfs.btrfs" | "btrfs" => "btrfs-progs",
         "mkfs.xfs" | "xfs_repair" => "xfsprogs",
+        "mkfs.bcachefs" | "bcachefs" => "bcachefs-tools",
+        "zpool" | "zfs" | "zgenhostid" => "zfs-utils",
         "mkswap" | "swapon" | "swapoff" | "mount" | "umount" | "cfdisk" | "fdisk"
         | "lsblk" | "blkid" | "findmnt" => "util-linux",
         "basestrap" | "fstabgen" | "artix-chroot" => "artools",
         "rc-service" | "rc-update" | "openrc" => "openrc",
         "ntpd" | "ntpdate" | "ntpq" => "ntp",
+        "chronyd" | "chronyc" => "chrony",
+        "smartctl" => "smartmontools",
         other => other,
     }
 }
@@ -56,25 +370,24 @@ fn offer_install(program: &str) -> Result<(), InstallerError> {
     ui::print_warning(&format!("Command '{}' not found.", program));
     println!();
 
-    if !Confirm::new()
-        .with_prompt(&format!("Install '{}' with pacman?", pkg))
-        .default(true)
-        .interact()?
-    {
+    if !prompt::confirm(
+        "install_missing_tools",
+        &format!("Install '{}' with pacman?", pkg),
+        true,
+    )? {
         return Err(InstallerError::CommandNotFound(program.to_string()));
     }
 
     println!();
-    let status = Command::new("pacman")
+    log_command("pacman", &["-Sy", "--noconfirm", pkg]);
+    let mut child = Command::new("pacman")
         .args(["-Sy", "--noconfirm", pkg])
-        .status()
+        .spawn()
         .map_err(|e| not_found_or_io("pacman", e))?;
+    let status = wait_child("pacman", &mut child, None, None)?;
 
     if !status.success() {
-        return Err(InstallerError::CommandFailed(
-            "pacman".to_string(),
-            status.code().unwrap_or(-1),
-        ));
+        return Err(failed("pacman", status));
     }
 
     // Verify the binary is actually resolvable in PATH before returning.
@@ -99,145 +412,432 @@ fn offer_install(program: &str) -> Result<(), InstallerError> {
     Ok(())
 }
 
-// ── Public API ────────────────────────────────────────────────────────────────
+// ── Runners ───────────────────────────────────────────────────────────────────
 
-/// Runs a command silently, discarding all output and ignoring any error.
-/// Use for cleanup operations where partial failure is acceptable (e.g. umount).
-pub fn run_best_effort(program: &str, args: &[&str]) {
-    let _ = Command::new(program)
-        .args(args)
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status();
+/// Executes the commands behind [`Cmd`]. The runner a step is given decides
+/// how its commands are carried out without touching the step:
+/// `SystemRunner` runs them, `DryRunner` prints them, `ApplyRunner` runs
+/// them checked off against a saved plan and, in tests,
+/// `RecordingRunner` captures the exact sequence a step produces.
+pub trait CommandRunner {
+    fn best_effort(&self, program: &str, args: &[&str]);
+
+    fn interactive(&self, program: &str, args: &[&str]) -> Result<(), InstallerError>;
+
+    /// Runs silently behind a spinner, killed after `limit` if given.
+    fn spinner(
+        &self,
+        program: &str,
+        args: &[&str],
+        spin_msg: &str,
+        done_msg: &str,
+        limit: Option<Duration>,
+    ) -> Result<(), InstallerError>;
+
+    /// Runs a pacman transaction (`basestrap`, `pacman -S`) behind a
+    /// progress bar built from its output.
+    fn transaction(&self, program: &str, args: &[&str]) -> Result<(), InstallerError>;
+
+    fn capture(&self, program: &str, args: &[&str]) -> Result<String, InstallerError>;
+
+    /// Like `capture`, but a non-zero exit still returns stdout — for tools
+    /// that report findings through their exit status, like smartctl.
+    fn capture_any(&self, program: &str, args: &[&str]) -> Result<String, InstallerError>;
+
+    /// Like `capture`, with `input` written to the child's stdin. The input
+    /// is never logged (it may be a password).
+    fn feed(&self, program: &str, args: &[&str], input: &str) -> Result<String, InstallerError>;
 }
 
-/// Run a command that **takes over the terminal** (stdin/stdout/stderr inherited).
-/// Use for interactive programs: `cfdisk`, `basestrap`, `artix-chroot`.
-pub fn run_interactive(program: &str, args: &[&str]) -> Result<(), InstallerError> {
-    let attempt = |p: &str, a: &[&str]| -> Result<(), InstallerError> {
-        let status = Command::new(p)
-            .args(a)
-            .status()
-            .map_err(|e| not_found_or_io(p, e))?;
-
-        if !status.success() {
-            return Err(InstallerError::CommandFailed(
-                p.to_string(),
-                status.code().unwrap_or(-1),
-            ));
-        }
-        Ok(())
-    };
+// ── Dry run ───────────────────────────────────────────────────────────────────
+//
+// With `--dry-run` every command that would change the system is printed
+// instead of executed. Read-only queries (`run_capture`: lsblk, findmnt,
+// pacman -Q…) still run, so the flow sees real data where it can.
 
-    match attempt(program, args) {
-        Err(InstallerError::CommandNotFound(_)) => {
-            offer_install(program)?;
-            attempt(program, args)
-        }
-        other => other,
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Switches [`Cmd::system`] to (or back from) the `DryRunner`.
+pub fn set_dry_run(on: bool) {
+    DRY_RUN.store(on, Ordering::SeqCst);
+}
+
+/// `true` in a dry run — for code that writes files directly.
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::SeqCst)
+}
+
+/// Prints commands instead of running them.
+pub struct DryRunner;
+
+impl DryRunner {
+    fn print(&self, program: &str, args: &[&str]) {
+        let line = command_line(program, args);
+        println!(
+            "  {}  {}",
+            console::style("[dry-run]").yellow().bold(),
+            console::style(&line).dim()
+        );
+        log::line(&format!("[dry-run] $ {}", line));
+        plan::command(&line);
     }
 }
 
-/// Run a command **silently** while displaying a spinner.
-/// On success prints `done_msg` with a ✓.
-/// On failure prints captured output and returns an error.
-pub fn run_with_spinner(
-    program: &str,
-    args: &[&str],
-    spin_msg: &str,
-    done_msg: &str,
-) -> Result<(), InstallerError> {
-    let attempt = |p: &str, a: &[&str]| -> Result<(), InstallerError> {
+impl CommandRunner for DryRunner {
+    fn best_effort(&self, program: &str, args: &[&str]) {
+        self.print(program, args);
+    }
+
+    fn interactive(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        self.print(program, args);
+        Ok(())
+    }
+
+    fn spinner(
+        &self,
+        program: &str,
+        args: &[&str],
+        spin_msg: &str,
+        done_msg: &str,
+        _limit: Option<Duration>,
+    ) -> Result<(), InstallerError> {
         let pb = ui::spinner(spin_msg);
-        let result = Command::new(p)
-            .args(a)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .map_err(|e| not_found_or_io(p, e));
+        thread::sleep(Duration::from_millis(300));
         pb.finish_and_clear();
+        self.print(program, args);
+        ui::print_success(done_msg);
+        Ok(())
+    }
+
+    fn transaction(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        self.print(program, args);
+        Ok(())
+    }
+
+    /// A query that can't work on a dev machine (no /mnt, not root) just
+    /// comes back empty instead of failing the rehearsal.
+    fn capture(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        Ok(capture_once(program, args).unwrap_or_default())
+    }
+
+    fn capture_any(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        Ok(capture_any_once(program, args).unwrap_or_default())
+    }
 
-        match result {
-            Err(e) => Err(e),
-            Ok(output) if !output.status.success() => {
-                print_captured_output(&output.stdout, &output.stderr);
-                Err(InstallerError::CommandFailed(
-                    p.to_string(),
-                    output.status.code().unwrap_or(-1),
-                ))
+    fn feed(&self, program: &str, args: &[&str], _input: &str) -> Result<String, InstallerError> {
+        self.print(program, args);
+        Ok(String::new())
+    }
+}
+
+// ── System runner ─────────────────────────────────────────────────────────────
+
+/// Runs commands for real. A missing program is offered for installation
+/// once, then retried.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn best_effort(&self, program: &str, args: &[&str]) {
+        log_command(program, args);
+        let _ = Command::new(program)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+
+    fn interactive(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        let attempt = |p: &str, a: &[&str]| -> Result<(), InstallerError> {
+            log_command(p, a);
+            let mut child = Command::new(p)
+                .args(a)
+                .spawn()
+                .map_err(|e| not_found_or_io(p, e))?;
+            let status = wait_child(p, &mut child, None, None)?;
+            // The child owned the terminal, so any Ctrl-C was meant for it.
+            interrupt::reset();
+
+            if !status.success() {
+                return Err(failed(p, status));
             }
-            Ok(_) => {
-                ui::print_success(done_msg);
-                Ok(())
+            Ok(())
+        };
+        with_install_retry(program, args, attempt)
+    }
+
+    fn spinner(
+        &self,
+        program: &str,
+        args: &[&str],
+        spin_msg: &str,
+        done_msg: &str,
+        limit: Option<Duration>,
+    ) -> Result<(), InstallerError> {
+        let attempt = |p: &str, a: &[&str]| -> Result<(), InstallerError> {
+            log_command(p, a);
+            let pb = ui::spinner(spin_msg);
+            let result = Command::new(p)
+                .args(a)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| not_found_or_io(p, e))
+                .and_then(|child| {
+                    let tail = |buf: &[u8]| {
+                        ui::spinner_tail(&pb, spin_msg, &tail_lines(buf, TAIL_LINES));
+                    };
+                    wait_output(p, child, limit, Some(&tail))
+                });
+            pb.finish_and_clear();
+
+            if let Ok(ref output) = result {
+                log_output(output);
             }
-        }
-    };
 
-    match attempt(program, args) {
-        Err(InstallerError::CommandNotFound(_)) => {
-            offer_install(program)?;
-            attempt(program, args)
-        }
-        other => other,
+            match result {
+                Err(e) => Err(e),
+                Ok(output) if !output.status.success() => {
+                    print_captured_output(&output.stdout, &output.stderr);
+                    Err(failed(p, output.status))
+                }
+                Ok(_) => {
+                    ui::print_success(done_msg);
+                    Ok(())
+                }
+            }
+        };
+        with_install_retry(program, args, attempt)
     }
-}
 
-/// Run a command, capture its stdout, and return it as a `String`.
-pub fn run_capture(program: &str, args: &[&str]) -> Result<String, InstallerError> {
-    let attempt = |p: &str, a: &[&str]| -> Result<String, InstallerError> {
-        let output = Command::new(p)
-            .args(a)
-            .stderr(Stdio::inherit())
-            .output()
-            .map_err(|e| not_found_or_io(p, e))?;
+    fn transaction(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        let attempt = |p: &str, a: &[&str]| -> Result<(), InstallerError> {
+            log_command(p, a);
+            let pb = ui::progress_bar("Resolving packages…");
+            let watch = Transaction::new(download_dir(p));
+            let result = Command::new(p)
+                .args(a)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| not_found_or_io(p, e))
+                .and_then(|child| {
+                    let update = |buf: &[u8]| watch.update(&pb, buf);
+                    wait_output(p, child, None, Some(&update))
+                });
+            pb.finish_and_clear();
+
+            let output = result?;
+            log_output(&output);
+            if output.status.success() {
+                return Ok(());
+            }
+            let err = failed(p, output.status);
+            let text = String::from_utf8_lossy(&output.stderr);
+            match mirrors::failed_mirror(&text) {
+                Some(mirror) if !matches!(err, InstallerError::Interrupted) => {
+                    for line in text.lines().filter(|l| l.starts_with("error:")) {
+                        eprintln!("{}", line);
+                    }
+                    Err(InstallerError::Download(p.to_string(), mirror))
+                }
+                _ => {
+                    print_captured_output(&output.stdout, &output.stderr);
+                    Err(err)
+                }
+            }
+        };
+        with_install_retry(program, args, attempt)
+    }
+
+    fn capture(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        with_install_retry(program, args, capture_once)
+    }
+
+    fn capture_any(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        with_install_retry(program, args, capture_any_once)
+    }
+
+    fn feed(&self, program: &str, args: &[&str], input: &str) -> Result<String, InstallerError> {
+        log_command(program, args);
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| not_found_or_io(program, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())?;
+        }
+        let output = wait_output(program, child, None, None)?;
 
         if !output.status.success() {
-            return Err(InstallerError::CommandFailed(
-                p.to_string(),
-                output.status.code().unwrap_or(-1),
-            ));
+            print_captured_output(&output.stdout, &output.stderr);
+            return Err(failed(program, output.status));
         }
 
         Ok(String::from_utf8_lossy(&output.stdout).into_owned())
-    };
+    }
+}
 
-    match attempt(program, args) {
-        Err(InstallerError::CommandNotFound(_)) => {
-            offer_install(program)?;
-            attempt(program, args)
+// ── Apply runner ──────────────────────────────────────────────────────────────
+
+/// `--apply`: runs commands like the `SystemRunner`, checking each one off
+/// against the saved plan. One an interrupted apply already carried out is
+/// skipped. Queries aren't part of the plan and always run.
+pub struct ApplyRunner;
+
+impl ApplyRunner {
+    fn planned<T: Default>(
+        &self,
+        program: &str,
+        args: &[&str],
+        run: impl FnOnce() -> Result<T, InstallerError>,
+    ) -> Result<T, InstallerError> {
+        let line = command_line(program, args);
+        if plan::already_applied(&line) {
+            return Ok(T::default());
         }
-        other => other,
+        plan::applying_command(&line);
+        let result = run();
+        if result.is_ok() {
+            plan::applied(&line);
+        }
+        result
     }
 }
 
-/// Run a command and **append** its stdout to a file (equivalent to `>> path`).
-pub fn run_append_to_file(
-    program: &str,
-    args: &[&str],
-    file_path: &str,
-) -> Result<(), InstallerError> {
-    let attempt = |p: &str, a: &[&str]| -> Result<(), InstallerError> {
-        let file = OpenOptions::new()
-            .append(true)
-            .create(true)
-            .open(file_path)?;
-
-        let status = Command::new(p)
-            .args(a)
-            .stdout(file)
-            .stderr(Stdio::piped())
-            .status()
-            .map_err(|e| not_found_or_io(p, e))?;
+impl CommandRunner for ApplyRunner {
+    fn best_effort(&self, program: &str, args: &[&str]) {
+        let _ = self.planned(program, args, || {
+            SystemRunner.best_effort(program, args);
+            Ok(())
+        });
+    }
 
-        if !status.success() {
-            return Err(InstallerError::CommandFailed(
-                p.to_string(),
-                status.code().unwrap_or(-1),
-            ));
+    fn interactive(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        self.planned(program, args, || SystemRunner.interactive(program, args))
+    }
+
+    fn spinner(
+        &self,
+        program: &str,
+        args: &[&str],
+        spin_msg: &str,
+        done_msg: &str,
+        limit: Option<Duration>,
+    ) -> Result<(), InstallerError> {
+        self.planned(program, args, || {
+            SystemRunner.spinner(program, args, spin_msg, done_msg, limit)
+        })
+    }
+
+    fn transaction(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        self.planned(program, args, || SystemRunner.transaction(program, args))
+    }
+
+    fn capture(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        SystemRunner.capture(program, args)
+    }
+
+    fn capture_any(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        SystemRunner.capture_any(program, args)
+    }
+
+    fn feed(&self, program: &str, args: &[&str], input: &str) -> Result<String, InstallerError> {
+        self.planned(program, args, || SystemRunner.feed(program, args, input))
+    }
+}
+
+// ── Recording runner ──────────────────────────────────────────────────────────
+
+/// Test runner: records each command as `program arg…` instead of running
+/// it. Queries answer with the canned reply for their program, else nothing.
+#[cfg(test)]
+pub struct RecordingRunner {
+    calls: std::cell::RefCell<Vec<String>>,
+    replies: Vec<(String, String)>,
+}
+
+#[cfg(test)]
+impl RecordingRunner {
+    pub fn new(replies: &[(&str, &str)]) -> Self {
+        RecordingRunner {
+            calls: Default::default(),
+            replies: replies.iter().map(|(p, r)| (p.to_string(), r.to_string())).collect(),
         }
+    }
+
+    /// The command API bound to this runner, for the step under test.
+    pub fn cmd(&self) -> Cmd<'_> {
+        Cmd(self)
+    }
+
+    /// The commands issued so far.
+    pub fn commands(&self) -> Vec<String> {
+        self.calls.borrow().clone()
+    }
+
+    fn push(&self, program: &str, args: &[&str]) {
+        let call = [program].iter().chain(args).copied().collect::<Vec<_>>().join(" ");
+        self.calls.borrow_mut().push(call);
+    }
+
+    fn reply(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        self.push(program, args);
+        let reply = self.replies.iter().find(|(p, _)| p == program);
+        Ok(reply.map(|(_, r)| r.clone()).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+impl CommandRunner for RecordingRunner {
+    fn best_effort(&self, program: &str, args: &[&str]) {
+        self.push(program, args);
+    }
+
+    fn interactive(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        self.push(program, args);
         Ok(())
-    };
+    }
+
+    fn spinner(
+        &self,
+        program: &str,
+        args: &[&str],
+        _spin_msg: &str,
+        _done_msg: &str,
+        _limit: Option<Duration>,
+    ) -> Result<(), InstallerError> {
+        self.push(program, args);
+        Ok(())
+    }
+
+    fn transaction(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        self.push(program, args);
+        Ok(())
+    }
+
+    fn capture(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        self.reply(program, args)
+    }
+
+    fn capture_any(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        self.reply(program, args)
+    }
 
+    fn feed(&self, program: &str, args: &[&str], _input: &str) -> Result<String, InstallerError> {
+        self.reply(program, args)
+    }
+}
+
+/// Runs `attempt`; if the program is missing, offers to install it and
+/// tries once more.
+fn with_install_retry<T>(
+    program: &str,
+    args: &[&str],
+    attempt: impl Fn(&str, &[&str]) -> Result<T, InstallerError>,
+) -> Result<T, InstallerError> {
     match attempt(program, args) {
         Err(InstallerError::CommandNotFound(_)) => {
             offer_install(program)?;
@@ -246,3 +846,225 @@ pub fn run_append_to_file(
         other => other,
     }
 }
+
+fn capture_once(program: &str, args: &[&str]) -> Result<String, InstallerError> {
+    let output = capture_output(program, args)?;
+
+    if !output.status.success() {
+        return Err(failed(program, output.status));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn capture_any_once(program: &str, args: &[&str]) -> Result<String, InstallerError> {
+    let output = capture_output(program, args)?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn capture_output(program: &str, args: &[&str]) -> Result<Output, InstallerError> {
+    log_command(program, args);
+    let child = Command::new(program)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| not_found_or_io(program, e))?;
+    wait_output(program, child, None, None)
+}
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// The command API bound to one runner. Steps get theirs through
+/// `pipeline::Context`, so a test can hand them a `RecordingRunner`; the
+/// free functions below use [`Cmd::system`].
+#[derive(Clone, Copy)]
+pub struct Cmd<'a>(&'a dyn CommandRunner);
+
+impl Cmd<'static> {
+    /// This process's runner: the `DryRunner` with `--dry-run`, the
+    /// `ApplyRunner` with `--apply`, else the `SystemRunner`.
+    pub fn system() -> Self {
+        if dry_run() {
+            Cmd(&DryRunner)
+        } else if plan::applying() {
+            Cmd(&ApplyRunner)
+        } else {
+            Cmd(&SystemRunner)
+        }
+    }
+}
+
+impl Cmd<'_> {
+    /// Runs a command silently, discarding all output and ignoring any error.
+    /// Use for cleanup operations where partial failure is acceptable (e.g. umount).
+    pub fn run_best_effort(&self, program: &str, args: &[&str]) {
+        self.0.best_effort(program, args);
+    }
+
+    /// Run a command that **takes over the terminal** (stdin/stdout/stderr inherited).
+    /// Use for interactive programs: `cfdisk`, `basestrap`, `artix-chroot`.
+    pub fn run_interactive(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        self.0.interactive(program, args)
+    }
+
+    /// Run a pacman transaction — `basestrap /mnt …` or `artix-chroot /mnt
+    /// pacman -S --noconfirm …` — behind a progress bar: the current package,
+    /// how far the transaction is and the download speed. The full output
+    /// goes to the log; on failure it is printed too.
+    ///
+    /// With `--cachedir`, basestrap gets `-c` (leave the target's cache alone)
+    /// and pacman the shared cache.
+    ///
+    /// A failed download (404, timeout) moves the failing mirror to the end of
+    /// the live mirror list and tries again, up to `MAX_FAILOVERS` times.
+    pub fn run_transaction(&self, program: &str, args: &[&str]) -> Result<(), InstallerError> {
+        let mut with_cache = Vec::new();
+        let args = match cache_dir() {
+            Some(dir) if program == "basestrap" => {
+                with_cache.push("-c");
+                with_cache.extend_from_slice(args);
+                with_cache.extend(["--cachedir", dir]);
+                &with_cache[..]
+            }
+            _ => args,
+        };
+        let mut failovers = 0;
+        loop {
+            match self.0.transaction(program, args) {
+                Err(InstallerError::Download(_, ref mirror))
+                    if program == "basestrap"
+                        && failovers < mirrors::MAX_FAILOVERS
+                        && mirrors::rotate(mirror) =>
+                {
+                    failovers += 1;
+                    log::line(&format!("mirror failover {} for {}", failovers, program));
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// Run a command **silently** while displaying a spinner.
+    /// On success prints `done_msg` with a ✓.
+    /// On failure prints captured output and returns an error.
+    pub fn run_with_spinner(
+        &self,
+        program: &str,
+        args: &[&str],
+        spin_msg: &str,
+        done_msg: &str,
+    ) -> Result<(), InstallerError> {
+        self.0.spinner(program, args, spin_msg, done_msg, None)
+    }
+
+    /// Like `run_with_spinner`, but gives up after `timeout` (e.g. `ntpd -gq`
+    /// without network): the child is killed, its output so far is shown, and
+    /// the user can retry, skip or abort. Returns `Ok(false)` when skipped.
+    pub fn run_with_timeout(
+        &self,
+        program: &str,
+        args: &[&str],
+        spin_msg: &str,
+        done_msg: &str,
+        timeout: Duration,
+    ) -> Result<bool, InstallerError> {
+        let items = [
+            "Retry".to_string(),
+            "Skip this command".to_string(),
+            "Abort installation".to_string(),
+        ];
+
+        loop {
+            match self.0.spinner(program, args, spin_msg, done_msg, Some(timeout)) {
+                Err(InstallerError::Timeout(msg)) if !deadline_passed() => {
+                    ui::print_warning(&msg);
+                    // Unattended runs abort unless the answer file says otherwise.
+                    let default = if answers::unattended() { 2 } else { 0 };
+                    match prompt::select(
+                        "on_timeout",
+                        "The command timed out. What now?",
+                        &items,
+                        &["retry", "skip", "abort"],
+                        default,
+                    )? {
+                        0 => continue,
+                        1 => return Ok(false),
+                        _ => return Err(InstallerError::Timeout(msg)),
+                    }
+                }
+                other => return other.map(|()| true),
+            }
+        }
+    }
+
+    /// Run a command, capture its stdout, and return it as a `String`.
+    pub fn run_capture(&self, program: &str, args: &[&str]) -> Result<String, InstallerError> {
+        self.0.capture(program, args)
+    }
+
+    /// Like [`Self::run_capture`], but returns stdout whatever the exit status.
+    pub fn run_capture_any(
+        &self,
+        program: &str,
+        args: &[&str],
+    ) -> Result<String, InstallerError> {
+        self.0.capture_any(program, args)
+    }
+
+    /// Run a command with `input` on its stdin and return its stdout.
+    pub fn run_feed(
+        &self,
+        program: &str,
+        args: &[&str],
+        input: &str,
+    ) -> Result<String, InstallerError> {
+        self.0.feed(program, args, input)
+    }
+}
+
+// The same API on this process's runner, for code outside the steps that
+// get a `Cmd` through their context: probes, the live environment, reports.
+
+pub fn run_best_effort(program: &str, args: &[&str]) {
+    Cmd::system().run_best_effort(program, args)
+}
+
+pub fn run_interactive(program: &str, args: &[&str]) -> Result<(), InstallerError> {
+    Cmd::system().run_interactive(program, args)
+}
+
+pub fn run_transaction(program: &str, args: &[&str]) -> Result<(), InstallerError> {
+    Cmd::system().run_transaction(program, args)
+}
+
+pub fn run_with_spinner(
+    program: &str,
+    args: &[&str],
+    spin_msg: &str,
+    done_msg: &str,
+) -> Result<(), InstallerError> {
+    Cmd::system().run_with_spinner(program, args, spin_msg, done_msg)
+}
+
+pub fn run_with_timeout(
+    program: &str,
+    args: &[&str],
+    spin_msg: &str,
+    done_msg: &str,
+    timeout: Duration,
+) -> Result<bool, InstallerError> {
+    Cmd::system().run_with_timeout(program, args, spin_msg, done_msg, timeout)
+}
+
+pub fn run_capture(program: &str, args: &[&str]) -> Result<String, InstallerError> {
+    Cmd::system().run_capture(program, args)
+}
+
+pub fn run_capture_any(program: &str, args: &[&str]) -> Result<String, InstallerError> {
+    Cmd::system().run_capture_any(program, args)
+}
+
+pub fn run_feed(program: &str, args: &[&str], input: &str) -> Result<String, InstallerError> {
+    Cmd::system().run_feed(program, args, input)
+}
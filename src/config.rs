@@ -1,9 +1,124 @@
+use crate::lsblk;
+
 /// Holds all user-selected installation parameters collected throughout the process.
 #[derive(Debug, Clone)]
 pub struct Config {
     pub efi_partition: String,
     pub swap_partition: Option<String>,
     pub root_partition: String,
+    /// Filesystem UUIDs of the partitions above, resolved after formatting.
+    /// Unlike device paths they survive disk reordering between boots.
+    pub efi_uuid: Option<String>,
+    pub swap_uuid: Option<String>,
+    pub root_uuid: Option<String>,
+    /// GPT partition UUIDs, kept by the partition table rather than the
+    /// filesystem — still valid when a `mkfs` failed or was redone.
+    pub efi_partuuid: Option<String>,
+    pub swap_partuuid: Option<String>,
+    pub root_partuuid: Option<String>,
+    pub root_fs: RootFs,
+    /// Optional dedicated `/var` partition (ext4).
+    pub var_partition: Option<String>,
+    pub var_uuid: Option<String>,
+    pub var_partuuid: Option<String>,
+    /// An existing /home partition kept by a reinstall — never formatted.
+    pub home_partition: Option<String>,
+    pub home_uuid: Option<String>,
+    pub home_partuuid: Option<String>,
+    /// Mount `/tmp` as tmpfs in the installed system.
+    pub tmpfs_tmp: bool,
+}
+
+impl Config {
+    /// Builds a config from device paths alone (UUIDs not yet known).
+    pub fn new(efi: String, swap: Option<String>, root: String) -> Self {
+        Config {
+            efi_partition: efi,
+            swap_partition: swap,
+            root_partition: root,
+            efi_uuid: None,
+            swap_uuid: None,
+            root_uuid: None,
+            efi_partuuid: None,
+            swap_partuuid: None,
+            root_partuuid: None,
+            root_fs: RootFs::Ext4,
+            var_partition: None,
+            var_uuid: None,
+            var_partuuid: None,
+            home_partition: None,
+            home_uuid: None,
+            home_partuuid: None,
+            tmpfs_tmp: false,
+        }
+    }
+
+    /// Looks up the UUID and PARTUUID of each partition via `blkid`. Call after
+    /// formatting, since `mkfs`/`mkswap` generate fresh UUIDs.
+    pub fn resolve_uuids(&mut self) {
+        self.efi_uuid = lsblk::uuid_of(&self.efi_partition);
+        self.swap_uuid = self.swap_partition.as_deref().and_then(lsblk::uuid_of);
+        self.root_uuid = lsblk::uuid_of(&self.root_partition);
+        self.var_uuid = self.var_partition.as_deref().and_then(lsblk::uuid_of);
+        self.home_uuid = self.home_partition.as_deref().and_then(lsblk::uuid_of);
+        self.efi_partuuid = lsblk::partuuid_of(&self.efi_partition);
+        self.swap_partuuid = self.swap_partition.as_deref().and_then(lsblk::partuuid_of);
+        self.root_partuuid = lsblk::partuuid_of(&self.root_partition);
+        self.var_partuuid = self.var_partition.as_deref().and_then(lsblk::partuuid_of);
+        self.home_partuuid = self.home_partition.as_deref().and_then(lsblk::partuuid_of);
+    }
+}
+
+/// Filesystem for the root partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootFs {
+    Ext4,
+    /// Subvolumes `@` and `@home`; see `steps::btrfs`.
+    Btrfs,
+    /// Experimental: out-of-tree since Linux 6.17, so it needs DKMS.
+    Bcachefs,
+    /// A `zroot` pool with datasets instead of a filesystem; see `steps::zfs`.
+    Zfs,
+}
+
+impl RootFs {
+    /// The `mkfs` program and its options, before the device. `None` for
+    /// ZFS, where a pool is created instead.
+    pub fn mkfs(self) -> Option<(&'static str, &'static [&'static str])> {
+        match self {
+            RootFs::Ext4 => Some(("mkfs.ext4", &[])),
+            RootFs::Btrfs => Some(("mkfs.btrfs", &["-f"])),
+            RootFs::Bcachefs => Some(("mkfs.bcachefs", &["-f"])),
+            RootFs::Zfs => None,
+        }
+    }
+
+    /// Filesystem type as `mount`, fstab and lsblk spell it.
+    pub fn name(self) -> &'static str {
+        match self {
+            RootFs::Ext4 => "ext4",
+            RootFs::Btrfs => "btrfs",
+            RootFs::Bcachefs => "bcachefs",
+            RootFs::Zfs => "zfs",
+        }
+    }
+}
+
+/// CPU architecture the installer runs on, and so installs for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    X86_64,
+    /// Artix ARM: one generic kernel, no x86 microcode.
+    Aarch64,
+}
+
+impl Arch {
+    pub fn current() -> Self {
+        match std::env::consts::ARCH {
+            "aarch64" => Arch::Aarch64,
+            _ => Arch::X86_64,
+        }
+    }
 }
 
 /// Which Linux kernel variant to install.
@@ -15,12 +130,32 @@ pub enum KernelVariant {
 }
 
 impl KernelVariant {
+    /// The variants packaged for this architecture, in menu order. Artix
+    /// ARM builds neither LTS nor zen.
+    pub fn available() -> &'static [KernelVariant] {
+        match Arch::current() {
+            Arch::X86_64 => &[KernelVariant::Stable, KernelVariant::Lts, KernelVariant::Zen],
+            Arch::Aarch64 => &[KernelVariant::Stable],
+        }
+    }
+
     /// The `basestrap` package name for this variant.
     pub fn package_name(self) -> &'static str {
-        match self {
-            KernelVariant::Stable => "linux",
-            KernelVariant::Lts => "linux-lts",
-            KernelVariant::Zen => "linux-zen",
+        match (self, Arch::current()) {
+            (KernelVariant::Stable, Arch::Aarch64) => "linux-aarch64",
+            (KernelVariant::Stable, _) => "linux",
+            (KernelVariant::Lts, _) => "linux-lts",
+            (KernelVariant::Zen, _) => "linux-zen",
+        }
+    }
+
+    /// The matching headers package, needed to build DKMS modules.
+    pub fn headers_package(self) -> &'static str {
+        match (self, Arch::current()) {
+            (KernelVariant::Stable, Arch::Aarch64) => "linux-aarch64-headers",
+            (KernelVariant::Stable, _) => "linux-headers",
+            (KernelVariant::Lts, _) => "linux-lts-headers",
+            (KernelVariant::Zen, _) => "linux-zen-headers",
         }
     }
 
@@ -33,3 +168,65 @@ impl KernelVariant {
         }
     }
 }
+
+/// What hands the GPU and input devices to the graphical session: elogind
+/// (logind API, per-session device ACLs) or the smaller seatd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeatManager {
+    Elogind,
+    Seatd,
+}
+
+impl SeatManager {
+    /// The package and its OpenRC service package.
+    pub fn packages(self) -> [&'static str; 2] {
+        match self {
+            SeatManager::Elogind => ["elogind", "elogind-openrc"],
+            SeatManager::Seatd => ["seatd", "seatd-openrc"],
+        }
+    }
+
+    /// Human-readable label shown to the user.
+    pub fn display_name(self) -> &'static str {
+        match self {
+            SeatManager::Elogind => "elogind (logind API — most desktops expect it)",
+            SeatManager::Seatd => "seatd (minimal — Wayland compositors, window managers)",
+        }
+    }
+}
+
+/// Which NTP implementation syncs the clock, in the live system and in the target.
+#[derive(Debug, Clone, Copy)]
+pub enum TimeDaemon {
+    Ntpd,
+    Chrony,
+    Openntpd,
+}
+
+impl TimeDaemon {
+    /// The package and its OpenRC service package.
+    pub fn packages(self) -> [&'static str; 2] {
+        match self {
+            TimeDaemon::Ntpd => ["ntp", "ntp-openrc"],
+            TimeDaemon::Chrony => ["chrony", "chrony-openrc"],
+            TimeDaemon::Openntpd => ["openntpd", "openntpd-openrc"],
+        }
+    }
+
+    /// The OpenRC service name in the target.
+    pub fn service(self) -> &'static str {
+        match self {
+            TimeDaemon::Ntpd | TimeDaemon::Openntpd => "ntpd",
+            TimeDaemon::Chrony => "chronyd",
+        }
+    }
+
+    /// Human-readable label shown to the user.
+    pub fn display_name(self) -> &'static str {
+        match self {
+            TimeDaemon::Ntpd => "ntpd (reference NTP)",
+            TimeDaemon::Chrony => "chrony (fast convergence, laptops)",
+            TimeDaemon::Openntpd => "OpenNTPD (minimal)",
+        }
+    }
+}
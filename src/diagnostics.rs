@@ -0,0 +1,113 @@
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{answers, cmd, error::InstallerError, log, lsblk, prompt, ui};
+
+/// Staging directory for the bundle's files, and the archive's top folder.
+const STAGING: &str = "/tmp/artix-installer-diagnostics";
+const USB_DIR: &str = "/tmp/artix-installer-usb";
+
+/// Lines of `dmesg` kept: enough for the failure, not the whole boot.
+const DMESG_LINES: usize = 300;
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// After a failed run, offers to pack what a bug report needs — the error,
+/// the installer log, `lsblk -f`, `/proc/mounts`, the end of `dmesg` and
+/// the target's `pacman.log` — into a tar.gz on a USB stick (or in /tmp).
+/// Call before the teardown, while `/mnt` is still mounted. Never fatal.
+///
+/// Non-interactive mode reads `diagnostics.write` (default: no) and
+/// `diagnostics.device` (a partition, or `"tmp"`).
+pub fn offer(e: &InstallerError) {
+    if !e.is_recoverable() || cmd::dry_run() {
+        return;
+    }
+    if let Err(why) = bundle(e) {
+        ui::print_warning(&format!("Couldn't write the support bundle: {}", why));
+    }
+}
+
+// ── Bundle ────────────────────────────────────────────────────────────────────
+
+fn bundle(e: &InstallerError) -> Result<(), InstallerError> {
+    println!();
+    let ask = "Write a support bundle (logs, disks, dmesg) to attach to a bug report?";
+    if !prompt::confirm("diagnostics.write", ask, !answers::unattended())? {
+        return Ok(());
+    }
+    let device = pick_device()?;
+
+    let _ = fs::remove_dir_all(STAGING);
+    fs::create_dir_all(STAGING)?;
+    let version = env!("CARGO_PKG_VERSION");
+    let error = format!("artix-installer {}\nerror: {}\n", version, e);
+    fs::write(format!("{}/error.txt", STAGING), error)?;
+    let _ = fs::copy(log::LOG_FILE, format!("{}/install.log", STAGING));
+    let _ = fs::copy("/mnt/var/log/pacman.log", format!("{}/pacman.log", STAGING));
+    let _ = fs::copy("/proc/mounts", format!("{}/mounts.txt", STAGING));
+    let lsblk = cmd::run_capture("lsblk", &["-f"]).unwrap_or_else(|why| format!("{}\n", why));
+    fs::write(format!("{}/lsblk.txt", STAGING), lsblk)?;
+    let dmesg = cmd::run_capture("dmesg", &[]).unwrap_or_else(|why| format!("{}\n", why));
+    let lines: Vec<&str> = dmesg.lines().collect();
+    let tail = lines[lines.len().saturating_sub(DMESG_LINES)..].join("\n");
+    fs::write(format!("{}/dmesg.txt", STAGING), tail + "\n")?;
+
+    let dir = match device.as_deref() {
+        Some(part) => {
+            fs::create_dir_all(USB_DIR)?;
+            cmd::run_with_spinner(
+                "mount",
+                &[part, USB_DIR],
+                &format!("Mounting {}…", part),
+                &format!("{} mounted.", part),
+            )?;
+            USB_DIR
+        }
+        None => "/tmp",
+    };
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let archive = format!("{}/artix-installer-diagnostics-{}.tar.gz", dir, stamp);
+    let packed = cmd::run_with_spinner(
+        "tar",
+        &["-C", "/tmp", "-czf", &archive, "artix-installer-diagnostics"],
+        "Packing the support bundle…",
+        "Support bundle packed.",
+    );
+    if device.is_some() {
+        cmd::run_best_effort("umount", &[USB_DIR]);
+    }
+    packed?;
+
+    let name = archive.rsplit('/').next().unwrap_or(&archive);
+    match device {
+        Some(part) => ui::print_success(&format!("{} saved on {} — attach it.", name, part)),
+        None => ui::print_success(&format!("Saved {} — copy it off before rebooting.", archive)),
+    }
+    Ok(())
+}
+
+/// An unmounted partition on a removable drive, or `None` for /tmp.
+fn pick_device() -> Result<Option<String>, InstallerError> {
+    let parts: Vec<(String, String)> = lsblk::list_disks()
+        .iter()
+        .filter(|d| d.is_removable())
+        .flat_map(|d| &d.partitions)
+        .filter(|p| p.mountpoint.is_empty() && !p.fstype.is_empty() && p.fstype != "swap")
+        .map(|p| (p.path.clone(), p.display()))
+        .collect();
+    if parts.is_empty() {
+        ui::print_info("No USB partition found — the bundle goes to /tmp.");
+        return Ok(None);
+    }
+
+    let mut items: Vec<String> = parts.iter().map(|(_, label)| label.clone()).collect();
+    items.push("/tmp on the live system".to_string());
+    let mut values: Vec<&str> = parts.iter().map(|(path, _)| path.as_str()).collect();
+    values.push("tmp");
+    println!();
+    let idx = prompt::select("diagnostics.device", "Save the bundle on", &items, &values, 0)?;
+    Ok(parts.get(idx).map(|(path, _)| path.clone()))
+}
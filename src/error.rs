@@ -14,12 +14,72 @@ pub enum InstallerError {
     #[error("Installation cancelled by user")]
     Cancelled,
 
+    #[error("Interrupted (Ctrl-C)")]
+    Interrupted,
+
     #[error("This installer must be run as root (sudo)")]
     NotRoot,
 
     #[error("BIOS/Legacy mode is not supported — this installer requires UEFI")]
     BiosNotSupported,
 
+    #[error("Unsupported live system: {0}")]
+    Unsupported(String),
+
+    #[error("Invalid partition layout: {0}")]
+    InvalidLayout(String),
+
     #[error("Prompt error: {0}")]
     Prompt(#[from] dialoguer::Error),
+
+    #[error("Invalid config file: {0}")]
+    Config(String),
+
+    #[error("No answer for '{0}' in the config file")]
+    MissingAnswer(String),
+
+    #[error("{0}")]
+    Usage(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("'{0}' couldn't download packages — check the network or the mirror list")]
+    Download(String, String),
+}
+
+/// Exit code of an unattended run that failed but left a checkpoint behind
+/// (`retry.on_failure = "resume"`): re-running with the same answer file
+/// continues where it stopped. `EX_TEMPFAIL` from sysexits.h.
+pub const EXIT_RESUMABLE: i32 = 75;
+
+impl InstallerError {
+    /// Process exit code, so provisioning pipelines can tell failure classes apart.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            InstallerError::Cancelled => 2,
+            InstallerError::NotRoot
+            | InstallerError::BiosNotSupported
+            | InstallerError::Unsupported(_) => 3,
+            InstallerError::Config(_) | InstallerError::MissingAnswer(_) => 4,
+            InstallerError::Usage(_) => 64,
+            InstallerError::Timeout(_) => 124,
+            InstallerError::Interrupted => 130,
+            _ => 1,
+        }
+    }
+
+    /// `true` for failures the user can sensibly retry after fixing
+    /// something by hand — a failed command, a device error, a timeout.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(
+            self,
+            InstallerError::Io(_)
+                | InstallerError::CommandFailed(..)
+                | InstallerError::CommandNotFound(_)
+                | InstallerError::InvalidLayout(_)
+                | InstallerError::Timeout(_)
+                | InstallerError::Download(..)
+        )
+    }
 }
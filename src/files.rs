@@ -0,0 +1,189 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use console::style;
+
+use crate::{cmd, error::InstallerError, log, plan, prompt, ui};
+
+/// Unchanged lines shown around each change in a diff hunk.
+const CONTEXT: usize = 3;
+
+// ── Public API ────────────────────────────────────────────────────────────────
+//
+// Every file written into the target system goes through this module.
+
+/// Replaces the contents of an existing target file with `new`.
+///
+/// Shows a unified diff of the change first and, in interactive mode, asks
+/// for confirmation. The diff is recorded in the install log either way.
+/// Returns `Ok(false)` when nothing changed or the user declined.
+pub fn edit(path: &str, new: &str) -> Result<bool, InstallerError> {
+    let old = fs::read_to_string(path)?;
+    let diff = unified_diff(path, &old, new);
+
+    if diff.is_empty() {
+        ui::print_info(&format!("{} is already up to date.", path));
+        return Ok(false);
+    }
+
+    println!();
+    print_diff(&diff);
+    println!();
+
+    let apply = prompt::confirm("apply_edits", &format!("Apply these changes to {}?", path), true)?;
+    log::line(&format!(
+        "{} {}:\n{}",
+        if apply { "edit" } else { "declined edit of" },
+        path,
+        diff.trim_end()
+    ));
+
+    if apply {
+        if cmd::dry_run() {
+            plan::edit(path, &diff);
+        }
+        write_atomic(path, new)?;
+    }
+    Ok(apply)
+}
+
+/// Writes `content` to `path` so that a crash at any point leaves either the
+/// old file or the complete new one — never a truncated mix.
+///
+/// Writes a sibling temp file, fsyncs it, renames it over `path`, then
+/// fsyncs the directory so the rename itself is durable.
+pub fn write_atomic(path: &str, content: &str) -> Result<(), InstallerError> {
+    if cmd::dry_run() {
+        ui::print_info(&format!("[dry-run] would write {} ({} bytes)", path, content.len()));
+        log::line(&format!("[dry-run] write {}", path));
+        plan::write(path, content);
+        return Ok(());
+    }
+
+    let target = Path::new(path);
+    let dir = target.parent().unwrap_or(Path::new("/"));
+    let name = target.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let tmp = dir.join(format!(".{}.artix-installer.tmp", name));
+
+    let result = (|| -> std::io::Result<()> {
+        let mut f = File::create(&tmp)?;
+        f.write_all(content.as_bytes())?;
+        f.sync_all()?;
+
+        // Keep the permissions of the file being replaced (e.g. 0440 sudoers).
+        if let Ok(meta) = fs::metadata(target) {
+            fs::set_permissions(&tmp, meta.permissions())?;
+        }
+
+        fs::rename(&tmp, target)?;
+        File::open(dir)?.sync_all()
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+
+    log::line(&format!("wrote {}", path));
+    Ok(())
+}
+
+// ── Diff rendering ────────────────────────────────────────────────────────────
+
+fn print_diff(diff: &str) {
+    for line in diff.lines() {
+        let styled = if line.starts_with("+++") || line.starts_with("---") {
+            style(line).bold()
+        } else if line.starts_with("@@") {
+            style(line).cyan()
+        } else if line.starts_with('+') {
+            style(line).green()
+        } else if line.starts_with('-') {
+            style(line).red()
+        } else {
+            style(line).dim()
+        };
+        println!("    {}", styled);
+    }
+}
+
+/// One line of an edit script: tag (' ', '-', '+'), text, and the old/new
+/// line indices at which it occurs.
+type DiffOp<'a> = (char, &'a str, usize, usize);
+
+/// Produces a `diff -u`-style diff of `old` → `new`, or `""` if identical.
+fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = edit_script(&a, &b);
+
+    let changed: Vec<usize> = (0..ops.len()).filter(|&i| ops[i].0 != ' ').collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", path, path);
+    let mut k = 0;
+    while k < changed.len() {
+        // Merge changes whose context windows touch into a single hunk.
+        let first = changed[k];
+        while k + 1 < changed.len() && changed[k + 1] - changed[k] <= 2 * CONTEXT + 1 {
+            k += 1;
+        }
+        let start = first.saturating_sub(CONTEXT);
+        let end = (changed[k] + CONTEXT + 1).min(ops.len());
+        let hunk = &ops[start..end];
+
+        let old_len = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_len = hunk.iter().filter(|op| op.0 != '-').count();
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk[0].2 + 1,
+            old_len,
+            hunk[0].3 + 1,
+            new_len
+        ));
+        for (tag, text, _, _) in hunk {
+            out.push_str(&format!("{}{}\n", tag, text));
+        }
+        k += 1;
+    }
+
+    out
+}
+
+/// Longest-common-subsequence edit script between two line lists.
+fn edit_script<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<DiffOp<'a>> {
+    let (n, m) = (a.len(), b.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && a[i] == b[j] {
+            ops.push((' ', a[i], i, j));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', a[i], i, j));
+            i += 1;
+        } else {
+            ops.push(('+', b[j], i, j));
+            j += 1;
+        }
+    }
+
+    ops
+}
@@ -0,0 +1,298 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use console::style;
+
+use crate::{cmd, error::InstallerError, log, lsblk, prompt, report, ui};
+
+/// Kernel log fragments that indicate failing media (USB stick or target disk).
+const IO_ERROR_MARKERS: &[&str] = &[
+    "I/O error",
+    "critical medium error",
+    "Medium Error",
+    "SQUASHFS error",
+    "blk_update_request",
+];
+
+/// ATA attributes whose raw value counts bad sectors; anything above zero
+/// means the drive has started remapping or losing data.
+const SMART_SECTOR_ATTRIBUTES: &[&str] = &[
+    "Reallocated_Sector_Ct",
+    "Current_Pending_Sector",
+    "Offline_Uncorrectable",
+];
+
+/// Below this charge, running on battery gets a warning before the
+/// destructive steps.
+const LOW_BATTERY_PERCENT: u32 = 30;
+
+/// Set once the user chose to carry on despite a low battery.
+static BATTERY_ACCEPTED: AtomicBool = AtomicBool::new(false);
+
+/// Rough installed sizes: base system with kernel and firmware, and the
+/// same with the desktop packages; the pacman cache is included.
+const BASE_DISK_GIB: u64 = 4;
+const DESKTOP_DISK_GIB: u64 = 8;
+
+/// RAM below which the system still installs but runs poorly.
+const BASE_RAM_MIB: u64 = 512;
+const DESKTOP_RAM_MIB: u64 = 2048;
+
+/// Kernel log lines already reported (or present before the installer started).
+static SEEN: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Records the current kernel log as a baseline so only new errors are reported.
+pub fn init() {
+    *SEEN.lock().unwrap() = Some(io_error_lines().into_iter().collect());
+}
+
+/// Reports I/O errors logged by the kernel since the last check, grouped by
+/// device — bad USB sticks and failing disks otherwise surface later as
+/// confusing package-corruption or filesystem errors.
+pub fn check() {
+    let mut guard = SEEN.lock().unwrap();
+    let Some(seen) = guard.as_mut() else { return };
+
+    let fresh: Vec<String> = io_error_lines()
+        .into_iter()
+        .filter(|l| seen.insert(l.clone()))
+        .collect();
+    if fresh.is_empty() {
+        return;
+    }
+
+    let mut by_device: BTreeMap<String, usize> = BTreeMap::new();
+    for line in &fresh {
+        log::line(&format!("kernel: {}", line));
+        *by_device.entry(device_of(line)).or_default() += 1;
+    }
+
+    println!();
+    println!(
+        "  {}",
+        style(format!(
+            "{}  THE KERNEL REPORTED I/O ERRORS DURING INSTALLATION",
+            ui::warn_mark()
+        ))
+        .red()
+        .bold()
+    );
+    for (dev, n) in &by_device {
+        ui::print_warning(&format!("{} — {} error(s)", dev, n));
+    }
+    ui::print_info("If this is the live USB stick, re-flash it or use another one.");
+    ui::print_info("If it is the target disk, the drive may be failing — check it with smartctl.");
+    println!();
+}
+
+/// Runs `smartctl -H -A` on the target disk before anything is written to
+/// it. A failing self-assessment or bad-sector counts are shown with the
+/// details and the install only goes on when confirmed. Disks without SMART
+/// (VMs, most USB bridges) and a missing smartctl skip the check.
+///
+/// Non-interactive mode reads `smart_continue` (default: stop).
+pub fn smart(disk: &str) -> Result<(), InstallerError> {
+    let out = match cmd::run_capture_any("smartctl", &["-H", "-A", disk]) {
+        Ok(out) => out,
+        Err(e) => {
+            log::line(&format!("smartctl {}: {}", disk, e));
+            String::new()
+        }
+    };
+    if !out.lines().any(|l| l.contains("overall-health") || l.contains("Health Status")) {
+        ui::print_info(&format!("No SMART data for {} — skipping the drive health check.", disk));
+        return Ok(());
+    }
+
+    let problems = smart_problems(&out);
+    if problems.is_empty() {
+        ui::print_success(&format!("{} passes its SMART health check.", disk));
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "  {}",
+        style(format!("{}  {} REPORTS SMART PROBLEMS", ui::warn_mark(), disk))
+            .red()
+            .bold()
+    );
+    for problem in &problems {
+        log::line(&format!("smart {}: {}", disk, problem));
+        ui::print_warning(problem);
+    }
+    ui::print_info(&format!(
+        "The drive may be failing — see `smartctl -a {}` for the full report.",
+        disk
+    ));
+    println!();
+
+    if !prompt::confirm("smart_continue", "Install to this disk anyway?", false)? {
+        return Err(InstallerError::Cancelled);
+    }
+    report::note("disk health", &problems.join("; "));
+    Ok(())
+}
+
+/// Warns before formatting and basestrap when a laptop runs on battery
+/// below [`LOW_BATTERY_PERCENT`] — losing power halfway leaves the target
+/// unbootable. Declining re-checks once, so plugging in the charger is
+/// enough; otherwise the install stops. Asked at most once per run.
+///
+/// Non-interactive mode reads `battery_continue` (default: stop).
+pub fn power() -> Result<(), InstallerError> {
+    if BATTERY_ACCEPTED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let Some(percent) = low_battery() else { return Ok(()) };
+
+    println!();
+    ui::print_warning(&format!(
+        "Running on battery at {}% — a power loss now corrupts the new system.",
+        percent
+    ));
+    ui::print_info("Plug in the charger, or continue at your own risk.");
+    println!();
+
+    if !prompt::confirm("battery_continue", "Continue on battery?", false)? {
+        match low_battery() {
+            Some(_) => return Err(InstallerError::Cancelled),
+            None => {
+                ui::print_success("Running on AC power.");
+                return Ok(());
+            }
+        }
+    }
+    BATTERY_ACCEPTED.store(true, Ordering::Relaxed);
+    log::line(&format!("continued on battery at {}%", percent));
+    Ok(())
+}
+
+/// Pre-flight for the chosen layout: stops when root (plus a separate
+/// /var) is smaller than the estimated install size, and warns when the
+/// machine has less RAM than the selection needs. Sizes that can't be read
+/// (a partition that doesn't exist yet in a dry run) skip the check.
+pub fn requirements(root: &str, var: Option<&str>, desktop: bool) -> Result<(), InstallerError> {
+    let (disk_gib, ram_mib, what) = if desktop {
+        (DESKTOP_DISK_GIB, DESKTOP_RAM_MIB, "the desktop")
+    } else {
+        (BASE_DISK_GIB, BASE_RAM_MIB, "a base system")
+    };
+
+    let sizes: Option<Vec<u64>> =
+        std::iter::once(root).chain(var).map(lsblk::size_bytes).collect();
+    if let Some(available) = sizes.map(|s| s.iter().sum::<u64>()) {
+        if available < disk_gib << 30 {
+            return Err(InstallerError::InvalidLayout(format!(
+                "{} GiB on {}{} is too small for {} (about {} GiB)",
+                available >> 30,
+                root,
+                var.map(|v| format!(" + {}", v)).unwrap_or_default(),
+                what,
+                disk_gib
+            )));
+        }
+    }
+
+    if let Some(ram) = ram_mib_total() {
+        if ram < ram_mib {
+            ui::print_warning(&format!(
+                "Only {} MiB of RAM — {} wants at least {} MiB and may be slow.",
+                ram, what, ram_mib
+            ));
+            log::line(&format!("low RAM: {} MiB for {}", ram, what));
+        }
+    }
+    Ok(())
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// `MemTotal` from /proc/meminfo.
+fn ram_mib_total() -> Option<u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|l| l.starts_with("MemTotal:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib / 1024)
+}
+
+/// The lowest battery charge when no AC adapter is online and a battery is
+/// below the threshold; `None` on AC, desktops and VMs.
+fn low_battery() -> Option<u32> {
+    let entries = fs::read_dir("/sys/class/power_supply").ok()?;
+    let mut on_ac = false;
+    let mut lowest: Option<u32> = None;
+    for entry in entries.flatten() {
+        let read = |name: &str| fs::read_to_string(entry.path().join(name)).unwrap_or_default();
+        match read("type").trim() {
+            "Mains" | "USB" => on_ac |= read("online").trim() == "1",
+            "Battery" => {
+                if let Ok(capacity) = read("capacity").trim().parse::<u32>() {
+                    lowest = Some(lowest.map_or(capacity, |l| l.min(capacity)));
+                }
+            }
+            _ => {}
+        }
+    }
+    lowest.filter(|&p| !on_ac && p < LOW_BATTERY_PERCENT)
+}
+
+/// The failing checks in `smartctl -H -A` output, one line each.
+fn smart_problems(out: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    for line in out.lines() {
+        let (key, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.trim();
+
+        if (key.contains("overall-health") && value != "PASSED")
+            || (key.contains("Health Status") && value != "OK")
+        {
+            problems.push(format!("Health self-assessment: {}", value));
+        } else if key.trim() == "Critical Warning" && value.trim_start_matches("0x") != "00" {
+            problems.push(format!("NVMe critical warning: {}", value));
+        } else if key.trim() == "Media and Data Integrity Errors" && value != "0" {
+            problems.push(format!("NVMe media errors: {}", value));
+        }
+
+        // `  5 Reallocated_Sector_Ct  0x0033  100  100  010  Pre-fail  Always  -  8`
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        if cols.len() >= 10 && SMART_SECTOR_ATTRIBUTES.contains(&cols[1]) {
+            let raw: u64 = cols[9].parse().unwrap_or(0);
+            if raw > 0 {
+                problems.push(format!("{}: {}", cols[1], raw));
+            }
+        }
+    }
+    problems
+}
+
+/// Kernel log lines that report a disk I/O error.
+fn io_error_lines() -> Vec<String> {
+    let Ok(out) = cmd::run_capture("dmesg", &["--level=emerg,alert,crit,err,warn"]) else {
+        return vec![];
+    };
+
+    out.lines()
+        .filter(|l| IO_ERROR_MARKERS.iter().any(|m| l.contains(m)))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Extracts the device name from e.g. `I/O error, dev sdb, sector 2048` or
+/// `Buffer I/O error on dev sdb1, logical block 0`.
+fn device_of(line: &str) -> String {
+    line.split("dev ")
+        .nth(1)
+        .and_then(|rest| rest.split([',', ' ', ':']).next())
+        .filter(|d| !d.is_empty())
+        .map(|d| format!("/dev/{}", d))
+        .unwrap_or_else(|| "unknown device".to_string())
+}
@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::error::InstallerError;
+
+// ── Ctrl-C handling ───────────────────────────────────────────────────────────
+//
+// SIGINT only sets a flag; the code that notices it returns
+// `InstallerError::Interrupted`, so the normal error path in `main` clears
+// the terminal, saves the session and tears down the mounts.
+//
+// Child processes share the terminal's process group and get the signal
+// too, so a running mkfs or basestrap stops on its own. At a dialoguer
+// prompt the terminal is in raw mode and Ctrl-C arrives as a read error
+// instead — `prompt` maps that to the same error.
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_sigint(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Installs the SIGINT handler.
+pub fn install() {
+    let handler = on_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+    }
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Forgets a Ctrl-C that an interactive child (cfdisk, a chroot shell)
+/// handled itself.
+pub fn reset() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+}
+
+/// Returns `Err(Interrupted)` once Ctrl-C has been pressed.
+pub fn check() -> Result<(), InstallerError> {
+    if interrupted() {
+        Err(InstallerError::Interrupted)
+    } else {
+        Ok(())
+    }
+}
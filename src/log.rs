@@ -0,0 +1,18 @@
+use std::{fs::OpenOptions, io::Write, sync::OnceLock, time::Instant};
+
+/// Plain-text log of everything the installer did during this run.
+pub const LOG_FILE: &str = "/tmp/artix-installer.log";
+
+static START: OnceLock<Instant> = OnceLock::new();
+
+/// Appends one line, prefixed with the time elapsed since the first entry.
+/// Logging is best-effort: a failing write never interrupts the installation.
+pub fn line(msg: &str) {
+    let start = *START.get_or_init(Instant::now);
+    let secs = start.elapsed().as_secs();
+
+    let Ok(mut f) = OpenOptions::new().append(true).create(true).open(LOG_FILE) else {
+        return;
+    };
+    let _ = writeln!(f, "[+{:02}:{:02}:{:02}] {}", secs / 3600, secs / 60 % 60, secs % 60, msg);
+}
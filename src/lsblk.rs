@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::{iter::Peekable, str::Chars};
 
 use crate::cmd;
 
+/// Columns requested from `lsblk`; every row carries all of them.
+const COLUMNS: &str = "NAME,TYPE,SIZE,MODEL,FSTYPE,LABEL,UUID,MOUNTPOINT,ROTA,TRAN,RM";
+
 // ── Data types ────────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone)]
@@ -9,73 +12,390 @@ pub struct Disk {
     pub path: String,  // /dev/sda
     pub size: String,  // 20G
     pub model: String, // SAMSUNG SSD 870
+    pub tran: String,  // usb, sata, nvme, …
+    pub rotational: bool,
+    pub removable: bool,
+    pub partitions: Vec<Partition>,
+}
+
+/// A partition as reported by `lsblk`. Empty strings mean "not set".
+#[derive(Debug, Clone)]
+pub struct Partition {
+    pub path: String,       // /dev/sda1
+    pub size: String,       // 512M
+    pub fstype: String,     // vfat, ext4, swap, …
+    pub label: String,      // EFI
+    pub uuid: String,       // 1234-ABCD
+    pub mountpoint: String, // /boot
+}
+
+/// Where the live ISO mounts its boot medium and root image.
+const LIVE_MOUNTS: &str = "/run/artix";
+
+/// Device name prefixes that are never installation targets:
+/// loop devices, compressed RAM swap, RAM disks and optical drives.
+const IGNORED_PREFIXES: &[&str] = &["/dev/loop", "/dev/zram", "/dev/ram", "/dev/sr"];
+
+/// `true` for the hardware areas of an eMMC chip — `mmcblkNboot0/1` (where
+/// SBC firmware and bootloaders live) and `mmcblkNrpmb` (replay-protected
+/// storage). lsblk reports them as disks, but they are never installation
+/// targets and must not be formatted.
+pub fn is_emmc_hw_area(path: &str) -> bool {
+    let Some(rest) = path.strip_prefix("/dev/mmcblk") else {
+        return false;
+    };
+    let area = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+    area.len() < rest.len() && (area.starts_with("boot") || area.starts_with("rpmb"))
 }
 
 impl Disk {
     /// One-line label shown in the arrow-key selector.
     pub fn display(&self) -> String {
-        format!("{:<12}  {:>8}   {}", self.path, self.size, self.model)
+        let tag = match self.kind_tag() {
+            Some(t) => format!("  [{}]", t),
+            None => String::new(),
+        };
+        format!("{:<12}  {:>8}   {}{}", self.path, self.size, self.model, tag)
+    }
+
+    /// `true` for USB-attached or removable media (flash drives, card readers).
+    pub fn is_removable(&self) -> bool {
+        self.removable || self.tran == "usb"
+    }
+
+    fn kind_tag(&self) -> Option<&'static str> {
+        match (self.tran == "usb", self.removable) {
+            (true, _) => Some("USB"),
+            (false, true) => Some("removable"),
+            _ if self.path.starts_with("/dev/mmcblk") => Some("eMMC"),
+            _ => None,
+        }
+    }
+}
+
+impl Partition {
+    /// One-line label shown in the role selector, e.g.
+    /// `/dev/sda4   200G   ext4 (label: HOME, mounted at /run/media/home)`.
+    pub fn display(&self) -> String {
+        let fs = if self.fstype.is_empty() { "no filesystem" } else { &self.fstype };
+
+        let mut extra = Vec::new();
+        if !self.label.is_empty() {
+            extra.push(format!("label: {}", self.label));
+        }
+        if !self.mountpoint.is_empty() {
+            extra.push(format!("mounted at {}", self.mountpoint));
+        }
+
+        let details = if extra.is_empty() {
+            fs.to_string()
+        } else {
+            format!("{} ({})", fs, extra.join(", "))
+        };
+        format!("{:<16}  {:>8}   {}", self.path, self.size, details)
     }
 }
 
 // ── Public API ────────────────────────────────────────────────────────────────
 
-/// Returns all block devices of type `disk` visible to the system.
+/// Returns all block devices of type `disk` visible to the system, each with
+/// its partitions, skipping loop/zram/optical devices and eMMC boot/RPMB
+/// areas.
 /// Falls back to an empty list if `lsblk` is unavailable.
 pub fn list_disks() -> Vec<Disk> {
-    let output = match cmd::run_capture(
-        "lsblk",
-        &["--pairs", "--output", "NAME,SIZE,TYPE,MODEL", "--nodeps"],
-    ) {
-        Ok(o) => o,
-        Err(_) => return vec![],
-    };
+    let args = ["--json", "--tree", "--paths", "--output", COLUMNS];
+    match cmd::run_capture("lsblk", &args) {
+        Ok(json) => parse_disks(&json),
+        Err(_) => vec![],
+    }
+}
 
-    output
+/// The disk among `disks` the live system runs from — e.g. the USB stick
+/// the ISO was written to — found by what is mounted under `/run/artix`.
+pub fn live_disk(disks: &[Disk]) -> Option<&Disk> {
+    let out = cmd::run_capture("findmnt", &["-rn", "-o", "SOURCE,TARGET"]).ok()?;
+    let sources: Vec<&str> = out
         .lines()
-        .filter_map(|line| {
-            let m = parse_pairs(line);
-            if m.get("TYPE").map(String::as_str) != Some("disk") {
-                return None;
+        .filter_map(|l| l.split_once(' '))
+        .filter(|(_, target)| target.starts_with(LIVE_MOUNTS))
+        .map(|(source, _)| source)
+        .collect();
+    disks.iter().find(|d| {
+        sources.iter().any(|s| *s == d.path || d.partitions.iter().any(|p| p.path == *s))
+    })
+}
+
+/// Returns the filesystem UUID of `device` via `blkid`, if it has one.
+pub fn uuid_of(device: &str) -> Option<String> {
+    let out = cmd::run_capture("blkid", &["-s", "UUID", "-o", "value", device]).ok()?;
+    let uuid = out.trim();
+    (!uuid.is_empty()).then(|| uuid.to_string())
+}
+
+/// Returns the GPT partition UUID of `device` via `blkid`, if it has one.
+pub fn partuuid_of(device: &str) -> Option<String> {
+    let out = cmd::run_capture("blkid", &["-s", "PARTUUID", "-o", "value", device]).ok()?;
+    let partuuid = out.trim();
+    (!partuuid.is_empty()).then(|| partuuid.to_string())
+}
+
+/// Size of a disk or partition in bytes, if `lsblk` knows it.
+pub fn size_bytes(device: &str) -> Option<u64> {
+    let args = ["--bytes", "--nodeps", "--noheadings", "--output", "SIZE", device];
+    let out = cmd::run_capture("lsblk", &args).ok()?;
+    out.trim().parse().ok()
+}
+
+/// Maps a filesystem UUID back to its current device node (e.g. `/dev/sdb3`).
+pub fn device_for_uuid(uuid: &str) -> Option<String> {
+    std::fs::canonicalize(format!("/dev/disk/by-uuid/{}", uuid))
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
+/// Maps a GPT partition UUID back to its current device node.
+pub fn device_for_partuuid(partuuid: &str) -> Option<String> {
+    std::fs::canonicalize(format!("/dev/disk/by-partuuid/{}", partuuid))
+        .ok()
+        .map(|p| p.to_string_lossy().into_owned())
+}
+
+// ── lsblk --json reader ───────────────────────────────────────────────────────
+//
+// {"blockdevices": [{"name": "/dev/sda", "type": "disk", …, "children": [{…}]}]}
+//
+// Values are strings, numbers, null, booleans (ROTA and RM on newer
+// util-linux, "0"/"1" strings on older ones) and the nested `children`.
+
+/// Disks and their partitions from `lsblk --json --tree` output.
+fn parse_disks(json: &str) -> Vec<Disk> {
+    let Some(root) = Reader { chars: json.chars().peekable() }.value() else {
+        return vec![];
+    };
+    root.list("blockdevices")
+        .iter()
+        .filter(|d| d.text("type") == "disk")
+        .filter(|d| {
+            let name = d.text("name");
+            !IGNORED_PREFIXES.iter().any(|p| name.starts_with(p)) && !is_emmc_hw_area(&name)
+        })
+        .map(|d| {
+            let partitions = d
+                .list("children")
+                .iter()
+                .filter(|p| p.text("type") == "part")
+                .map(|p| Partition {
+                    path: p.text("name"),
+                    size: p.text("size"),
+                    fstype: p.text("fstype"),
+                    label: p.text("label"),
+                    uuid: p.text("uuid"),
+                    mountpoint: p.text("mountpoint"),
+                })
+                .collect();
+            let model = d.text("model");
+            Disk {
+                path: d.text("name"),
+                size: d.text("size"),
+                model: if model.is_empty() { "—".to_string() } else { model },
+                tran: d.text("tran"),
+                rotational: d.flag("rota"),
+                removable: d.flag("rm"),
+                partitions,
             }
-            Some(Disk {
-                path: format!("/dev/{}", m.get("NAME").map(String::as_str).unwrap_or("")),
-                size: m.get("SIZE").cloned().unwrap_or_default(),
-                model: {
-                    let s = m.get("MODEL").cloned().unwrap_or_default();
-                    if s.is_empty() { "—".to_string() } else { s }
-                },
-            })
         })
         .collect()
 }
 
-// ── lsblk --pairs parser ──────────────────────────────────────────────────────
-//
-// Each line looks like:   NAME="sda1" SIZE="512M" TYPE="part" PARTTYPENAME="EFI System"
+enum Json {
+    Null,
+    Bool(bool),
+    /// A string, or a number as written.
+    Text(String),
+    List(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
 
-fn parse_pairs(line: &str) -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    let mut rest = line.trim();
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// A field as text; empty when missing or null.
+    fn text(&self, key: &str) -> String {
+        match self.get(key) {
+            Some(Json::Text(t)) => t.clone(),
+            Some(Json::Bool(b)) => (if *b { "1" } else { "0" }).to_string(),
+            _ => String::new(),
+        }
+    }
 
-    while !rest.is_empty() {
-        let Some(eq) = rest.find('=') else { break };
-        let key = rest[..eq].split_whitespace().last().unwrap_or("").to_string();
-        rest = &rest[eq + 1..];
+    /// A boolean field, whichever way this lsblk spells it.
+    fn flag(&self, key: &str) -> bool {
+        match self.get(key) {
+            Some(Json::Bool(b)) => *b,
+            Some(Json::Text(t)) => t == "1",
+            _ => false,
+        }
+    }
 
-        if !rest.starts_with('"') {
-            break;
+    fn list(&self, key: &str) -> &[Json] {
+        match self.get(key) {
+            Some(Json::List(items)) => items,
+            _ => &[],
         }
-        rest = &rest[1..]; // skip opening "
+    }
+}
 
-        let Some(close) = rest.find('"') else { break };
-        let value = rest[..close].to_string();
-        rest = &rest[close + 1..]; // skip closing "
+struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+}
 
-        if !key.is_empty() {
-            map.insert(key, value);
+impl Reader<'_> {
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+        match *self.chars.peek()? {
+            '{' => {
+                self.chars.next();
+                let mut fields = Vec::new();
+                while self.more('}')? {
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    if self.chars.next()? != ':' {
+                        return None;
+                    }
+                    fields.push((key, self.value()?));
+                }
+                Some(Json::Object(fields))
+            }
+            '[' => {
+                self.chars.next();
+                let mut items = Vec::new();
+                while self.more(']')? {
+                    items.push(self.value()?);
+                }
+                Some(Json::List(items))
+            }
+            '"' => self.string().map(Json::Text),
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = self.chars.next_if(|c| !",]} \t\r\n".contains(*c)) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "" => None,
+                    "null" => Some(Json::Null),
+                    "true" => Some(Json::Bool(true)),
+                    "false" => Some(Json::Bool(false)),
+                    _ => Some(Json::Text(word)),
+                }
+            }
         }
     }
 
-    map
+    /// Skips a separating comma; `false` once `close` ends the object or
+    /// list, `None` at the end of the input.
+    fn more(&mut self, close: char) -> Option<bool> {
+        self.skip_whitespace();
+        if self.chars.next_if_eq(&',').is_some() {
+            self.skip_whitespace();
+        }
+        if self.chars.next_if_eq(&close).is_some() {
+            return Some(false);
+        }
+        self.chars.peek().map(|_| true)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        if self.chars.next()? != '"' {
+            return None;
+        }
+        let mut out = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(out),
+                '\\' => match self.chars.next()? {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    'b' => out.push('\u{8}'),
+                    'f' => out.push('\u{c}'),
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| self.chars.next()).collect();
+                        let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                        out.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    c => out.push(c),
+                },
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LSBLK: &str = r#"{
+   "blockdevices": [
+      {"name":"/dev/loop0", "type":"loop", "size":"700M", "model":null, "fstype":"squashfs",
+       "label":null, "uuid":null, "mountpoint":"/run/artix/sfs", "rota":false,
+       "tran":null, "rm":false},
+      {"name":"/dev/sda", "type":"disk", "size":"20G", "model":"SAMSUNG SSD 870",
+       "fstype":null, "label":null, "uuid":null, "mountpoint":null, "rota":false,
+       "tran":"sata", "rm":false,
+       "children": [
+          {"name":"/dev/sda1", "type":"part", "size":"512M", "model":null, "fstype":"vfat",
+           "label":"EFI", "uuid":"1234-ABCD", "mountpoint":null, "rota":false,
+           "tran":null, "rm":false},
+          {"name":"/dev/sda2", "type":"part", "size":"19.5G", "model":null,
+           "fstype":"crypto_LUKS", "label":"My \"Data\"", "uuid":"5678", "mountpoint":null,
+           "rota":false, "tran":null, "rm":false,
+           "children": [
+              {"name":"/dev/mapper/root", "type":"crypt", "size":"19.5G", "model":null,
+               "fstype":"ext4", "label":null, "uuid":"9abc", "mountpoint":"/mnt",
+               "rota":false, "tran":null, "rm":false}
+           ]
+          }
+       ]
+      },
+      {"name":"/dev/sdb", "type":"disk", "size":"7.5G", "model":"Flash\u0020Disk",
+       "fstype":null, "label":null, "uuid":null, "mountpoint":null, "rota":"1",
+       "tran":"usb", "rm":"1"}
+   ]
+}"#;
+
+    #[test]
+    fn reads_disks_and_partitions() {
+        let disks = parse_disks(LSBLK);
+        assert_eq!(disks.len(), 2);
+
+        let sda = &disks[0];
+        assert_eq!((sda.path.as_str(), sda.model.as_str()), ("/dev/sda", "SAMSUNG SSD 870"));
+        assert!(!sda.rotational && !sda.is_removable());
+        assert_eq!(sda.partitions.len(), 2);
+        let efi = &sda.partitions[0];
+        assert_eq!((efi.fstype.as_str(), efi.label.as_str()), ("vfat", "EFI"));
+        assert_eq!((efi.uuid.as_str(), efi.mountpoint.as_str()), ("1234-ABCD", ""));
+        assert_eq!(sda.partitions[1].label, "My \"Data\"");
+
+        let sdb = &disks[1];
+        assert_eq!(sdb.model, "Flash Disk");
+        assert!(sdb.rotational && sdb.removable && sdb.partitions.is_empty());
+    }
+
+    #[test]
+    fn garbage_gives_no_disks() {
+        assert!(parse_disks("").is_empty());
+        assert!(parse_disks("{\"blockdevices\": [").is_empty());
+    }
 }
@@ -1,146 +1,235 @@
+mod answers;
+mod cli;
+mod clone;
 mod cmd;
 mod config;
+mod diagnostics;
 mod error;
+mod files;
+mod health;
+mod interrupt;
+mod log;
 mod lsblk;
+mod mirrors;
+mod pipeline;
+mod plan;
+mod profile;
+mod prompt;
+mod remote;
+mod report;
 mod session;
 mod steps;
 mod ui;
 
-use dialoguer::Confirm;
+use std::time::Instant;
 
+use answers::Answers;
+use cli::ProfileCommand;
 use error::InstallerError;
 use session::Session;
 
 // ── Entry point ───────────────────────────────────────────────────────────────
 
 fn main() {
-    if let Err(e) = run() {
+    let started = Instant::now();
+    let mut sess = Session::default();
+    let result = run(&mut sess, started);
+
+    if let Err(ref e) = result {
+        if matches!(e, InstallerError::Interrupted) {
+            on_interrupt(&sess);
+        }
+        log::line(&format!("! installation failed: {}", e));
+        health::check();
+        // The step deadline may be what failed — don't let it cut teardown short.
+        cmd::set_deadline(None);
+        diagnostics::offer(e);
+        steps::mount::teardown();
+    }
+    plan::export(result.is_ok());
+    plan::show(result.is_ok());
+    if result.is_ok() {
+        report::print_timings(started.elapsed());
+    }
+    if answers::unattended() {
+        report::export(&report::render(&sess, &result, started.elapsed()));
+        if result.is_ok() {
+            steps::reboot::after_install();
+        }
+    }
+
+    if let Err(e) = result {
         println!();
         ui::print_error(&format!("{}", e));
-        std::process::exit(1);
+        std::process::exit(exit_code(&e));
     }
 }
 
-fn run() -> Result<(), InstallerError> {
-    check_root()?;
-
-    ui::print_banner();
-    ui::print_info("This wizard will guide you through a full Artix Linux installation.");
-    ui::print_info("You will be asked before each destructive operation.");
-
-    let mut sess = check_resume()?;
+/// `e.exit_code()`, except that an unattended run that gave up on a
+/// recoverable failure reports what it did with the checkpoint.
+fn exit_code(e: &InstallerError) -> i32 {
+    if !answers::unattended() || !e.is_recoverable() {
+        return e.exit_code();
+    }
+    match pipeline::on_failure() {
+        pipeline::OnFailure::Resume if Session::load().is_some() => {
+            ui::print_info("Checkpoint kept — re-run with the same config to resume.");
+            error::EXIT_RESUMABLE
+        }
+        pipeline::OnFailure::Restart => {
+            Session::clear();
+            ui::print_info("Checkpoint removed — the next run starts from the first step.");
+            e.exit_code()
+        }
+        // Nothing completed yet, so there is nothing to resume from.
+        pipeline::OnFailure::Resume => e.exit_code(),
+    }
+}
 
-    // ── Step 1: Detect boot mode ──────────────────────────────────────────────
-    ui::print_step(1, 9, "System Mode Detection");
-    if sess.last_step < 1 {
-        steps::uefi::check()?;
-        sess.last_step = 1;
+/// Restores the terminal and re-saves the checkpoint after Ctrl-C; the
+/// teardown that follows in `main` releases the mounts.
+fn on_interrupt(sess: &Session) {
+    // Let the teardown commands run; a second Ctrl-C still stops them.
+    interrupt::reset();
+    let _ = console::Term::stdout().show_cursor();
+    println!();
+    println!();
+    ui::print_warning("Interrupted.");
+    if sess.last_step > 0 {
         sess.save().ok();
-    } else {
-        ui::print_success("Already completed — skipping.");
+        ui::print_info(&format!(
+            "Progress saved after step {}/{} — run artix-installer again and choose to resume.",
+            sess.last_step, pipeline::total()
+        ));
     }
+}
 
-    // ── Step 2: Partition the disk ────────────────────────────────────────────
-    ui::print_step(2, 9, "Disk Partitioning");
-    let disk = if sess.last_step < 2 {
-        let d = steps::partition::run()?;
-        sess.disk = Some(d.clone());
-        sess.last_step = 2;
-        sess.save().ok();
-        d
-    } else {
-        let d = sess.disk.clone().unwrap_or_default();
-        ui::print_success(&format!("Already completed — disk: {}.", d));
-        d
-    };
+fn run(sess: &mut Session, started: Instant) -> Result<(), InstallerError> {
+    let mut args = cli::parse()?;
+    ui::configure(args.no_color, args.ascii, args.accessible);
+    if let Some(ref out) = args.clone_to {
+        return clone::run(out);
+    }
+    match args.profile {
+        Some(ProfileCommand::Save { ref name, ref file }) => return profile::save(name, file),
+        Some(ProfileCommand::List) => {
+            profile::print_list();
+            return Ok(());
+        }
+        None => {}
+    }
+    if let Some(ref name) = args.load_profile {
+        args.config = Some(profile::load(name)?);
+    }
+    if let Some(ref config) = args.config {
+        let sha256 = args.config_sha256.as_deref();
+        answers::init(answers::load_verified(config, sha256, args.config_key.as_deref())?);
+    }
 
-    // ── Step 3: Assign roles + format ─────────────────────────────────────────
-    ui::print_step(3, 9, "Partition Formatting");
-    let config = if sess.last_step < 3 {
-        let c = steps::format::build_config(&disk)?;
-        steps::format::run(&c)?;
-        sess.efi_partition  = Some(c.efi_partition.clone());
-        sess.swap_partition = c.swap_partition.clone();
-        sess.root_partition = Some(c.root_partition.clone());
-        sess.last_step = 3;
-        sess.save().ok();
-        c
-    } else {
-        let c = sess.to_config();
-        ui::print_success(&format!(
-            "Already completed — EFI: {}  swap: {}  root: {}.",
-            c.efi_partition,
-            c.swap_partition.as_deref().unwrap_or("none"),
-            c.root_partition,
-        ));
-        c
-    };
+    cmd::set_dry_run(args.dry_run);
+    if let Some(ref path) = args.export_plan {
+        plan::init(path);
+    }
+    if args.plan {
+        plan::show_at_exit();
+        let options = [
+            ("--packages-file", &args.packages_file),
+            ("--download-limit", &args.download_limit),
+            ("--cachedir", &args.cachedir),
+        ];
+        for (flag, value) in options {
+            if let Some(value) = value {
+                plan::option(flag, value);
+            }
+        }
+    }
 
-    // ── Step 4: Mount the new filesystem ──────────────────────────────────────
-    // Mounts are not persistent across process restarts, so always remount.
-    ui::print_step(4, 9, "Mounting Partitions");
-    steps::mount::run(&config)?;
-    if sess.last_step < 4 {
-        sess.last_step = 4;
-        sess.save().ok();
+    if !args.dry_run {
+        check_root()?;
     }
+    check_libc()?;
+    check_arch()?;
+    interrupt::install();
+    health::init();
 
-    // ── Step 5: Sync the system clock ─────────────────────────────────────────
-    ui::print_step(5, 9, "Time Synchronization");
-    if sess.last_step < 5 {
-        steps::ntp::run()?;
-        sess.last_step = 5;
-        sess.save().ok();
+    ui::print_banner();
+    if args.remote {
+        return remote::start();
+    }
+    remote::claim();
+    ui::print_info("This wizard will guide you through a full Artix Linux installation.");
+    if args.config.is_none() && !args.apply {
+        if let Some(path) = profile::pick()? {
+            answers::init(Answers::load(&path)?);
+        }
+    }
+    if args.dry_run {
+        ui::print_warning("Dry run — commands are printed, not executed. Nothing is written.");
+    }
+    if answers::unattended() {
+        ui::print_info("Non-interactive mode — answers are read from the config file.");
     } else {
-        ui::print_success("Already completed — skipping.");
+        ui::print_info("You will be asked before each destructive operation.");
     }
 
-    // ── Step 6: Install base packages ─────────────────────────────────────────
-    ui::print_step(6, 9, "Base System Installation");
-    if sess.last_step < 6 {
-        steps::packages::install_base()?;
-        sess.last_step = 6;
-        sess.save().ok();
-    } else {
-        ui::print_success("Already completed — skipping.");
+    if args.apply {
+        load_plan(&mut args)?;
     }
 
-    // ── Step 7: Install kernel ────────────────────────────────────────────────
-    ui::print_step(7, 9, "Kernel Installation");
-    if sess.last_step < 7 {
-        let kernel = steps::packages::ask_kernel()?;
-        steps::packages::install_kernel(kernel)?;
-        sess.kernel = Some(kernel);
-        sess.last_step = 7;
-        sess.save().ok();
-    } else {
-        ui::print_success(&format!(
-            "Already completed — kernel: {}.",
-            sess.kernel.map(|k| k.display_name()).unwrap_or("unknown"),
-        ));
+    steps::locale::console_font()?;
+    steps::packages::load_extra(args.packages_file.as_deref())?;
+    if let Some(ref rate) = args.download_limit {
+        steps::pacman::limit_downloads(rate)?;
+    }
+    if let Some(ref dir) = args.cachedir {
+        steps::packages::use_cache(dir)?;
     }
 
-    // ── Step 8: Desktop packages ──────────────────────────────────────────────
-    ui::print_step(8, 9, "Desktop Environment");
-    if sess.last_step < 8 {
-        steps::packages::install_desktop()?;
-        sess.last_step = 8;
-        sess.save().ok();
-    } else {
-        ui::print_success("Already completed — skipping.");
+    *sess = check_resume()?;
+    if plan::applying() && sess.last_step == 0 {
+        plan::reset_applied();
     }
+    pipeline::run_all(sess, started)?;
 
-    // ── Step 9: Generate fstab + enter chroot ────────────────────────────────
-    ui::print_step(9, 9, "Final Setup");
-    steps::fstab::generate()?;
-    steps::chroot::run()?;
+    cmd::set_deadline(None);
 
     // Installation complete — remove checkpoint file.
     Session::clear();
+    plan::finish_apply();
+    log::line("installation complete");
 
     Ok(())
 }
 
+// ── Saved plan ────────────────────────────────────────────────────────────────
+
+/// `--apply`: loads the plan `--plan` saved, takes back the options it was
+/// made with unless given again, and asks once before carrying it out.
+fn load_plan(args: &mut cli::Args) -> Result<(), InstallerError> {
+    let saved = plan::load()?;
+    for (flag, value) in saved.options {
+        let slot = match flag.as_str() {
+            "--packages-file" => &mut args.packages_file,
+            "--download-limit" => &mut args.download_limit,
+            "--cachedir" => &mut args.cachedir,
+            _ => continue,
+        };
+        slot.get_or_insert(value);
+    }
+
+    ui::print_info(&format!(
+        "Saved plan loaded — {} actions, {} answers.",
+        saved.actions,
+        saved.answers.len()
+    ));
+    // Passing --apply to an unattended run is the go-ahead.
+    let question = "Carry out the plan now? It changes the disks it lists.";
+    if !answers::unattended() && !prompt::confirm("apply", question, false)? {
+        return Err(InstallerError::Cancelled);
+    }
+    Ok(())
+}
+
 // ── Session resume prompt ─────────────────────────────────────────────────────
 
 fn check_resume() -> Result<Session, InstallerError> {
@@ -150,16 +239,12 @@ fn check_resume() -> Result<Session, InstallerError> {
 
     println!();
     ui::print_info(&format!(
-        "Previous session found — completed step {}/9.",
-        saved.last_step
+        "Previous session found — completed step {}/{}.",
+        saved.last_step, pipeline::total()
     ));
     println!();
 
-    if Confirm::new()
-        .with_prompt("Resume from last checkpoint? (N = start from scratch)")
-        .default(true)
-        .interact()?
-    {
+    if prompt::confirm("resume", "Resume from last checkpoint? (N = start from scratch)", true)? {
         ui::print_success("Resuming previous session.");
         Ok(saved)
     } else {
@@ -188,3 +273,73 @@ fn check_root() -> Result<(), InstallerError> {
 
     Ok(())
 }
+
+/// The package sets (base, elogind, firmware) are glibc builds; on a
+/// musl-based live system basestrap would fail halfway, so refuse up front.
+fn check_libc() -> Result<(), InstallerError> {
+    let musl = ["/lib", "/usr/lib"].iter().any(|dir| {
+        std::fs::read_dir(dir).is_ok_and(|entries| {
+            entries
+                .flatten()
+                .any(|e| e.file_name().to_string_lossy().starts_with("ld-musl-"))
+        })
+    });
+
+    if musl {
+        return Err(InstallerError::Unsupported(
+            "musl libc detected — Artix packages are built against glibc; \
+             boot an official Artix ISO instead"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Artix ships x86_64 packages, and aarch64 ones through Artix ARM. On a
+/// 32-bit CPU or kernel basestrap would leave an unbootable system, so
+/// refuse up front and point to what does run there.
+fn check_arch() -> Result<(), InstallerError> {
+    let machine = kernel_machine();
+    // An unknown machine name is not worth refusing over.
+    if machine == "x86_64" || machine == "aarch64" || machine.is_empty() {
+        return Ok(());
+    }
+
+    let long_mode = std::fs::read_to_string("/proc/cpuinfo").is_ok_and(|info| {
+        info.lines()
+            .find(|l| l.starts_with("flags"))
+            .is_some_and(|l| l.split_whitespace().any(|f| f == "lm"))
+    });
+    let reason = match machine.as_str() {
+        "i386" | "i486" | "i586" | "i686" if long_mode => format!(
+            "{} kernel on a 64-bit CPU — boot the x86_64 Artix ISO instead",
+            machine
+        ),
+        "i386" | "i486" | "i586" | "i686" => format!(
+            "32-bit-only CPU ({}) — Artix needs x86_64; \
+             a 32-bit distribution such as Arch Linux 32 still supports it",
+            machine
+        ),
+        "armv7l" | "armv6l" => format!(
+            "32-bit ARM ({}) is not supported — Artix ARM needs aarch64",
+            machine
+        ),
+        other => format!("{} CPUs are not supported — Artix needs x86_64 or aarch64", other),
+    };
+    Err(InstallerError::Unsupported(reason))
+}
+
+/// The running kernel's machine name, as `uname -m` prints it.
+fn kernel_machine() -> String {
+    // SAFETY: `uname` fills the zeroed struct; every field is NUL-terminated.
+    unsafe {
+        let mut uts: libc::utsname = std::mem::zeroed();
+        if libc::uname(&mut uts) != 0 {
+            return String::new();
+        }
+        std::ffi::CStr::from_ptr(uts.machine.as_ptr())
+            .to_string_lossy()
+            .into_owned()
+    }
+}
@@ -0,0 +1,69 @@
+use std::fs;
+
+use crate::{files, log, report, ui};
+
+/// The live system's mirror list, which basestrap also copies to the target.
+const MIRRORLIST: &str = "/etc/pacman.d/mirrorlist";
+
+/// Automatic failovers per transaction before the error goes to the user.
+pub const MAX_FAILOVERS: usize = 3;
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// The mirror a failed pacman run couldn't download from — `Some("")` when
+/// the output shows a failed download (404, timeout, unresolvable host) but
+/// not which mirror, `None` for any other failure.
+pub fn failed_mirror(output: &str) -> Option<String> {
+    // error: failed retrieving file 'core.db' from mirror.example.org : … 404
+    let line = output.lines().find(|l| l.contains("failed retrieving file"));
+    if let Some(line) = line {
+        let host = line.split(" from ").nth(1).and_then(|r| r.split_whitespace().next());
+        return Some(host.unwrap_or_default().to_string());
+    }
+    output.contains("failed to retrieve some files").then(String::new)
+}
+
+/// Moves the failing mirror (the first one when `host` is empty) to the
+/// end of the mirror list, so the next attempt starts with another one.
+/// `false` when there is nothing to rotate to.
+pub fn rotate(host: &str) -> bool {
+    let Ok(list) = fs::read_to_string(MIRRORLIST) else {
+        return false;
+    };
+    let mut lines: Vec<&str> = list.lines().collect();
+    let servers: Vec<usize> = (0..lines.len()).filter(|&i| is_server(lines[i])).collect();
+    if servers.len() < 2 {
+        return false;
+    }
+    let failed = servers
+        .iter()
+        .copied()
+        .find(|&i| !host.is_empty() && lines[i].contains(host))
+        .unwrap_or(servers[0]);
+    let line = lines.remove(failed);
+    lines.push(line);
+
+    let mut rotated = lines.join("\n");
+    rotated.push('\n');
+    if let Err(e) = files::write_atomic(MIRRORLIST, &rotated) {
+        log::line(&format!("! mirror rotation failed: {}", e));
+        return false;
+    }
+    let next = lines.iter().find(|l| is_server(l)).map(|l| url_of(l)).unwrap_or_default();
+    let failed = url_of(line);
+    ui::print_warning(&format!("Downloads from {} failed — retrying with {}.", failed, next));
+    report::note("mirrors", &format!("{} failed, moved to the end of the list", failed));
+    true
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+fn is_server(line: &str) -> bool {
+    line.trim_start().strip_prefix("Server").is_some_and(|r| r.trim_start().starts_with('='))
+}
+
+/// `https://mirror.example.org/artix` from `Server = https://…/$repo/os/$arch`.
+fn url_of(line: &str) -> &str {
+    let url = line.split_once('=').map(|(_, url)| url.trim()).unwrap_or_default();
+    url.split("/$repo").next().unwrap_or(url)
+}
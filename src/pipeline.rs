@@ -0,0 +1,620 @@
+use std::{
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    answers,
+    cmd::{self, Cmd},
+    config::{Config, RootFs, SeatManager, TimeDaemon},
+    error::InstallerError,
+    health, interrupt, log, plan, profile, prompt, report,
+    session::Session,
+    steps, ui,
+};
+
+// ── Step trait ────────────────────────────────────────────────────────────────
+
+/// One numbered stage of the installation.
+pub trait Step {
+    /// Stable id — used for checkpoints, `[timeouts]` keys and dependencies.
+    fn id(&self) -> &'static str;
+
+    /// Heading shown in the step header.
+    fn title(&self) -> &'static str;
+
+    /// Ids of the steps whose results this one builds on. A step nothing
+    /// depends on may be skipped after a failure.
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// `true` when a resumed session can skip this step.
+    fn is_done(&self, sess: &Session) -> bool {
+        sess.is_done(self.id())
+    }
+
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError>;
+
+    /// Called instead of `run` for a completed step on resume, to restore
+    /// whatever later steps need from it.
+    fn restore(&self, _ctx: &mut Context) {
+        ui::print_success("Already completed — skipping.");
+    }
+}
+
+/// State shared between steps.
+pub struct Context<'a> {
+    pub sess: &'a mut Session,
+    /// When the run began, for the report's duration.
+    pub started: Instant,
+    /// Runs the steps' commands — for real, or as a dry run.
+    pub cmd: Cmd<'a>,
+    config: Option<Config>,
+}
+
+impl Context<'_> {
+    /// The partition layout chosen in the format step.
+    pub fn config(&mut self) -> &Config {
+        self.config.get_or_insert_with(|| self.sess.to_config())
+    }
+}
+
+// ── Registry ──────────────────────────────────────────────────────────────────
+
+/// Every step, in execution order. Dependencies must come earlier.
+fn registry() -> Vec<Box<dyn Step>> {
+    vec![
+        Box::new(Uefi),
+        Box::new(Partition),
+        Box::new(Review),
+        Box::new(Format),
+        Box::new(Mount),
+        Box::new(Ntp),
+        Box::new(Base),
+        Box::new(Kernel),
+        Box::new(Desktop),
+        Box::new(Makepkg),
+        Box::new(Verify),
+        Box::new(Bootloader),
+        Box::new(Final),
+    ]
+}
+
+/// Step order when checkpoints stored only a count, before steps had ids.
+const LEGACY_ORDER: &[&str] = &[
+    "uefi", "partition", "format", "mount", "ntp", "base", "kernel", "desktop", "makepkg",
+    "verify", "final",
+];
+
+/// Number of numbered steps shown in the step headers.
+pub fn total() -> u8 {
+    registry().len() as u8
+}
+
+// ── Executor ──────────────────────────────────────────────────────────────────
+
+/// Runs every step in order, skipping those a resumed session already did.
+pub fn run_all(sess: &mut Session, started: Instant) -> Result<(), InstallerError> {
+    let _ = POLICY.set(RetryPolicy::from_answers()?);
+    let steps = registry();
+    let total = steps.len() as u8;
+
+    // Checkpoints written before steps had ids only carry a step count.
+    if sess.completed.is_empty() {
+        let n = (sess.last_step as usize).min(LEGACY_ORDER.len());
+        sess.completed = LEGACY_ORDER[..n].iter().map(|id| id.to_string()).collect();
+    }
+
+    let mut ctx = Context { sess, started, cmd: Cmd::system(), config: None };
+    for (i, step) in steps.iter().enumerate() {
+        let n = i as u8 + 1;
+        begin_step(n, total, step.id(), step.title(), started)?;
+
+        if step.is_done(ctx.sess) {
+            step.restore(&mut ctx);
+            continue;
+        }
+
+        let skippable = !steps.iter().any(|s| s.depends_on().contains(&step.id()));
+        let step_started = Instant::now();
+        recover(step.id(), skippable, || {
+            plan::begin_apply(step.id());
+            step.run(&mut ctx)
+        })?;
+        report::timing(step.id(), step_started.elapsed());
+
+        ctx.sess.complete(step.id(), n);
+        ctx.sess.save().ok();
+    }
+
+    Ok(())
+}
+
+// ── Step boundaries ───────────────────────────────────────────────────────────
+
+/// Stops if Ctrl-C was pressed, reports any new kernel I/O errors, prints
+/// the step header and, in non-interactive mode, arms the command deadline
+/// for this step from the `[timeouts]` section of the answer file:
+///
+/// ```toml
+/// [timeouts]
+/// step   = 1800   # default limit for every step, in seconds
+/// base   = 3600   # per-step override, keyed by step id
+/// global = 7200   # limit for the whole run
+/// ```
+fn begin_step(
+    n: u8,
+    total: u8,
+    id: &str,
+    title: &str,
+    started: Instant,
+) -> Result<(), InstallerError> {
+    interrupt::check()?;
+    health::check();
+    ui::print_step(n, total, title);
+    log::line(&format!("== step {}/{}: {}", n, total, title));
+    if cmd::dry_run() {
+        plan::step(title);
+    }
+
+    let Some(a) = answers::get() else { return Ok(()) };
+    let secs = |key: &str| a.int(key).filter(|s| *s > 0).map(|s| s as u64);
+
+    let step = secs(&format!("timeouts.{}", id))
+        .or_else(|| secs("timeouts.step"))
+        .map(|s| {
+            let at = Instant::now() + Duration::from_secs(s);
+            (at, format!("step '{}' limit of {}s", id, s))
+        });
+    let global = secs("timeouts.global")
+        .map(|s| (started + Duration::from_secs(s), format!("global limit of {}s", s)));
+
+    cmd::set_deadline(match (step, global) {
+        (Some(s), Some(g)) => Some(if g.0 < s.0 { g } else { s }),
+        (s, g) => s.or(g),
+    });
+    Ok(())
+}
+
+// ── Step recovery ─────────────────────────────────────────────────────────────
+
+/// Runs one piece of work. If it fails with something a human can fix,
+/// offers to retry it, open a shell first, skip it (when `skippable`) or
+/// abort. Aborting returns `Cancelled`, so an enclosing `recover` doesn't
+/// ask again. Non-interactive runs retry as the `[retry]` policy allows,
+/// then fail; unrecoverable errors fail straight away.
+fn recover(
+    id: &str,
+    skippable: bool,
+    mut body: impl FnMut() -> Result<(), InstallerError>,
+) -> Result<(), InstallerError> {
+    let mut attempt = 0;
+    loop {
+        let err = match body() {
+            Ok(()) => return Ok(()),
+            Err(e) if !e.is_recoverable() => return Err(e),
+            Err(e) if answers::unattended() => {
+                attempt += 1;
+                auto_retry(id, e, attempt)?;
+                continue;
+            }
+            Err(e) => e,
+        };
+
+        println!();
+        ui::print_error(&format!("Step '{}' failed: {}", id, err));
+        log::line(&format!("! step '{}' failed: {}", id, err));
+
+        loop {
+            let mut items = vec![
+                "Retry the step".to_string(),
+                "Open a shell to fix things, then come back here".to_string(),
+            ];
+            let mut values = vec!["retry", "shell"];
+            if skippable {
+                items.push("Skip this step and continue".to_string());
+                values.push("skip");
+            }
+            items.push("Abort the installation".to_string());
+            values.push("abort");
+
+            println!();
+            let idx = prompt::select("on_step_failure", "What now?", &items, &values, 0)?;
+            match values[idx] {
+                "retry" => break,
+                "shell" => {
+                    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+                    ui::print_info("Type 'exit' to return to the installer.");
+                    if let Err(e) = cmd::run_interactive(&shell, &[]) {
+                        ui::print_warning(&format!("{}", e));
+                    }
+                }
+                "skip" => {
+                    log::line(&format!("skipped step '{}'", id));
+                    ui::print_warning(&format!("Skipped step '{}'.", id));
+                    return Ok(());
+                }
+                _ => return Err(InstallerError::Cancelled),
+            }
+        }
+    }
+}
+
+// ── Unattended retry policy ───────────────────────────────────────────────────
+
+/// What happens to the checkpoint when an unattended run gives up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnFailure {
+    /// Keep it and exit with `EXIT_RESUMABLE`; a re-run continues.
+    Resume,
+    /// Remove it; the next run starts over from the first step.
+    Restart,
+}
+
+/// The `[retry]` section of the answer file:
+///
+/// ```toml
+/// [retry]
+/// attempts    = 2          # automatic retries per failed step (default 0)
+/// backoff     = 30         # seconds before the first retry, doubled after each
+/// max_backoff = 600
+/// on_failure  = "resume"   # resume | restart
+/// ```
+///
+/// Retries never outlast the step or global deadline from `[timeouts]`.
+#[derive(Debug, Clone, Copy)]
+struct RetryPolicy {
+    attempts: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+    on_failure: OnFailure,
+}
+
+static POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+impl RetryPolicy {
+    fn from_answers() -> Result<Self, InstallerError> {
+        let a = answers::get();
+        let secs = |key: &str, default: u64| {
+            a.and_then(|a| a.int(key)).map_or(default, |s| s.max(0) as u64)
+        };
+        let on_failure = match a.and_then(|a| a.str("retry.on_failure")) {
+            None | Some("resume") => OnFailure::Resume,
+            Some("restart") => OnFailure::Restart,
+            Some(other) => {
+                return Err(InstallerError::Config(format!(
+                    "invalid value '{}' for 'retry.on_failure' (resume | restart)",
+                    other
+                )))
+            }
+        };
+
+        Ok(RetryPolicy {
+            attempts: secs("retry.attempts", 0) as u32,
+            backoff: Duration::from_secs(secs("retry.backoff", 30)),
+            max_backoff: Duration::from_secs(secs("retry.max_backoff", 600)),
+            on_failure,
+        })
+    }
+}
+
+/// The policy for a run that failed; `Resume` if the steps never started.
+pub fn on_failure() -> OnFailure {
+    POLICY.get().map_or(OnFailure::Resume, |p| p.on_failure)
+}
+
+/// Waits out the back-off before retry number `attempt`, or returns `err`
+/// when the attempts are used up or the wait would hit a deadline.
+fn auto_retry(id: &str, err: InstallerError, attempt: u32) -> Result<(), InstallerError> {
+    let Some(policy) = POLICY.get() else { return Err(err) };
+    if attempt > policy.attempts {
+        return Err(err);
+    }
+
+    println!();
+    ui::print_error(&format!("Step '{}' failed: {}", id, err));
+    log::line(&format!("! step '{}' failed (attempt {}): {}", id, attempt, err));
+
+    let wait = policy
+        .backoff
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(policy.max_backoff);
+    if cmd::time_left().is_some_and(|left| left <= wait) {
+        ui::print_warning("Not retrying — the time limit would pass during the back-off.");
+        return Err(err);
+    }
+
+    ui::print_info(&format!(
+        "Retrying in {}s (attempt {} of {}).",
+        wait.as_secs(),
+        attempt,
+        policy.attempts
+    ));
+    let until = Instant::now() + wait;
+    while Instant::now() < until {
+        interrupt::check()?;
+        thread::sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
+// ── Steps ─────────────────────────────────────────────────────────────────────
+
+struct Uefi;
+
+impl Step for Uefi {
+    fn id(&self) -> &'static str { "uefi" }
+    fn title(&self) -> &'static str { "System Mode Detection" }
+
+    fn run(&self, _ctx: &mut Context) -> Result<(), InstallerError> {
+        steps::uefi::check()
+    }
+}
+
+struct Partition;
+
+impl Step for Partition {
+    fn id(&self) -> &'static str { "partition" }
+    fn title(&self) -> &'static str { "Disk Partitioning" }
+    fn depends_on(&self) -> &'static [&'static str] { &["uefi"] }
+
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        ctx.sess.disk = Some(steps::partition::run()?);
+        ctx.sess.portable = steps::portable::enabled();
+        Ok(())
+    }
+
+    fn restore(&self, ctx: &mut Context) {
+        steps::portable::set(ctx.sess.portable);
+        let disk = ctx.sess.disk.as_deref().unwrap_or_default();
+        ui::print_success(&format!("Already completed — disk: {}.", disk));
+    }
+}
+
+struct Review;
+
+impl Step for Review {
+    fn id(&self) -> &'static str { "review" }
+    fn title(&self) -> &'static str { "Review" }
+    fn depends_on(&self) -> &'static [&'static str] { &["partition"] }
+
+    /// Checkpoints from before the review screen existed may be past
+    /// formatting without it; asking again would replace the formatted layout.
+    fn is_done(&self, sess: &Session) -> bool {
+        sess.is_done("review") || sess.is_done("format")
+    }
+
+    /// The layout is kept in the session for the format step, which then
+    /// formats it without asking for the roles again.
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        let reviewed = steps::review::run(ctx.sess.disk.as_deref().unwrap_or_default())?;
+        ctx.sess.set_config(&reviewed.layout);
+        ctx.sess.presets = reviewed.presets;
+        ctx.config = Some(reviewed.layout);
+        Ok(())
+    }
+
+    fn restore(&self, ctx: &mut Context) {
+        steps::review::apply(&ctx.sess.presets);
+        ui::print_success("Already completed — reviewed answers restored.");
+    }
+}
+
+struct Format;
+
+impl Step for Format {
+    fn id(&self) -> &'static str { "format" }
+    fn title(&self) -> &'static str { "Partition Formatting" }
+    fn depends_on(&self) -> &'static [&'static str] { &["review"] }
+
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        let mut c = ctx.config().clone();
+        steps::format::confirm_layout(&c)?;
+        steps::format::run(ctx.cmd, &c)?;
+        c.resolve_uuids();
+        ctx.sess.set_config(&c);
+        ctx.config = Some(c);
+        Ok(())
+    }
+
+    fn restore(&self, ctx: &mut Context) {
+        let c = ctx.config();
+        ui::print_success(&format!(
+            "Already completed — EFI: {}  swap: {}  root: {}  /var: {}.",
+            c.efi_partition,
+            c.swap_partition.as_deref().unwrap_or("none"),
+            c.root_partition,
+            c.var_partition.as_deref().unwrap_or("on root"),
+        ));
+    }
+}
+
+struct Mount;
+
+impl Step for Mount {
+    fn id(&self) -> &'static str { "mount" }
+    fn title(&self) -> &'static str { "Mounting Partitions" }
+    fn depends_on(&self) -> &'static [&'static str] { &["format"] }
+
+    /// Always runs — it keeps mounts that survived from an earlier run and
+    /// redoes the rest.
+    fn is_done(&self, _sess: &Session) -> bool {
+        false
+    }
+
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        steps::mount::run(ctx.cmd, ctx.config())
+    }
+}
+
+struct Ntp;
+
+impl Step for Ntp {
+    fn id(&self) -> &'static str { "ntp" }
+    fn title(&self) -> &'static str { "Time Synchronization" }
+
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        ctx.sess.time_daemon = Some(steps::ntp::run()?);
+        Ok(())
+    }
+}
+
+struct Base;
+
+impl Step for Base {
+    fn id(&self) -> &'static str { "base" }
+    fn title(&self) -> &'static str { "Base System Installation" }
+    fn depends_on(&self) -> &'static [&'static str] { &["mount"] }
+
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        health::power()?;
+        let haveged = steps::entropy::check()?;
+        ctx.sess.seat = Some(steps::packages::install_base()?);
+        if haveged {
+            steps::entropy::install_in_target()?;
+        }
+        Ok(())
+    }
+}
+
+struct Kernel;
+
+impl Step for Kernel {
+    fn id(&self) -> &'static str { "kernel" }
+    fn title(&self) -> &'static str { "Kernel Installation" }
+    fn depends_on(&self) -> &'static [&'static str] { &["base"] }
+
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        let kernels = steps::packages::ask_kernels()?;
+        steps::packages::install_kernels(&kernels)?;
+        match ctx.config().root_fs {
+            RootFs::Btrfs => steps::btrfs::install_in_target()?,
+            RootFs::Bcachefs => steps::packages::install_bcachefs(&kernels)?,
+            RootFs::Zfs => steps::zfs::install_in_target(&kernels)?,
+            RootFs::Ext4 => {}
+        }
+        ctx.sess.kernels = kernels;
+        Ok(())
+    }
+
+    fn restore(&self, ctx: &mut Context) {
+        let names: Vec<&str> = ctx.sess.kernels.iter().map(|k| k.display_name()).collect();
+        ui::print_success(&format!(
+            "Already completed — kernel: {}.",
+            if names.is_empty() { "unknown".to_string() } else { names.join(", ") },
+        ));
+    }
+}
+
+struct Desktop;
+
+impl Step for Desktop {
+    fn id(&self) -> &'static str { "desktop" }
+    fn title(&self) -> &'static str { "Desktop Environment" }
+    fn depends_on(&self) -> &'static [&'static str] { &["base"] }
+
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        steps::packages::install_desktop(ctx.sess.seat.unwrap_or(SeatManager::Elogind))
+    }
+}
+
+struct Makepkg;
+
+impl Step for Makepkg {
+    fn id(&self) -> &'static str { "makepkg" }
+    fn title(&self) -> &'static str { "Build Optimization" }
+    fn depends_on(&self) -> &'static [&'static str] { &["base"] }
+
+    fn run(&self, _ctx: &mut Context) -> Result<(), InstallerError> {
+        steps::makepkg::run()
+    }
+}
+
+struct Verify;
+
+impl Step for Verify {
+    fn id(&self) -> &'static str { "verify" }
+    fn title(&self) -> &'static str { "Post-install Verification" }
+    fn depends_on(&self) -> &'static [&'static str] { &["kernel"] }
+
+    fn run(&self, _ctx: &mut Context) -> Result<(), InstallerError> {
+        steps::verify::run()
+    }
+}
+
+struct Bootloader;
+
+impl Step for Bootloader {
+    fn id(&self) -> &'static str { "bootloader" }
+    fn title(&self) -> &'static str { "Bootloader" }
+    fn depends_on(&self) -> &'static [&'static str] { &["kernel"] }
+
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        let efi = ctx.config().efi_partition.clone();
+        steps::bootloader::run(&ctx.sess.kernels, &efi)
+    }
+}
+
+struct Final;
+
+impl Step for Final {
+    fn id(&self) -> &'static str { "final" }
+    fn title(&self) -> &'static str { "Final Setup" }
+    fn depends_on(&self) -> &'static [&'static str] { &["base"] }
+
+    fn is_done(&self, _sess: &Session) -> bool {
+        false
+    }
+
+    /// Each part recovers on its own: re-running fstab generation after a
+    /// failed chroot would append the entries twice.
+    fn run(&self, ctx: &mut Context) -> Result<(), InstallerError> {
+        let config = ctx.config().clone();
+        let cmd = ctx.cmd;
+        let kernels = ctx.sess.kernels.clone();
+        let time_daemon = ctx.sess.time_daemon.unwrap_or(TimeDaemon::Ntpd);
+        let seat = ctx.sess.seat.unwrap_or(SeatManager::Elogind);
+        recover("identity", false, steps::identity::run)?;
+        recover("locale", true, steps::locale::run)?;
+        recover("console font", true, steps::locale::install_font)?;
+        let mut user = None;
+        recover("users", true, || {
+            user = steps::users::run(seat)?;
+            Ok(())
+        })?;
+        recover("dotfiles", true, || steps::dotfiles::run(user.as_deref()))?;
+        recover("network", true, steps::network::run)?;
+        let mut ssh = false;
+        recover("ssh", true, || {
+            ssh = steps::ssh::run(user.as_deref())?;
+            Ok(())
+        })?;
+        recover("firewall", true, || steps::firewall::run(ssh))?;
+        recover("time", true, || steps::ntp::install_in_target(time_daemon))?;
+        recover("initramfs", true, || steps::initramfs::run(&config))?;
+        recover("accessibility", true, steps::accessibility::run)?;
+        recover("graphics", true, || steps::graphics::run(&kernels))?;
+        recover("power", true, steps::power::run)?;
+        recover("pacman", true, steps::pacman::ignore)?;
+        recover("fstab", false, || steps::fstab::generate(cmd, &config))?;
+        recover("maintenance", true, || steps::maintenance::run(&config))?;
+        recover("snapshots", true, || steps::btrfs::snapshots(&config))?;
+        recover("chroot", true, || steps::chroot::run(&config, &kernels, user.as_deref()))?;
+        steps::postinstall::menu()?;
+        recover("package cache", true, steps::packages::update_cache)?;
+
+        println!();
+        let packages = report::save_manifest();
+        let summary = report::render(ctx.sess, &Ok(()), ctx.started.elapsed());
+        report::save_to_target(&summary, &packages);
+        profile::save_to_target(&config);
+        if steps::mount::unmount_all() {
+            ui::print_success("All filesystems unmounted — reboot when you are ready.");
+        } else {
+            ui::print_warning("Some filesystems are still in use — run: umount -R /mnt && reboot");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,643 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+use console::style;
+
+use crate::{
+    error::InstallerError,
+    log,
+    prompt::{self, Given},
+    ui,
+};
+
+/// Most lines of a new file's content shown in the plan.
+const MAX_FILE_LINES: usize = 40;
+
+/// Where `--plan` saves the plan for `--apply`.
+const PLAN_FILE: &str = "/tmp/artix-installer.plan";
+
+/// The commands `--apply` has carried out in the current step, so an
+/// interrupted apply resumes after the last one.
+const APPLIED_FILE: &str = "/tmp/artix-installer.applied";
+
+/// Answers that belong to the run they were given in, not to the plan.
+const RUN_ONLY_KEYS: &[&str] = &["resume", "apply", "on_step_failure", "on_timeout"];
+
+/// Programs whose effect is gone after a teardown or reboot (mounts, open
+/// LUKS volumes, imported pools, the console font), so a resumed apply
+/// runs them again instead of skipping them.
+const LIVE_STATE: &[&str] = &[
+    "mount",
+    "umount",
+    "swapon",
+    "swapoff",
+    "modprobe",
+    "setfont",
+    "loadkeys",
+    "vgchange",
+    "cryptsetup open",
+    "zpool import",
+    "zpool export",
+    "zfs mount",
+];
+
+/// One planned change, recorded while a dry run walks the steps.
+#[derive(Debug, Clone)]
+enum Entry {
+    Step(String),
+    Command(String),
+    Edit { path: String, diff: String },
+    Write { path: String, content: String },
+}
+
+/// Everything the dry run would have done, in order.
+static PLAN: Mutex<Vec<Entry>> = Mutex::new(Vec::new());
+
+/// `--export-plan <FILE>`, if given.
+static OUTPUT: OnceLock<String> = OnceLock::new();
+
+/// `--plan`: print the numbered action list once the planning run ends.
+static SHOW: AtomicBool = AtomicBool::new(false);
+
+/// Command-line options that shape the plan, saved with it.
+static OPTIONS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// The saved plan `--apply` is carrying out.
+static APPLYING: Mutex<Option<Applying>> = Mutex::new(None);
+
+// ── Recording ─────────────────────────────────────────────────────────────────
+//
+// Called from the dry-run paths only (`DryRunner`, `files`), so a real run
+// never accumulates anything.
+
+/// Starts a new section; later entries are listed under it.
+pub fn step(title: &str) {
+    push(Entry::Step(title.to_string()));
+}
+
+pub fn command(line: &str) {
+    push(Entry::Command(line.to_string()));
+}
+
+/// An approved edit of an existing file, as a unified diff.
+pub fn edit(path: &str, diff: &str) {
+    push(Entry::Edit { path: path.to_string(), diff: diff.to_string() });
+}
+
+/// A file write. Skipped right after an `edit` of the same path, which
+/// already shows the change.
+pub fn write(path: &str, content: &str) {
+    let mut plan = PLAN.lock().unwrap();
+    if matches!(plan.last(), Some(Entry::Edit { path: p, .. }) if p == path) {
+        return;
+    }
+    plan.push(Entry::Write { path: path.to_string(), content: content.to_string() });
+}
+
+fn push(entry: Entry) {
+    PLAN.lock().unwrap().push(entry);
+}
+
+// ── Export ────────────────────────────────────────────────────────────────────
+
+/// Sets where `export` writes the plan. Call once, before the steps run.
+pub fn init(path: &str) {
+    let _ = OUTPUT.set(path.to_string());
+}
+
+/// Writes the recorded plan to the `--export-plan` file — HTML when it ends
+/// in `.html`/`.htm`, Markdown otherwise. `complete` is `false` when the
+/// rehearsal stopped early. Failures are reported but never fatal.
+pub fn export(complete: bool) {
+    let Some(path) = OUTPUT.get() else { return };
+    let plan = PLAN.lock().unwrap();
+    let doc = if path.ends_with(".html") || path.ends_with(".htm") {
+        render_html(&plan, complete)
+    } else {
+        render_markdown(&plan, complete)
+    };
+
+    match fs::write(path, doc) {
+        Ok(()) => ui::print_success(&format!("Installation plan written to {}.", path)),
+        Err(e) => ui::print_warning(&format!("Could not write the plan to {}: {}", path, e)),
+    }
+}
+
+// ── Terminal ──────────────────────────────────────────────────────────────────
+
+/// Makes [`show`] print the plan. Call once, before the steps run.
+pub fn show_at_exit() {
+    SHOW.store(true, Ordering::SeqCst);
+}
+
+/// Prints the recorded plan as one numbered list of actions under the step
+/// headings, for `--plan`. Does nothing unless [`show_at_exit`] was called.
+pub fn show(complete: bool) {
+    if !SHOW.load(Ordering::SeqCst) {
+        return;
+    }
+    let plan = PLAN.lock().unwrap();
+    let actions = plan.iter().filter(|e| !matches!(e, Entry::Step(_))).count();
+
+    println!();
+    println!("  {}", style(format!("Installation plan — {} actions", actions)).bold());
+    let mut n = 0;
+    for (i, entry) in plan.iter().enumerate() {
+        let line = match entry {
+            Entry::Step(title) => {
+                if !is_empty_step(&plan, i) {
+                    println!();
+                    println!("  {}", style(title).cyan().bold());
+                }
+                continue;
+            }
+            Entry::Command(line) => format!("$ {}", line),
+            Entry::Edit { path, diff } => {
+                let count = |sign: char, skip: &str| {
+                    diff.lines().filter(|l| l.starts_with(sign) && !l.starts_with(skip)).count()
+                };
+                format!("edit   {}  (+{} −{})", path, count('+', "+++"), count('-', "---"))
+            }
+            Entry::Write { path, content } => {
+                format!("write  {}  ({} bytes)", path, content.len())
+            }
+        };
+        n += 1;
+        println!("  {:>4}  {}", n, line);
+    }
+    println!();
+    if !complete {
+        ui::print_warning("Incomplete: planning stopped before the last step.");
+        ui::print_info("Nothing was executed.");
+        return;
+    }
+    let saved = render_saved(&saved_answers(), &OPTIONS.lock().unwrap(), &plan);
+    match fs::write(PLAN_FILE, saved) {
+        Ok(()) => ui::print_info(&format!(
+            "Nothing was executed. The plan is saved in {} — run artix-installer --apply \
+             to carry it out.",
+            PLAN_FILE
+        )),
+        Err(e) => {
+            ui::print_warning(&format!("Could not save the plan to {}: {}", PLAN_FILE, e));
+        }
+    }
+}
+
+// ── Apply ─────────────────────────────────────────────────────────────────────
+//
+// `--plan` saves the answers it was given, the options that shape the run and
+// the planned actions. `--apply` presets those answers, so nothing is asked
+// again (except passwords, which are never saved), and runs the steps for
+// real. Each command is checked off against the plan; within a step, the
+// ones carried out are kept in `APPLIED_FILE`, so resuming an interrupted
+// step continues from the next action instead of repeating them.
+
+/// A saved plan, as read back by [`load`].
+#[derive(Debug, Default, PartialEq)]
+pub struct Saved {
+    pub answers: Vec<(String, String)>,
+    /// `--flag value` pairs, like `--cachedir /mnt/usb`.
+    pub options: Vec<(String, String)>,
+    pub commands: Vec<String>,
+    /// Commands, edits and new files together.
+    pub actions: usize,
+}
+
+/// Progress through the plan while `--apply` runs.
+#[derive(Debug, Default)]
+struct Applying {
+    commands: Vec<String>,
+    /// Index of the next planned command expected.
+    next: usize,
+    /// The step running, and the commands it has carried out so far.
+    step: String,
+    done: Vec<String>,
+    /// Commands an interrupted attempt at this step already carried out.
+    skip: VecDeque<String>,
+}
+
+/// Saves a command-line option along with the plan.
+pub fn option(flag: &str, value: &str) {
+    OPTIONS.lock().unwrap().push((flag.to_string(), value.to_string()));
+}
+
+/// Reads the plan `--plan` saved, presets its answers and starts checking
+/// commands off against it.
+pub fn load() -> Result<Saved, InstallerError> {
+    let text = fs::read_to_string(PLAN_FILE).map_err(|_| {
+        InstallerError::Config(format!("no saved plan in {} — run with --plan first", PLAN_FILE))
+    })?;
+    let saved = parse_saved(&text)?;
+    for (key, value) in &saved.answers {
+        prompt::preset(key, value);
+    }
+    *APPLYING.lock().unwrap() =
+        Some(Applying { commands: saved.commands.clone(), ..Applying::default() });
+    Ok(saved)
+}
+
+/// `true` while `--apply` carries out a saved plan.
+pub fn applying() -> bool {
+    APPLYING.lock().unwrap().is_some()
+}
+
+/// Forgets what an earlier apply carried out — for a run that starts from
+/// the first step again.
+pub fn reset_applied() {
+    let _ = fs::remove_file(APPLIED_FILE);
+}
+
+/// Called as step `id` starts (or is retried). When an interrupted apply
+/// stopped inside this step, the commands it already carried out are
+/// skipped as they come up again.
+pub fn begin_apply(id: &str) {
+    let mut applying = APPLYING.lock().unwrap();
+    let Some(a) = applying.as_mut() else { return };
+    let (step, done) = fs::read_to_string(APPLIED_FILE)
+        .map(|text| parse_applied(&text))
+        .unwrap_or_default();
+    a.skip = if step == id { done.into() } else { VecDeque::new() };
+    a.step = id.to_string();
+    a.done.clear();
+}
+
+/// `true` when `line` was already carried out by an interrupted apply of
+/// this step and can be skipped.
+pub fn already_applied(line: &str) -> bool {
+    let mut applying = APPLYING.lock().unwrap();
+    let Some(a) = applying.as_mut() else { return false };
+    if !take_skip(&mut a.skip, line) {
+        return false;
+    }
+    a.expect(line);
+    a.done.push(line.to_string());
+    save_applied(a);
+    ui::print_info(&format!("Already applied: {}", line));
+    true
+}
+
+/// Called before `line` runs: shows where it is in the plan, and warns
+/// about a command the plan didn't have (a result only known on the real
+/// machine led somewhere else).
+pub fn applying_command(line: &str) {
+    let mut applying = APPLYING.lock().unwrap();
+    let Some(a) = applying.as_mut() else { return };
+    match a.expect(line) {
+        Some(k) => println!("  {}", style(format!("[{}/{}]", k, a.commands.len())).dim()),
+        None => {
+            ui::print_warning(&format!("Not in the plan: {}", line));
+            log::line(&format!("! not in the plan: {}", line));
+        }
+    }
+}
+
+/// Called after `line` succeeded.
+pub fn applied(line: &str) {
+    let mut applying = APPLYING.lock().unwrap();
+    let Some(a) = applying.as_mut() else { return };
+    a.done.push(line.to_string());
+    save_applied(a);
+}
+
+/// Removes the plan and the progress once the apply has finished.
+pub fn finish_apply() {
+    if applying() {
+        let _ = fs::remove_file(PLAN_FILE);
+        reset_applied();
+    }
+}
+
+impl Applying {
+    /// Moves past `line` in the plan; its 1-based number, if planned.
+    fn expect(&mut self, line: &str) -> Option<usize> {
+        let found = self.commands[self.next..].iter().position(|c| c == line)?;
+        self.next += found + 1;
+        Some(self.next)
+    }
+}
+
+fn save_applied(a: &Applying) {
+    if let Err(e) = fs::write(APPLIED_FILE, render_applied(&a.step, &a.done)) {
+        log::line(&format!("! could not save apply progress: {}", e));
+    }
+}
+
+/// Pops `line` off the front of `skip` and says whether to skip it. A
+/// live-state command is run anyway; any other command breaks the match,
+/// and everything after it runs.
+fn take_skip(skip: &mut VecDeque<String>, line: &str) -> bool {
+    if skip.front().map(String::as_str) != Some(line) {
+        skip.clear();
+        return false;
+    }
+    skip.pop_front();
+    !LIVE_STATE.iter().any(|p| {
+        line.strip_prefix(p).is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+    })
+}
+
+/// The answers to save with the plan, as [`prompt::preset`] takes them.
+fn saved_answers() -> Vec<(String, String)> {
+    prompt::given()
+        .into_iter()
+        .filter(|(key, _)| !RUN_ONLY_KEYS.contains(&key.as_str()))
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Given::Bool(b) => if b { "yes" } else { "no" }.to_string(),
+                Given::Text(t) => t,
+                Given::List(l) => l.join(","),
+                Given::Secret => return None,
+            };
+            Some((key, value))
+        })
+        .collect()
+}
+
+fn render_saved(
+    answers: &[(String, String)],
+    options: &[(String, String)],
+    plan: &[Entry],
+) -> String {
+    let mut out = String::new();
+    for (key, value) in answers {
+        out.push_str(&format!("answer {}={}\n", key, encode(value)));
+    }
+    for (flag, value) in options {
+        out.push_str(&format!("option {} {}\n", flag, encode(value)));
+    }
+    for entry in plan {
+        let line = match entry {
+            Entry::Step(title) => format!("step {}", encode(title)),
+            Entry::Command(line) => format!("run {}", encode(line)),
+            Entry::Edit { path, .. } => format!("edit {}", encode(path)),
+            Entry::Write { path, .. } => format!("write {}", encode(path)),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+fn parse_saved(text: &str) -> Result<Saved, InstallerError> {
+    let mut saved = Saved::default();
+    for line in text.lines().filter(|l| !l.is_empty()) {
+        let bad = || InstallerError::Config(format!("unreadable line in {}: {}", PLAN_FILE, line));
+        let (kind, rest) = line.split_once(' ').ok_or_else(bad)?;
+        match kind {
+            "answer" => {
+                let (key, value) = rest.split_once('=').ok_or_else(bad)?;
+                saved.answers.push((key.to_string(), decode(value)));
+            }
+            "option" => {
+                let (flag, value) = rest.split_once(' ').ok_or_else(bad)?;
+                saved.options.push((flag.to_string(), decode(value)));
+            }
+            "run" => {
+                saved.commands.push(decode(rest));
+                saved.actions += 1;
+            }
+            "edit" | "write" => saved.actions += 1,
+            "step" => {}
+            _ => return Err(bad()),
+        }
+    }
+    Ok(saved)
+}
+
+fn render_applied(step: &str, done: &[String]) -> String {
+    let mut out = format!("step={}\n", step);
+    for line in done {
+        out.push_str(&encode(line));
+        out.push('\n');
+    }
+    out
+}
+
+fn parse_applied(text: &str) -> (String, Vec<String>) {
+    let mut lines = text.lines();
+    let step = lines.next().and_then(|l| l.strip_prefix("step=")).unwrap_or_default();
+    (step.to_string(), lines.map(decode).collect())
+}
+
+/// One value per line: backslashes and newlines are escaped.
+fn encode(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn decode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn render_markdown(plan: &[Entry], complete: bool) -> String {
+    let mut blocks = vec![
+        "# Artix Linux installation plan".to_string(),
+        "Recorded by `artix-installer --dry-run`. Nothing below has been executed.".to_string(),
+    ];
+    if !complete {
+        blocks.push("> **Incomplete:** the rehearsal stopped before the last step.".to_string());
+    }
+
+    let mut n = 0;
+    for (i, entry) in plan.iter().enumerate() {
+        match entry {
+            Entry::Step(title) => {
+                n += 1;
+                blocks.push(format!("## {}. {}", n, title));
+                if is_empty_step(plan, i) {
+                    blocks.push("_No changes._".to_string());
+                }
+            }
+            // Consecutive commands form one list.
+            Entry::Command(line) => {
+                let item = format!("- `{}`", line);
+                match (i.checked_sub(1).map(|p| &plan[p]), blocks.last_mut()) {
+                    (Some(Entry::Command(_)), Some(list)) => {
+                        list.push('\n');
+                        list.push_str(&item);
+                    }
+                    _ => blocks.push(item),
+                }
+            }
+            Entry::Edit { path, diff } => {
+                blocks.push(format!("Edit `{}`:", path));
+                blocks.push(format!("```diff\n{}\n```", diff.trim_end()));
+            }
+            Entry::Write { path, content } => {
+                blocks.push(format!("Write `{}` ({} bytes):", path, content.len()));
+                blocks.push(format!("```\n{}\n```", excerpt(content)));
+            }
+        }
+    }
+
+    let mut out = blocks.join("\n\n");
+    out.push('\n');
+    out
+}
+
+fn render_html(plan: &[Entry], complete: bool) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Artix Linux installation plan</title>\n\
+         <style>body{font-family:sans-serif;max-width:60em;margin:auto}\
+         pre,code{background:#f4f4f4}pre{padding:.5em;overflow-x:auto}</style>\n\
+         </head>\n<body>\n<h1>Artix Linux installation plan</h1>\n\
+         <p>Recorded by <code>artix-installer --dry-run</code>. \
+         Nothing below has been executed.</p>\n",
+    );
+    if !complete {
+        out.push_str(
+            "<p><strong>Incomplete:</strong> the rehearsal stopped before the last step.</p>\n",
+        );
+    }
+
+    let mut n = 0;
+    let mut in_list = false;
+    for (i, entry) in plan.iter().enumerate() {
+        let is_command = matches!(entry, Entry::Command(_));
+        if in_list && !is_command {
+            out.push_str("</ul>\n");
+            in_list = false;
+        } else if !in_list && is_command {
+            out.push_str("<ul>\n");
+            in_list = true;
+        }
+
+        match entry {
+            Entry::Step(title) => {
+                n += 1;
+                out.push_str(&format!("<h2>{}. {}</h2>\n", n, escape(title)));
+                if is_empty_step(plan, i) {
+                    out.push_str("<p><em>No changes.</em></p>\n");
+                }
+            }
+            Entry::Command(line) => {
+                out.push_str(&format!("<li><code>{}</code></li>\n", escape(line)));
+            }
+            Entry::Edit { path, diff } => out.push_str(&format!(
+                "<p>Edit <code>{}</code>:</p>\n<pre>{}</pre>\n",
+                escape(path),
+                escape(diff.trim_end())
+            )),
+            Entry::Write { path, content } => out.push_str(&format!(
+                "<p>Write <code>{}</code> ({} bytes):</p>\n<pre>{}</pre>\n",
+                escape(path),
+                content.len(),
+                escape(&excerpt(content))
+            )),
+        }
+    }
+    if in_list {
+        out.push_str("</ul>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// `true` when the step heading at `i` has nothing recorded under it.
+fn is_empty_step(plan: &[Entry], i: usize) -> bool {
+    matches!(plan.get(i + 1), None | Some(Entry::Step(_)))
+}
+
+/// The first `MAX_FILE_LINES` lines of `content`, noting what was cut.
+fn excerpt(content: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() <= MAX_FILE_LINES {
+        return content.trim_end().to_string();
+    }
+    format!(
+        "{}\n… ({} more lines)",
+        lines[..MAX_FILE_LINES].join("\n"),
+        lines.len() - MAX_FILE_LINES
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect()
+    }
+
+    #[test]
+    fn saved_plan_reads_back() {
+        let answers = owned(&[("hostname", "host42"), ("packages", "vim,git")]);
+        let options = owned(&[("--cachedir", "/mnt/usb cache")]);
+        let plan = vec![
+            Entry::Step("Format".to_string()),
+            Entry::Command("mkfs.ext4 -F /dev/sda2".to_string()),
+            Entry::Edit { path: "/mnt/etc/fstab".to_string(), diff: "+a\n-b".to_string() },
+            Entry::Command("sh -c echo 'a\\b'\nnext".to_string()),
+            Entry::Write { path: "/mnt/etc/hostname".to_string(), content: "x".to_string() },
+        ];
+
+        let saved = parse_saved(&render_saved(&answers, &options, &plan)).unwrap();
+        assert_eq!(saved.answers, answers);
+        assert_eq!(saved.options, options);
+        assert_eq!(saved.commands, ["mkfs.ext4 -F /dev/sda2", "sh -c echo 'a\\b'\nnext"]);
+        assert_eq!(saved.actions, 4);
+    }
+
+    #[test]
+    fn garbled_plan_is_refused() {
+        assert!(parse_saved("answer hostname=a\nrun\n").is_err());
+        assert!(parse_saved("delete /dev/sda\n").is_err());
+    }
+
+    #[test]
+    fn applied_progress_reads_back() {
+        let done = vec!["mkfs.fat -F32 /dev/sda1".to_string(), "a\\nb".to_string()];
+        let (step, read) = parse_applied(&render_applied("format", &done));
+        assert_eq!(step, "format");
+        assert_eq!(read, done);
+    }
+
+    #[test]
+    fn resumed_step_skips_only_what_was_done() {
+        let mut skip: VecDeque<String> =
+            ["mkfs.fat -F32 /dev/sda1", "mount /dev/sda2 /mnt", "mkfs.ext4 -F /dev/sda3"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+
+        assert!(take_skip(&mut skip, "mkfs.fat -F32 /dev/sda1"));
+        // Mounts are gone after the teardown, so they run again.
+        assert!(!take_skip(&mut skip, "mount /dev/sda2 /mnt"));
+        // Past the first difference, everything runs.
+        assert!(!take_skip(&mut skip, "mkfs.btrfs -f /dev/sda3"));
+        assert!(!take_skip(&mut skip, "mkfs.ext4 -F /dev/sda3"));
+        assert!(skip.is_empty());
+    }
+
+    #[test]
+    fn live_state_matches_whole_words() {
+        let mut skip: VecDeque<String> =
+            ["mountpoint -q /mnt".to_string(), "cryptsetup open /dev/sda2 root".to_string()].into();
+        assert!(take_skip(&mut skip, "mountpoint -q /mnt"));
+        assert!(!take_skip(&mut skip, "cryptsetup open /dev/sda2 root"));
+    }
+}
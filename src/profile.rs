@@ -0,0 +1,235 @@
+use std::{fs, path::Path};
+
+use crate::{
+    answers::{self, Answers},
+    cmd,
+    config::Config,
+    error::InstallerError,
+    files,
+    prompt::{self, Given},
+    ui,
+};
+
+/// Saved answer files, one `<name>.toml` each.
+pub const DIR: &str = "/etc/artix-installer/profiles";
+
+/// How the installed system was configured, as an answer file.
+const TARGET_CONFIG: &str = "/mnt/etc/artix-installer/config.toml";
+
+/// Prompts that steer the wizard itself rather than the system it builds.
+const NOT_SAVED: &[&str] = &[
+    "profile", "resume", "review", "cfdisk", "apply_edits", "fstab_edit", "locale.adjust",
+    "chroot.menu", "post_install", "extra_packages",
+];
+
+// ── Commands ──────────────────────────────────────────────────────────────────
+
+/// `profile save <name> <file>`: checks that `file` parses as an answer
+/// file and stores it as profile `name`, replacing an older one.
+pub fn save(name: &str, file: &str) -> Result<(), InstallerError> {
+    check_name(name)?;
+    let content = fs::read_to_string(file)?;
+    Answers::parse(&content).map_err(|e| InstallerError::Config(format!("{}: {}", file, e)))?;
+
+    fs::create_dir_all(DIR)?;
+    let path = path_of(name);
+    files::write_atomic(&path, &content)?;
+    ui::print_success(&format!("Profile '{}' saved to {}.", name, path));
+    Ok(())
+}
+
+/// `profile load <name>`: the answer file to run with, like `--config`.
+pub fn load(name: &str) -> Result<String, InstallerError> {
+    check_name(name)?;
+    let path = path_of(name);
+    if !Path::new(&path).is_file() {
+        let known = list();
+        let hint = if known.is_empty() { "none saved".to_string() } else { known.join(", ") };
+        return Err(InstallerError::Config(format!(
+            "no profile '{}' in {} ({})",
+            name, DIR, hint
+        )));
+    }
+    Ok(path)
+}
+
+/// `profile list`: prints the saved profile names.
+pub fn print_list() {
+    let names = list();
+    if names.is_empty() {
+        ui::print_info(&format!("No profiles in {}.", DIR));
+    }
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+// ── Provenance ────────────────────────────────────────────────────────────────
+
+/// Writes every answer of this run to `/etc/artix-installer/config.toml` in
+/// the target, as an answer file that rebuilds the system (`--config`, or
+/// `profile save`). The partition roles come from `config`, each with its
+/// UUID; passwords appear only as commented placeholders and `format` stays
+/// commented out. Never fatal.
+pub fn save_to_target(config: &Config) {
+    let content = render(config);
+    let result = (|| -> Result<(), InstallerError> {
+        if !cmd::dry_run() {
+            fs::create_dir_all("/mnt/etc/artix-installer")?;
+        }
+        files::write_atomic(TARGET_CONFIG, &content)
+    })();
+    let path = TARGET_CONFIG.trim_start_matches("/mnt");
+    match result {
+        Ok(()) => ui::print_success(&format!("Install configuration saved to {}.", path)),
+        Err(e) => ui::print_warning(&format!("Could not save {}: {}", path, e)),
+    }
+}
+
+fn render(config: &Config) -> String {
+    let mut out = String::from("# How this system was installed, saved by artix-installer.\n");
+    out.push_str("# Feed it back with --config, or: artix-installer profile save <NAME> <FILE>\n");
+    let roles = [
+        ("efi", Some(&config.efi_partition), &config.efi_uuid),
+        ("swap", config.swap_partition.as_ref(), &config.swap_uuid),
+        ("root", Some(&config.root_partition), &config.root_uuid),
+        ("var", config.var_partition.as_ref(), &config.var_uuid),
+    ];
+    out.push('\n');
+    for (role, device, uuid) in roles {
+        let value = answers::quote(device.map_or("none", String::as_str));
+        match uuid {
+            Some(uuid) => out.push_str(&format!("{:<4} = {}   # UUID={}\n", role, value, uuid)),
+            None => out.push_str(&format!("{:<4} = {}\n", role, value)),
+        }
+    }
+    if let (Some(home), uuid) = (&config.home_partition, &config.home_uuid) {
+        let uuid = uuid.as_deref().unwrap_or("?");
+        out.push_str(&format!("# /home kept from the previous install: {}  UUID={}\n", home, uuid));
+    }
+
+    let given: Vec<(String, Given)> = prompt::given()
+        .into_iter()
+        .filter(|(key, _)| !NOT_SAVED.contains(&key.as_str()) && !key.ends_with("_now"))
+        .filter(|(key, _)| !["efi", "swap", "root", "var"].contains(&key.as_str()))
+        .collect();
+    // Top-level keys first: TOML puts everything after a header in its section.
+    let mut sections: Vec<&str> = vec![""];
+    for (key, _) in &given {
+        let section = key.split_once('.').map_or("", |(s, _)| s);
+        if !sections.contains(&section) {
+            sections.push(section);
+        }
+    }
+    for section in sections {
+        if !section.is_empty() {
+            out.push_str(&format!("\n[{}]\n", section));
+        }
+        for (key, value) in &given {
+            let (s, name) = key.split_once('.').unwrap_or(("", key));
+            if s != section {
+                continue;
+            }
+            let line = match value {
+                Given::Bool(b) => format!("{} = {}", name, b),
+                Given::Text(t) => format!("{} = {}", name, answers::quote(t)),
+                Given::List(l) => {
+                    let items: Vec<&str> = l.iter().map(String::as_str).collect();
+                    format!("{} = {}", name, answers::list(&items))
+                }
+                Given::Secret => format!("# {} = \"…\"   # redacted", name),
+            };
+            if key == "format" {
+                out.push_str(&format!("# {}   # destructive: opt in\n", line));
+            } else {
+                out.push_str(&format!("{}\n", line));
+            }
+        }
+    }
+    out
+}
+
+// ── Start-up choice ───────────────────────────────────────────────────────────
+
+/// Without `--config`, offers the saved profiles before the first question.
+/// Returns the chosen answer file, or `None` to answer interactively (also
+/// when there are no profiles).
+pub fn pick() -> Result<Option<String>, InstallerError> {
+    let names = list();
+    if names.is_empty() {
+        return Ok(None);
+    }
+
+    let mut items: Vec<String> =
+        names.iter().map(|n| format!("{:<16}{}", n, summary(&path_of(n)))).collect();
+    items.push("None — answer every question".to_string());
+    let mut values: Vec<&str> = names.iter().map(String::as_str).collect();
+    values.push("none");
+
+    println!();
+    let ask = "Start from a saved profile?";
+    let idx = prompt::select("profile", ask, &items, &values, names.len())?;
+    Ok(names.get(idx).map(|n| path_of(n)))
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Profile names, sorted.
+fn list() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(DIR)
+        .map(|dir| {
+            dir.flatten()
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter_map(|f| f.strip_suffix(".toml").map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// The profile's first comment line, as a one-line description.
+fn summary(path: &str) -> String {
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let first = content.lines().next().unwrap_or_default().trim();
+    first.strip_prefix('#').unwrap_or_default().trim().to_string()
+}
+
+fn path_of(name: &str) -> String {
+    format!("{}/{}.toml", DIR, name)
+}
+
+/// Letters, digits, `-` and `_`, so a name can't point outside [`DIR`].
+fn check_name(name: &str) -> Result<(), InstallerError> {
+    let ok = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if ok {
+        Ok(())
+    } else {
+        Err(InstallerError::Usage(format!(
+            "profile names may only use letters, digits, '-' and '_' (got '{}')",
+            name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_config_parses_back() {
+        let root = r#"/dev/disk/by-id/usb-"Flash"\Disk-part3"#;
+        let mut config = Config::new("/dev/sda1".to_string(), None, root.to_string());
+        config.root_uuid = Some("1111-2222".to_string());
+        let motd = r#"Built with "care", \o/"#;
+        prompt::preset("test.motd", motd);
+        prompt::input("test.motd", "Message of the day", "").unwrap();
+
+        let answers = Answers::parse(&render(&config)).unwrap();
+        assert_eq!(answers.str("efi"), Some("/dev/sda1"));
+        assert_eq!(answers.str("swap"), Some("none"));
+        assert_eq!(answers.str("root"), Some(root));
+        assert_eq!(answers.str("test.motd"), Some(motd));
+    }
+}
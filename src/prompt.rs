@@ -0,0 +1,375 @@
+use std::{collections::HashMap, io, sync::Mutex};
+
+use dialoguer::{Confirm, Input, MultiSelect, Password, Select};
+
+use crate::{answers, error::InstallerError, ui};
+
+// ── Answer-aware prompts ──────────────────────────────────────────────────────
+//
+// Each prompt has a stable `key`. In interactive mode the user is asked as
+// usual, under a one-line key legend; in non-interactive mode (`--config`)
+// the answer is read from the answer file instead, falling back to the
+// prompt's default. A key answered on the review screen isn't asked again.
+
+/// Ctrl-C at a prompt saves the checkpoint and exits; a re-run resumes.
+const QUIT_KEY: (&str, &str) = ("Ctrl-C", "save & quit");
+
+/// Answers given ahead of time on the review screen, by prompt key. They
+/// take precedence over both the user and the answer file.
+static PRESET: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+/// Records the answer for `key`, so the prompt isn't shown again when its
+/// step runs. Confirmations take `"yes"` / `"no"`, menus one of their values.
+pub fn preset(key: &str, value: &str) {
+    PRESET
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(key.to_string(), value.to_string());
+}
+
+/// Drops the preset for `key`, so the prompt is shown again — for settings
+/// revisited after the main flow.
+pub fn forget(key: &str) {
+    if let Some(map) = PRESET.lock().unwrap().as_mut() {
+        map.remove(key);
+    }
+}
+
+/// An answer as given, for [`given`]. Secrets keep only the fact that one
+/// was given.
+#[derive(Debug, Clone)]
+pub enum Given {
+    Bool(bool),
+    Text(String),
+    List(Vec<String>),
+    Secret,
+}
+
+/// Every answer of this run by key, in the order first asked; a later
+/// answer to the same key replaces the earlier one.
+static GIVEN: Mutex<Vec<(String, Given)>> = Mutex::new(Vec::new());
+
+/// The answers given so far, presets and answer-file values included.
+pub fn given() -> Vec<(String, Given)> {
+    GIVEN.lock().unwrap().clone()
+}
+
+fn record(key: &str, value: Given) {
+    let mut given = GIVEN.lock().unwrap();
+    match given.iter_mut().find(|(k, _)| k == key) {
+        Some(entry) => entry.1 = value,
+        None => given.push((key.to_string(), value)),
+    }
+}
+
+fn preset_for(key: &str) -> Option<String> {
+    PRESET.lock().unwrap().as_ref()?.get(key).cloned()
+}
+
+/// Yes/no question. Unattended: `key = true|false`, else `default`.
+pub fn confirm(key: &str, prompt: &str, default: bool) -> Result<bool, InstallerError> {
+    let answer = ask_confirm(key, prompt, default)?;
+    record(key, Given::Bool(answer));
+    Ok(answer)
+}
+
+fn ask_confirm(key: &str, prompt: &str, default: bool) -> Result<bool, InstallerError> {
+    if let Some(v) = preset_for(key) {
+        log_answer(prompt, &v);
+        return Ok(v == "yes");
+    }
+    let Some(a) = answers::get() else {
+        if ui::accessible() {
+            return typed_confirm(prompt, default);
+        }
+        ui::print_legend(&[("y/n", "answer"), ("Enter", "default"), QUIT_KEY]);
+        return Confirm::new()
+            .with_prompt(ui::text(prompt))
+            .default(default)
+            .interact()
+            .map_err(prompt_error);
+    };
+
+    let answer = a.bool(key).unwrap_or(default);
+    log_answer(prompt, if answer { "yes" } else { "no" });
+    Ok(answer)
+}
+
+/// Arrow-key menu. Unattended: `key = "<value>"` where `<value>` is one of
+/// `values` (parallel to `items`), else `default`.
+pub fn select(
+    key: &str,
+    prompt: &str,
+    items: &[String],
+    values: &[&str],
+    default: usize,
+) -> Result<usize, InstallerError> {
+    let idx = ask_select(key, prompt, items, values, default)?;
+    record(key, Given::Text(values[idx].to_string()));
+    Ok(idx)
+}
+
+fn ask_select(
+    key: &str,
+    prompt: &str,
+    items: &[String],
+    values: &[&str],
+    default: usize,
+) -> Result<usize, InstallerError> {
+    if let Some(idx) = preset_for(key).and_then(|v| values.iter().position(|x| *x == v)) {
+        log_answer(prompt, values[idx]);
+        return Ok(idx);
+    }
+    let Some(a) = answers::get() else {
+        if ui::accessible() {
+            return numbered_select(prompt, items, default);
+        }
+        ui::print_legend(&[("↑/↓", "select"), ("Enter", "confirm"), QUIT_KEY]);
+        let items: Vec<String> = items.iter().map(|i| ui::text(i).into_owned()).collect();
+        return Select::new()
+            .with_prompt(ui::text(prompt))
+            .items(&items)
+            .default(default)
+            .interact()
+            .map_err(prompt_error);
+    };
+
+    let idx = match a.str(key) {
+        Some(v) => values
+            .iter()
+            .position(|x| *x == v)
+            .ok_or_else(|| InstallerError::Config(format!("invalid value '{}' for '{}'", v, key)))?,
+        None => default,
+    };
+    log_answer(prompt, values[idx]);
+    Ok(idx)
+}
+
+/// Checkbox menu; at least one item must be picked. Unattended: `key` as
+/// an array of `values` (a single string also works), else `defaults`.
+/// A preset lists the values comma-separated.
+pub fn multi_select(
+    key: &str,
+    prompt: &str,
+    items: &[String],
+    values: &[&str],
+    defaults: &[bool],
+) -> Result<Vec<usize>, InstallerError> {
+    let picked = ask_multi_select(key, prompt, items, values, defaults)?;
+    record(key, Given::List(picked.iter().map(|&i| values[i].to_string()).collect()));
+    Ok(picked)
+}
+
+fn ask_multi_select(
+    key: &str,
+    prompt: &str,
+    items: &[String],
+    values: &[&str],
+    defaults: &[bool],
+) -> Result<Vec<usize>, InstallerError> {
+    let lookup = |names: &[&str]| -> Result<Vec<usize>, InstallerError> {
+        names
+            .iter()
+            .map(|v| {
+                values.iter().position(|x| x == v).ok_or_else(|| {
+                    InstallerError::Config(format!("invalid value '{}' for '{}'", v, key))
+                })
+            })
+            .collect()
+    };
+    let picked = if let Some(v) = preset_for(key) {
+        lookup(&v.split(',').collect::<Vec<_>>())?
+    } else if let Some(a) = answers::get() {
+        match (a.list(key), a.str(key)) {
+            (Some(list), _) => lookup(&list.iter().map(String::as_str).collect::<Vec<_>>())?,
+            (None, Some(one)) => lookup(&[one])?,
+            (None, None) => (0..items.len()).filter(|&i| defaults[i]).collect(),
+        }
+    } else {
+        return loop {
+            let picked = if ui::accessible() {
+                numbered_multi_select(prompt, items, defaults)?
+            } else {
+                ui::print_legend(&[
+                    ("↑/↓", "move"),
+                    ("Space", "toggle"),
+                    ("Enter", "confirm"),
+                    QUIT_KEY,
+                ]);
+                let items: Vec<String> = items.iter().map(|i| ui::text(i).into_owned()).collect();
+                MultiSelect::new()
+                    .with_prompt(ui::text(prompt))
+                    .items(&items)
+                    .defaults(defaults)
+                    .interact()
+                    .map_err(prompt_error)?
+            };
+            if !picked.is_empty() {
+                break Ok(picked);
+            }
+            ui::print_warning("Pick at least one.");
+        };
+    };
+
+    if picked.is_empty() {
+        return Err(InstallerError::Config(format!("'{}' needs at least one value", key)));
+    }
+    let names: Vec<&str> = picked.iter().map(|&i| values[i]).collect();
+    log_answer(prompt, &names.join(", "));
+    Ok(picked)
+}
+
+/// Free-text input. Unattended: `key = "<text>"`, else `default`; an error
+/// if neither is available. Interactive input may be blank only when
+/// `default` is empty.
+pub fn input(key: &str, prompt: &str, default: &str) -> Result<String, InstallerError> {
+    let answer = ask_input(key, prompt, default)?;
+    record(key, Given::Text(answer.clone()));
+    Ok(answer)
+}
+
+fn ask_input(key: &str, prompt: &str, default: &str) -> Result<String, InstallerError> {
+    if let Some(v) = preset_for(key) {
+        log_answer(prompt, &v);
+        return Ok(v);
+    }
+    let Some(a) = answers::get() else {
+        if !ui::accessible() {
+            ui::print_legend(&[("Enter", "accept"), QUIT_KEY]);
+        }
+        let input = Input::<String>::new().with_prompt(ui::text(prompt));
+        // An empty default means "may be left blank".
+        let input = if default.is_empty() {
+            input.allow_empty(true)
+        } else {
+            input.default(default.to_string())
+        };
+        return input.interact_text().map_err(prompt_error);
+    };
+
+    let answer = a.str(key).unwrap_or(default);
+    if answer.is_empty() {
+        return Err(InstallerError::MissingAnswer(key.to_string()));
+    }
+    log_answer(prompt, answer);
+    Ok(answer.to_string())
+}
+
+/// Hidden input, asked twice. Unattended: `key = "<secret>"`; an error if
+/// missing. Never echoed or logged.
+pub fn password(key: &str, prompt: &str) -> Result<String, InstallerError> {
+    let secret = ask_password(key, prompt)?;
+    record(key, Given::Secret);
+    Ok(secret)
+}
+
+fn ask_password(key: &str, prompt: &str) -> Result<String, InstallerError> {
+    let Some(a) = answers::get() else {
+        return Password::new()
+            .with_prompt(ui::text(prompt))
+            .with_confirmation("Repeat", ui::text("The entries don't match — try again."))
+            .interact()
+            .map_err(prompt_error);
+    };
+
+    match a.str(key) {
+        Some(secret) if !secret.is_empty() => {
+            log_answer(prompt, "********");
+            Ok(secret.to_string())
+        }
+        _ => Err(InstallerError::MissingAnswer(key.to_string())),
+    }
+}
+
+// ── Accessible prompts ────────────────────────────────────────────────────────
+//
+// Arrow-key menus redraw in place, which screen readers can't follow. With
+// `--accessible` every question is a plain line answered by typing.
+
+/// `yes` / `no` typed out; Enter alone takes the default.
+fn typed_confirm(prompt: &str, default: bool) -> Result<bool, InstallerError> {
+    let answer = Input::<String>::new()
+        .with_prompt(format!("{} Type yes or no", ui::text(prompt)))
+        .default(if default { "yes" } else { "no" }.to_string())
+        .validate_with(|v: &String| match v.trim().to_lowercase().as_str() {
+            "y" | "yes" | "n" | "no" => Ok(()),
+            _ => Err("Please type yes or no."),
+        })
+        .interact_text()
+        .map_err(prompt_error)?;
+    Ok(answer.trim().to_lowercase().starts_with('y'))
+}
+
+/// Lists the options one per line, then asks for a number.
+fn numbered_select(
+    prompt: &str,
+    items: &[String],
+    default: usize,
+) -> Result<usize, InstallerError> {
+    let n = items.len();
+    println!("{}", ui::text(prompt));
+    for (i, item) in items.iter().enumerate() {
+        println!("  {}. {}", i + 1, ui::text(console::strip_ansi_codes(item).trim()));
+    }
+    let choice = Input::<usize>::new()
+        .with_prompt(format!("Type a number from 1 to {}", n))
+        .default(default + 1)
+        .validate_with(|v: &usize| {
+            if (1..=n).contains(v) {
+                Ok(())
+            } else {
+                Err(format!("There is no option {}.", v))
+            }
+        })
+        .interact_text()
+        .map_err(prompt_error)?;
+    Ok(choice - 1)
+}
+
+/// Lists the options one per line, then asks for numbers separated by commas.
+fn numbered_multi_select(
+    prompt: &str,
+    items: &[String],
+    defaults: &[bool],
+) -> Result<Vec<usize>, InstallerError> {
+    let n = items.len();
+    println!("{}", ui::text(prompt));
+    for (i, item) in items.iter().enumerate() {
+        println!("  {}. {}", i + 1, ui::text(console::strip_ansi_codes(item).trim()));
+    }
+    let default: Vec<String> =
+        (0..n).filter(|&i| defaults[i]).map(|i| (i + 1).to_string()).collect();
+    let parse = |v: &str| -> Option<Vec<usize>> {
+        v.split(',')
+            .map(|p| p.trim().parse::<usize>().ok().filter(|c| (1..=n).contains(c)))
+            .collect()
+    };
+    let answer = Input::<String>::new()
+        .with_prompt(format!("Type numbers from 1 to {}, separated by commas", n))
+        .default(default.join(","))
+        .validate_with(|v: &String| match parse(v) {
+            Some(_) => Ok(()),
+            None => Err("Use option numbers separated by commas, e.g. 1,2."),
+        })
+        .interact_text()
+        .map_err(prompt_error)?;
+    let mut picked = parse(&answer).unwrap_or_default();
+    picked.sort_unstable();
+    picked.dedup();
+    Ok(picked.into_iter().map(|c| c - 1).collect())
+}
+
+/// Ctrl-C in raw mode surfaces as an `Interrupted` read error, not SIGINT.
+fn prompt_error(e: dialoguer::Error) -> InstallerError {
+    let dialoguer::Error::IO(ref io_err) = e;
+    if io_err.kind() == io::ErrorKind::Interrupted {
+        InstallerError::Interrupted
+    } else {
+        InstallerError::Prompt(e)
+    }
+}
+
+fn log_answer(prompt: &str, answer: &str) {
+    ui::print_info(&format!("{}  {}", prompt, console::style(answer).cyan().bold()));
+}
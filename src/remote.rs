@@ -0,0 +1,112 @@
+use std::{env, fs, io::Read, path::Path};
+
+use crate::{cmd, error::InstallerError, files, log, ui};
+
+/// Live-system sshd settings for the remote session.
+const DROP_IN_DIR: &str = "/etc/ssh/sshd_config.d";
+const DROP_IN: &str = "/etc/ssh/sshd_config.d/10-artix-installer-remote.conf";
+
+/// Present while a one-time password is waiting to be used.
+const MARKER: &str = "/run/artix-installer-remote";
+
+/// No 0/O, 1/l/I — the password is read off a screen and typed elsewhere.
+const ALPHABET: &[u8] = b"abcdefghijkmnpqrstuvwxyzACDEFGHJKLMNPQRTUVWXY34679";
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// `--remote`: starts sshd on the live system, gives root a one-time
+/// password and prints how to connect, then exits. The wizard itself runs
+/// in the SSH session; see [`claim`].
+pub fn start() -> Result<(), InstallerError> {
+    let password = one_time_password()?;
+
+    cmd::run_with_spinner(
+        "ssh-keygen",
+        &["-A"],
+        "Generating SSH host keys…",
+        "SSH host keys ready.",
+    )?;
+    if !cmd::dry_run() {
+        fs::create_dir_all(DROP_IN_DIR)?;
+    }
+    files::write_atomic(
+        DROP_IN,
+        "# Written by artix-installer --remote; the live system only.\n\
+         PermitRootLogin yes\n\
+         PasswordAuthentication yes\n",
+    )?;
+    cmd::run_feed("chpasswd", &[], &format!("root:{}\n", password))?;
+    cmd::run_with_spinner("rc-service", &["sshd", "restart"], "Starting sshd…", "sshd running.")?;
+    files::write_atomic(MARKER, "")?;
+    log::line("remote mode: sshd started, one-time root password set");
+
+    let exe = env::current_exe()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| "artix-installer".to_string());
+    let addresses = addresses();
+    let host = addresses.first().map(String::as_str).unwrap_or("<this machine>");
+
+    println!();
+    let mut rows = vec![("password", password.as_str()), ("user", "root")];
+    for addr in &addresses {
+        rows.push(("address", addr.as_str()));
+    }
+    ui::print_kv_box("Remote installation", &rows);
+    println!();
+    ui::print_info(&format!("Connect with:  ssh -t root@{} {}", host, exe));
+    ui::print_info("The password works for one login — the wizard turns it off once it starts.");
+    if addresses.is_empty() {
+        ui::print_warning("No network address found — connect the machine to a network first.");
+    }
+    Ok(())
+}
+
+/// Called at start-up. Inside the SSH session opened with the one-time
+/// password, turns password logins off again so the password can't be
+/// reused; key-based logins and the open session keep working.
+pub fn claim() {
+    if env::var_os("SSH_CONNECTION").is_none() || !Path::new(MARKER).exists() {
+        return;
+    }
+    let result = files::write_atomic(
+        DROP_IN,
+        "# Written by artix-installer --remote; the live system only.\n\
+         PermitRootLogin prohibit-password\n\
+         PasswordAuthentication no\n",
+    )
+    .and_then(|()| {
+        cmd::run_with_spinner("rc-service", &["sshd", "reload"], "Locking sshd…", "sshd reloaded.")
+    });
+    match result {
+        Ok(()) => {
+            let _ = fs::remove_file(MARKER);
+            ui::print_success("Remote session claimed — the one-time password no longer works.");
+        }
+        Err(e) => ui::print_warning(&format!("Couldn't turn off password logins: {}", e)),
+    }
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// 12 characters from /dev/urandom, about 67 bits.
+fn one_time_password() -> Result<String, InstallerError> {
+    let mut bytes = [0u8; 12];
+    fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes.iter().map(|b| ALPHABET[*b as usize % ALPHABET.len()] as char).collect())
+}
+
+/// Global IPv4 and IPv6 addresses of the live system, IPv4 first.
+fn addresses() -> Vec<String> {
+    let mut out = Vec::new();
+    for family in ["-4", "-6"] {
+        let listing = cmd::run_capture("ip", &["-o", family, "addr", "show", "scope", "global"])
+            .unwrap_or_default();
+        for line in listing.lines() {
+            // `2: enp1s0    inet 192.168.1.20/24 brd … scope global …`
+            if let Some(addr) = line.split_whitespace().nth(3) {
+                out.push(addr.split('/').next().unwrap_or(addr).to_string());
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,246 @@
+use std::{fs, sync::Mutex, time::Duration};
+
+use crate::{answers, cmd, error::InstallerError, files, log, session::Session, ui};
+
+/// Where the successful run leaves its report in the installed system.
+const TARGET_REPORT: &str = "/mnt/var/log/artix-installer-report.txt";
+
+/// The explicit package list, in the target and on the live system. Both
+/// are valid `--packages-file` input.
+const TARGET_MANIFEST: &str = "/mnt/var/log/artix-installer-packages.txt";
+const HOST_MANIFEST: &str = "/tmp/artix-installer-packages.txt";
+
+/// Extra `(topic, detail)` lines collected by the steps, e.g. which tuning
+/// was applied, shown after the configuration.
+static NOTES: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Wall-clock time of each step this run, by step id, in run order.
+static TIMINGS: Mutex<Vec<(String, Duration)>> = Mutex::new(Vec::new());
+
+// ── Report content ────────────────────────────────────────────────────────────
+
+/// Adds a line to the report's notes section.
+pub fn note(topic: &str, detail: &str) {
+    NOTES.lock().unwrap().push((topic.to_string(), detail.to_string()));
+}
+
+/// Records how long step `id` took, retries and prompts included.
+pub fn timing(id: &str, took: Duration) {
+    TIMINGS.lock().unwrap().push((id.to_string(), took));
+}
+
+/// `45s`, `7m12s`, `1h02m05s`.
+pub fn duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m{:02}s", secs / 3600, secs / 60 % 60, secs % 60),
+    }
+}
+
+/// Shows the time each step took, slowest marked, with the total.
+pub fn print_timings(total: Duration) {
+    let timings = TIMINGS.lock().unwrap();
+    if timings.is_empty() {
+        return;
+    }
+    let slowest = timings.iter().map(|(_, d)| *d).max().unwrap_or_default();
+    let mut rows: Vec<(String, String)> = timings
+        .iter()
+        .map(|(id, d)| {
+            let mark = if *d == slowest && timings.len() > 1 { "  ← slowest" } else { "" };
+            (id.clone(), format!("{}{}", duration(*d), mark))
+        })
+        .collect();
+    rows.push(("total".to_string(), duration(total)));
+    let rows: Vec<(&str, &str)> = rows.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    println!();
+    ui::print_kv_box("Time per step", &rows);
+}
+
+/// Renders a plain-text summary of the run: outcome, duration, the
+/// configuration that was (or was being) installed and the time per step.
+pub fn render(sess: &Session, result: &Result<(), InstallerError>, elapsed: Duration) -> String {
+    let outcome = match result {
+        Ok(()) => "success".to_string(),
+        Err(e) => format!("failed — {}", e),
+    };
+    let none = || "—".to_string();
+    let kernels: Vec<&str> = sess.kernels.iter().map(|k| k.package_name()).collect();
+
+    let rows = [
+        ("result", outcome),
+        ("duration", duration(elapsed)),
+        ("last step", sess.last_step.to_string()),
+        ("disk", sess.disk.clone().unwrap_or_else(none)),
+        ("efi", sess.efi_partition.clone().unwrap_or_else(none)),
+        ("efi uuid", sess.efi_uuid.clone().unwrap_or_else(none)),
+        ("swap", sess.swap_partition.clone().unwrap_or_else(none)),
+        ("swap uuid", sess.swap_uuid.clone().unwrap_or_else(none)),
+        ("root", sess.root_partition.clone().unwrap_or_else(none)),
+        ("root uuid", sess.root_uuid.clone().unwrap_or_else(none)),
+        ("kernel", if kernels.is_empty() { none() } else { kernels.join(" ") }),
+    ];
+
+    let mut out = String::from("Artix Linux installation report\n");
+    out.push_str(&"─".repeat(31));
+    out.push('\n');
+    for (key, val) in rows {
+        out.push_str(&format!("{:<12}{}\n", key, val));
+    }
+
+    let notes = NOTES.lock().unwrap();
+    if !notes.is_empty() {
+        out.push_str("\nNotes\n");
+        for (topic, detail) in notes.iter() {
+            out.push_str(&format!("{:<12}{}\n", topic, detail));
+        }
+    }
+
+    let timings = TIMINGS.lock().unwrap();
+    if !timings.is_empty() {
+        out.push_str("\nTime per step\n");
+        for (id, took) in timings.iter() {
+            out.push_str(&format!("{:<12}{}\n", id, duration(*took)));
+        }
+    }
+    out
+}
+
+// ── Target copy ───────────────────────────────────────────────────────────────
+
+/// Saves `report` plus the explicitly installed `packages` to
+/// `/mnt/var/log/artix-installer-report.txt`, so the machine itself records
+/// how it was provisioned. Call before unmounting. Never fatal.
+pub fn save_to_target(report: &str, packages: &str) {
+    let mut content = report.to_string();
+    content.push_str("\nExplicitly installed packages\n");
+    content.push_str(&"─".repeat(29));
+    content.push('\n');
+    content.push_str(packages);
+
+    let result = (|| -> Result<(), InstallerError> {
+        if !cmd::dry_run() {
+            fs::create_dir_all("/mnt/var/log")?;
+        }
+        files::write_atomic(TARGET_REPORT, &content)
+    })();
+    match result {
+        Ok(()) => ui::print_success(&format!("Installation report saved to {}.", TARGET_REPORT)),
+        Err(e) => ui::print_warning(&format!("Could not save {}: {}", TARGET_REPORT, e)),
+    }
+}
+
+// ── Package manifest ──────────────────────────────────────────────────────────
+
+/// Saves `pacman -Qqe` from the chroot — every explicitly installed package —
+/// to the target and the live system, so the install can be reproduced
+/// with `--packages-file` or diffed against other machines. Returns the list
+/// (empty if pacman couldn't be asked); failures are reported, not fatal.
+pub fn save_manifest() -> String {
+    let packages = if cmd::dry_run() {
+        String::new()
+    } else {
+        cmd::run_capture("artix-chroot", &["/mnt", "pacman", "-Qqe"]).unwrap_or_else(|e| {
+            ui::print_warning(&format!("Couldn't list the installed packages: {}", e));
+            String::new()
+        })
+    };
+    let content = format!(
+        "# Explicitly installed packages (pacman -Qqe), saved by artix-installer.\n{}",
+        packages
+    );
+    if !cmd::dry_run() {
+        let _ = fs::create_dir_all("/mnt/var/log");
+    }
+    for path in [TARGET_MANIFEST, HOST_MANIFEST] {
+        if let Err(e) = files::write_atomic(path, &content) {
+            ui::print_warning(&format!("Could not save {}: {}", path, e));
+        }
+    }
+    let count = packages.lines().filter(|l| !l.trim().is_empty()).count();
+    ui::print_success(&format!(
+        "Package manifest ({} packages) saved to {} and {}.",
+        count,
+        TARGET_MANIFEST.trim_start_matches("/mnt"),
+        HOST_MANIFEST
+    ));
+    packages
+}
+
+// ── Export ────────────────────────────────────────────────────────────────────
+
+/// Copies the report and the log to `report.dest` from the answer file, so a
+/// provisioning system can archive them. `dest` is either a directory (local
+/// path or mounted share) or an `http(s)://` URL that accepts PUT uploads.
+///
+/// Files are prefixed with the machine's DMI product UUID to keep reports from
+/// different machines apart. Failures are reported but never fatal.
+pub fn export(report: &str) {
+    let Some(dest) = answers::get().and_then(|a| a.str("report.dest")) else {
+        return;
+    };
+    if cmd::dry_run() {
+        ui::print_info(&format!("[dry-run] would export the report and log to {}", dest));
+        return;
+    }
+
+    let id = machine_id();
+    let report_name = format!("{}-report.txt", id);
+    let log_name = format!("{}-install.log", id);
+
+    let result = if dest.starts_with("http://") || dest.starts_with("https://") {
+        upload(dest, report, &report_name, &log_name)
+    } else {
+        copy_to_dir(dest, report, &report_name, &log_name)
+    };
+
+    match result {
+        Ok(()) => ui::print_success(&format!("Report and log exported to {}.", dest)),
+        Err(e) => ui::print_warning(&format!("Could not export report to {}: {}", dest, e)),
+    }
+}
+
+fn copy_to_dir(
+    dir: &str,
+    report: &str,
+    report_name: &str,
+    log_name: &str,
+) -> Result<(), InstallerError> {
+    fs::create_dir_all(dir)?;
+    fs::write(format!("{}/{}", dir, report_name), report)?;
+    fs::copy(log::LOG_FILE, format!("{}/{}", dir, log_name))?;
+    Ok(())
+}
+
+fn upload(
+    url: &str,
+    report: &str,
+    report_name: &str,
+    log_name: &str,
+) -> Result<(), InstallerError> {
+    let base = url.trim_end_matches('/');
+    let tmp = format!("/tmp/{}", report_name);
+    fs::write(&tmp, report)?;
+
+    for (file, name) in [(tmp.as_str(), report_name), (log::LOG_FILE, log_name)] {
+        cmd::run_with_spinner(
+            "curl",
+            &["-fsS", "-T", file, &format!("{}/{}", base, name)],
+            &format!("Uploading {}…", name),
+            &format!("{} uploaded.", name),
+        )?;
+    }
+    Ok(())
+}
+
+/// Stable per-machine identifier: DMI product UUID, else the live hostname.
+fn machine_id() -> String {
+    ["/sys/class/dmi/id/product_uuid", "/etc/hostname"]
+        .iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .map(|s| s.trim().to_lowercase())
+        .find(|s| !s.is_empty())
+        .unwrap_or_else(|| "artix".to_string())
+}
@@ -1,6 +1,10 @@
 use std::{fs, io::Write};
 
-use crate::config::{Config, KernelVariant};
+use crate::{
+    cmd,
+    config::{Config, KernelVariant, RootFs, SeatManager, TimeDaemon},
+    log, lsblk,
+};
 
 const SESSION_FILE: &str = "/tmp/artix-installer.session";
 
@@ -12,11 +16,34 @@ const SESSION_FILE: &str = "/tmp/artix-installer.session";
 pub struct Session {
     /// Index of the last fully completed step (0 = nothing done yet).
     pub last_step: u8,
+    /// Ids of the completed steps.
+    pub completed: Vec<String>,
     pub disk: Option<String>,
     pub efi_partition: Option<String>,
     pub swap_partition: Option<String>,
     pub root_partition: Option<String>,
-    pub kernel: Option<KernelVariant>,
+    pub efi_uuid: Option<String>,
+    pub swap_uuid: Option<String>,
+    pub root_uuid: Option<String>,
+    pub efi_partuuid: Option<String>,
+    pub swap_partuuid: Option<String>,
+    pub root_partuuid: Option<String>,
+    pub root_fs: Option<RootFs>,
+    pub var_partition: Option<String>,
+    pub var_uuid: Option<String>,
+    pub var_partuuid: Option<String>,
+    pub home_partition: Option<String>,
+    pub home_uuid: Option<String>,
+    pub home_partuuid: Option<String>,
+    pub tmpfs_tmp: bool,
+    /// Installed kernels; the first is the default boot entry.
+    pub kernels: Vec<KernelVariant>,
+    pub time_daemon: Option<TimeDaemon>,
+    pub seat: Option<SeatManager>,
+    /// Portable (USB) install; see `steps::portable`.
+    pub portable: bool,
+    /// Answers settled on the review screen, as `(prompt key, value)`.
+    pub presets: Vec<(String, String)>,
 }
 
 impl Session {
@@ -24,7 +51,10 @@ impl Session {
 
     /// Returns `Some(session)` if a valid checkpoint file exists, else `None`.
     pub fn load() -> Option<Self> {
-        let content = fs::read_to_string(SESSION_FILE).ok()?;
+        Self::parse(&fs::read_to_string(SESSION_FILE).ok()?)
+    }
+
+    fn parse(content: &str) -> Option<Self> {
         let mut s = Session::default();
 
         for line in content.lines() {
@@ -35,12 +65,34 @@ impl Session {
             };
             match key {
                 "step"   => s.last_step     = val.parse().unwrap_or(0),
+                "done"   => s.completed = val.split(',').map(str::to_string).collect(),
                 "disk"   => s.disk          = Some(val),
                 "efi"    => s.efi_partition  = Some(val),
                 "swap"   => s.swap_partition = Some(val),
                 "root"   => s.root_partition = Some(val),
-                "kernel" => s.kernel = Some(KernelVariant::from_str(&val)),
-                _ => {}
+                "var"    => s.var_partition  = Some(val),
+                "home"   => s.home_partition = Some(val),
+                "efi_uuid"  => s.efi_uuid  = Some(val),
+                "swap_uuid" => s.swap_uuid = Some(val),
+                "root_uuid" => s.root_uuid = Some(val),
+                "var_uuid"  => s.var_uuid  = Some(val),
+                "home_uuid" => s.home_uuid = Some(val),
+                "efi_partuuid"  => s.efi_partuuid  = Some(val),
+                "swap_partuuid" => s.swap_partuuid = Some(val),
+                "root_partuuid" => s.root_partuuid = Some(val),
+                "var_partuuid"  => s.var_partuuid  = Some(val),
+                "home_partuuid" => s.home_partuuid = Some(val),
+                "tmpfs_tmp" => s.tmpfs_tmp = val == "yes",
+                "root_fs"   => s.root_fs   = Some(RootFs::from_str(&val)),
+                "kernel" => s.kernels = val.split(',').map(KernelVariant::from_str).collect(),
+                "ntp"    => s.time_daemon = Some(TimeDaemon::from_str(&val)),
+                "seat"   => s.seat        = Some(SeatManager::from_str(&val)),
+                "portable" => s.portable = val == "yes",
+                _ => {
+                    if let Some(key) = key.strip_prefix("preset.") {
+                        s.presets.push((key.to_string(), val));
+                    }
+                }
             }
         }
 
@@ -48,32 +100,149 @@ impl Session {
     }
 
     /// Writes the current state to disk. Errors are silently ignored by callers.
+    /// A dry run never writes one, so it can't be resumed by a real run.
     pub fn save(&self) -> std::io::Result<()> {
+        if cmd::dry_run() {
+            return Ok(());
+        }
+        let mut f = fs::File::create(SESSION_FILE)?;
+        f.write_all(self.render().as_bytes())
+    }
+
+    fn render(&self) -> String {
         let mut out = format!("step={}\n", self.last_step);
+        if !self.completed.is_empty() {
+            out.push_str(&format!("done={}\n", self.completed.join(",")));
+        }
         if let Some(ref v) = self.disk          { out.push_str(&format!("disk={}\n",  v)); }
         if let Some(ref v) = self.efi_partition  { out.push_str(&format!("efi={}\n",   v)); }
         if let Some(ref v) = self.swap_partition { out.push_str(&format!("swap={}\n",  v)); }
         if let Some(ref v) = self.root_partition { out.push_str(&format!("root={}\n",  v)); }
-        if let Some(k)     = self.kernel         { out.push_str(&format!("kernel={}\n", k.as_str())); }
-
-        let mut f = fs::File::create(SESSION_FILE)?;
-        f.write_all(out.as_bytes())
+        if let Some(ref v) = self.var_partition  { out.push_str(&format!("var={}\n",   v)); }
+        if let Some(ref v) = self.home_partition { out.push_str(&format!("home={}\n",  v)); }
+        if let Some(ref v) = self.efi_uuid       { out.push_str(&format!("efi_uuid={}\n",  v)); }
+        if let Some(ref v) = self.swap_uuid      { out.push_str(&format!("swap_uuid={}\n", v)); }
+        if let Some(ref v) = self.root_uuid      { out.push_str(&format!("root_uuid={}\n", v)); }
+        if let Some(ref v) = self.var_uuid       { out.push_str(&format!("var_uuid={}\n",  v)); }
+        if let Some(ref v) = self.home_uuid      { out.push_str(&format!("home_uuid={}\n", v)); }
+        if let Some(ref v) = self.efi_partuuid { out.push_str(&format!("efi_partuuid={}\n", v)); }
+        if let Some(ref v) = self.swap_partuuid { out.push_str(&format!("swap_partuuid={}\n", v)); }
+        if let Some(ref v) = self.root_partuuid { out.push_str(&format!("root_partuuid={}\n", v)); }
+        if let Some(ref v) = self.var_partuuid { out.push_str(&format!("var_partuuid={}\n", v)); }
+        if let Some(ref v) = self.home_partuuid { out.push_str(&format!("home_partuuid={}\n", v)); }
+        if self.tmpfs_tmp                        { out.push_str("tmpfs_tmp=yes\n"); }
+        if let Some(fs) = self.root_fs {
+            out.push_str(&format!("root_fs={}\n", fs.name()));
+        }
+        if !self.kernels.is_empty() {
+            let names: Vec<&str> = self.kernels.iter().map(|k| k.as_str()).collect();
+            out.push_str(&format!("kernel={}\n", names.join(",")));
+        }
+        if let Some(d)     = self.time_daemon    { out.push_str(&format!("ntp={}\n", d.as_str())); }
+        if self.portable                         { out.push_str("portable=yes\n"); }
+        if let Some(m) = self.seat {
+            out.push_str(&format!("seat={}\n", m.as_str()));
+        }
+        for (key, value) in &self.presets {
+            out.push_str(&format!("preset.{}={}\n", key, value));
+        }
+        out
     }
 
     /// Removes the session file (called on successful completion or fresh start).
     pub fn clear() {
+        if cmd::dry_run() {
+            return;
+        }
         let _ = fs::remove_file(SESSION_FILE);
     }
 
     // ── Helpers ───────────────────────────────────────────────────────────────
 
+    pub fn is_done(&self, id: &str) -> bool {
+        self.completed.iter().any(|s| s == id)
+    }
+
+    /// Marks step `id` (number `n`) as completed.
+    pub fn complete(&mut self, id: &str, n: u8) {
+        if !self.is_done(id) {
+            self.completed.push(id.to_string());
+        }
+        self.last_step = self.last_step.max(n);
+    }
+
+    /// Records the partition layout — with UUIDs once it has been formatted.
+    pub fn set_config(&mut self, c: &Config) {
+        self.efi_partition  = Some(c.efi_partition.clone());
+        self.swap_partition = c.swap_partition.clone();
+        self.root_partition = Some(c.root_partition.clone());
+        self.efi_uuid  = c.efi_uuid.clone();
+        self.swap_uuid = c.swap_uuid.clone();
+        self.root_uuid = c.root_uuid.clone();
+        self.efi_partuuid  = c.efi_partuuid.clone();
+        self.swap_partuuid = c.swap_partuuid.clone();
+        self.root_partuuid = c.root_partuuid.clone();
+        self.var_partuuid  = c.var_partuuid.clone();
+        self.home_partuuid = c.home_partuuid.clone();
+        self.root_fs   = Some(c.root_fs);
+        self.var_partition = c.var_partition.clone();
+        self.var_uuid  = c.var_uuid.clone();
+        self.home_partition = c.home_partition.clone();
+        self.home_uuid = c.home_uuid.clone();
+        self.tmpfs_tmp = c.tmpfs_tmp;
+    }
+
     /// Reconstructs a `Config` from saved partition data.
-    /// Panics only if called when session data is incomplete (programmer error).
+    /// Device paths are re-resolved from the saved UUIDs where possible, so a
+    /// resumed session still finds its partitions after sda↔sdb reordering.
+    /// A partition whose filesystem UUID is gone (say, a retry after a failed
+    /// `mkfs`) is found by its PARTUUID instead.
     pub fn to_config(&self) -> Config {
+        let resolve = |path: &Option<String>, uuid: &Option<String>, partuuid: &Option<String>| {
+            let saved = path.clone().unwrap_or_default();
+            let current = uuid
+                .as_deref()
+                .and_then(lsblk::device_for_uuid)
+                .or_else(|| partuuid.as_deref().and_then(lsblk::device_for_partuuid));
+            match current {
+                Some(current) if current != saved => {
+                    log::line(&format!(
+                        "resume: UUID {:?} / PARTUUID {:?} moved {} → {}",
+                        uuid, partuuid, saved, current
+                    ));
+                    current
+                }
+                _ => saved,
+            }
+        };
+
         Config {
-            efi_partition:  self.efi_partition.clone().unwrap_or_default(),
-            swap_partition: self.swap_partition.clone(),
-            root_partition: self.root_partition.clone().unwrap_or_default(),
+            efi_partition:  resolve(&self.efi_partition, &self.efi_uuid, &self.efi_partuuid),
+            swap_partition: self
+                .swap_partition
+                .as_ref()
+                .map(|_| resolve(&self.swap_partition, &self.swap_uuid, &self.swap_partuuid)),
+            root_partition: resolve(&self.root_partition, &self.root_uuid, &self.root_partuuid),
+            efi_uuid:  self.efi_uuid.clone(),
+            swap_uuid: self.swap_uuid.clone(),
+            root_uuid: self.root_uuid.clone(),
+            efi_partuuid:  self.efi_partuuid.clone(),
+            swap_partuuid: self.swap_partuuid.clone(),
+            root_partuuid: self.root_partuuid.clone(),
+            root_fs:   self.root_fs.unwrap_or(RootFs::Ext4),
+            var_partition: self
+                .var_partition
+                .as_ref()
+                .map(|_| resolve(&self.var_partition, &self.var_uuid, &self.var_partuuid)),
+            var_uuid:  self.var_uuid.clone(),
+            var_partuuid: self.var_partuuid.clone(),
+            home_partition: self
+                .home_partition
+                .as_ref()
+                .map(|_| resolve(&self.home_partition, &self.home_uuid, &self.home_partuuid)),
+            home_uuid: self.home_uuid.clone(),
+            home_partuuid: self.home_partuuid.clone(),
+            tmpfs_tmp: self.tmpfs_tmp,
         }
     }
 }
@@ -81,7 +250,7 @@ impl Session {
 // ── KernelVariant ↔ string ────────────────────────────────────────────────────
 
 impl KernelVariant {
-    fn as_str(self) -> &'static str {
+    pub fn as_str(self) -> &'static str {
         match self {
             KernelVariant::Stable => "stable",
             KernelVariant::Lts    => "lts",
@@ -97,3 +266,74 @@ impl KernelVariant {
         }
     }
 }
+
+// ── TimeDaemon ↔ string ───────────────────────────────────────────────────────
+
+impl TimeDaemon {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeDaemon::Ntpd     => "ntpd",
+            TimeDaemon::Chrony   => "chrony",
+            TimeDaemon::Openntpd => "openntpd",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "chrony"   => TimeDaemon::Chrony,
+            "openntpd" => TimeDaemon::Openntpd,
+            _          => TimeDaemon::Ntpd,
+        }
+    }
+}
+
+// ── SeatManager ↔ string ──────────────────────────────────────────────────────
+
+impl SeatManager {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SeatManager::Elogind => "elogind",
+            SeatManager::Seatd   => "seatd",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "seatd" => SeatManager::Seatd,
+            _       => SeatManager::Elogind,
+        }
+    }
+}
+
+// ── RootFs ↔ string ───────────────────────────────────────────────────────────
+
+impl RootFs {
+    fn from_str(s: &str) -> Self {
+        match s {
+            "btrfs"    => RootFs::Btrfs,
+            "bcachefs" => RootFs::Bcachefs,
+            "zfs"      => RootFs::Zfs,
+            _          => RootFs::Ext4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reviewed_answers_survive_a_resume() {
+        let mut sess = Session::default();
+        sess.complete("review", 3);
+        sess.presets = vec![
+            ("hostname".to_string(), "artix".to_string()),
+            ("user.groups".to_string(), "wheel,audio".to_string()),
+            ("user.name".to_string(), String::new()),
+        ];
+
+        let loaded = Session::parse(&sess.render()).unwrap();
+        assert!(loaded.is_done("review"));
+        assert_eq!(loaded.presets, sess.presets);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{cmd, error::InstallerError, prompt, ui};
+
+/// Offers a speaking console (espeakup) and/or braille display support
+/// (brltty) in the installed system, so a blind user who installed with
+/// them on the live ISO keeps them after reboot. Defaults to whatever is
+/// running in the live environment.
+pub fn run() -> Result<(), InstallerError> {
+    let speech = is_running("espeakup");
+    let braille = is_running("brltty");
+
+    let items = [
+        "None".to_string(),
+        "Speech — espeakup console screen reader".to_string(),
+        "Braille — brltty".to_string(),
+        "Speech and braille".to_string(),
+    ];
+    let default = match (speech, braille) {
+        (false, false) => 0,
+        (true, false) => 1,
+        (false, true) => 2,
+        (true, true) => 3,
+    };
+
+    println!();
+    let idx = prompt::select(
+        "screen_reader",
+        "Console accessibility in the new system",
+        &items,
+        &["none", "speech", "braille", "both"],
+        default,
+    )?;
+
+    let mut services = Vec::new();
+    if idx == 1 || idx == 3 {
+        services.push("espeakup");
+    }
+    if idx == 2 || idx == 3 {
+        services.push("brltty");
+    }
+    if services.is_empty() {
+        return Ok(());
+    }
+
+    let mut args = vec!["/mnt"];
+    for s in &services {
+        args.push(s);
+        args.push(if *s == "espeakup" { "espeakup-openrc" } else { "brltty-openrc" });
+    }
+
+    println!();
+    cmd::run_transaction("basestrap", &args)?;
+    for s in services {
+        cmd::run_with_spinner(
+            "artix-chroot",
+            &["/mnt", "rc-update", "add", s, "default"],
+            &format!("Enabling {}…", s),
+            &format!("{} enabled at boot.", s),
+        )?;
+    }
+
+    ui::print_success("The console will be accessible after reboot.");
+    Ok(())
+}
+
+/// `true` if a process named `name` is running in the live environment.
+fn is_running(name: &str) -> bool {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.flatten().any(|e| {
+        std::fs::read_to_string(e.path().join("comm")).is_ok_and(|c| c.trim() == name)
+    })
+}
@@ -0,0 +1,356 @@
+use crate::{
+    answers, cmd,
+    config::{Arch, KernelVariant},
+    error::InstallerError,
+    files, prompt, report,
+    steps::{efiboot, portable, uefi},
+    ui,
+};
+
+const CUSTOM_SCRIPT: &str = "/mnt/etc/grub.d/40_custom";
+const LINUX_SCRIPT: &str = "/mnt/etc/grub.d/10_linux";
+const DEFAULT_GRUB: &str = "/mnt/etc/default/grub";
+const GRUB_CFG: &str = "/mnt/boot/grub/grub.cfg";
+const FALLBACK_HOOK: &str = "/mnt/etc/pacman.d/hooks/95-grub-fallback.hook";
+
+/// Installs GRUB for UEFI into the ESP mounted at `/mnt/boot` and
+/// generates its config. Optionally protects the menu with a password
+/// first, since `grub-mkconfig` must run after that change. With several
+/// `kernels` each gets its own top-level entry, the first one as default.
+/// Finally the firmware boot entry is checked; see [`efiboot`].
+pub fn run(kernels: &[KernelVariant], efi_partition: &str) -> Result<(), InstallerError> {
+    let target = format!("--target={}", grub_target());
+    let install = format!("grub-install {}", target);
+    ui::print_kv_box(
+        "Bootloader",
+        &[
+            ("packages", "grub efibootmgr"),
+            ("install", install.as_str()),
+            ("config", "grub-mkconfig -o /boot/grub/grub.cfg"),
+        ],
+    );
+    println!();
+
+    if !prompt::confirm("bootloader", "Install the GRUB bootloader?", true)? {
+        ui::print_warning("Skipping bootloader — install one from the chroot before rebooting.");
+        return Ok(());
+    }
+
+    println!();
+    cmd::run_transaction("basestrap", &["/mnt", "grub", "efibootmgr"])?;
+    if portable::enabled() {
+        install_removable(&target)?;
+    } else {
+        cmd::run_with_spinner(
+            "artix-chroot",
+            &[
+                "/mnt",
+                "grub-install",
+                &target,
+                "--efi-directory=/boot",
+                &format!("--bootloader-id={}", efiboot::LABEL),
+            ],
+            "Installing GRUB to the EFI partition…",
+            "GRUB installed.",
+        )?;
+        install_fallback(&target)?;
+    }
+
+    protect_menu()?;
+    let params = ask_cmdline()?;
+    if !params.is_empty() {
+        let words: Vec<&str> = params.split_whitespace().collect();
+        if !add_cmdline(&words)? {
+            ui::print_warning(&format!(
+                "GRUB's command line wasn't updated — add {} to it by hand.",
+                params
+            ));
+        }
+    }
+    if kernels.len() > 1 {
+        list_kernels(kernels[0])?;
+    }
+
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "grub-mkconfig", "-o", "/boot/grub/grub.cfg"],
+        "Generating /boot/grub/grub.cfg…",
+        "GRUB configuration generated.",
+    )?;
+
+    if portable::enabled() {
+        return Ok(());
+    }
+    let loader = format!("\\EFI\\{}\\{}", efiboot::LABEL, grub_image());
+    efiboot::ensure_entry(efi_partition, &loader)
+}
+
+/// GRUB's EFI platform for the machine's architecture and firmware —
+/// cheap tablets and Atom laptops pair a 64-bit CPU with 32-bit UEFI.
+fn grub_target() -> &'static str {
+    match Arch::current() {
+        Arch::X86_64 if uefi::is_ia32() => "i386-efi",
+        Arch::X86_64 => "x86_64-efi",
+        Arch::Aarch64 => "arm64-efi",
+    }
+}
+
+/// Optionally installs a second copy of GRUB at the fallback path
+/// `\EFI\BOOT\BOOTX64.EFI`, which firmware boots without an NVRAM entry —
+/// needed for portable USB installs and firmwares that forget their entries.
+///
+/// Non-interactive mode reads `bootloader_removable` (default: no).
+fn install_fallback(target: &str) -> Result<(), InstallerError> {
+    let path = format!("\\EFI\\BOOT\\{}", fallback_image());
+    println!();
+    let ask = format!("Also install GRUB to the fallback path {}?", path);
+    if !prompt::confirm("bootloader_removable", &ask, false)? {
+        return Ok(());
+    }
+    install_removable(target)
+}
+
+/// GRUB at the fallback path only — `--removable` leaves NVRAM alone, which
+/// is what a portable install wants: the host's boot entries aren't ours.
+/// A pacman hook reinstalls the copy on every grub upgrade, so it keeps
+/// matching the new modules and `grub.cfg`.
+fn install_removable(target: &str) -> Result<(), InstallerError> {
+    let path = format!("\\EFI\\BOOT\\{}", fallback_image());
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "grub-install", target, "--efi-directory=/boot", "--removable"],
+        &format!("Installing GRUB to {}…", path),
+        &format!("GRUB installed to {}.", path),
+    )?;
+    write_fallback_hook(target, &path)?;
+    report::note("bootloader", &format!("fallback copy at {}", path));
+    Ok(())
+}
+
+/// `/etc/pacman.d/hooks/95-grub-fallback.hook` in the target.
+fn write_fallback_hook(target: &str, path: &str) -> Result<(), InstallerError> {
+    let hook = format!(
+        "[Trigger]\n\
+         Type = Package\n\
+         Operation = Install\n\
+         Operation = Upgrade\n\
+         Target = grub\n\
+         \n\
+         [Action]\n\
+         Description = Refreshing the fallback GRUB at {}\n\
+         When = PostTransaction\n\
+         Exec = /usr/bin/grub-install {} --efi-directory=/boot --removable\n",
+        path,
+        target
+    );
+    if !cmd::dry_run() {
+        std::fs::create_dir_all("/mnt/etc/pacman.d/hooks")?;
+    }
+    files::write_atomic(FALLBACK_HOOK, &hook)?;
+    ui::print_success("pacman hook added: the fallback copy is refreshed on grub upgrades.");
+    Ok(())
+}
+
+/// The file grub-install puts in `\EFI\<id>\` for [`grub_target`].
+fn grub_image() -> &'static str {
+    match grub_target() {
+        "i386-efi" => "grubia32.efi",
+        "arm64-efi" => "grubaa64.efi",
+        _ => "grubx64.efi",
+    }
+}
+
+/// The removable-media file name firmware looks for, per [`grub_target`].
+fn fallback_image() -> &'static str {
+    match grub_target() {
+        "i386-efi" => "BOOTIA32.EFI",
+        "arm64-efi" => "BOOTAA64.EFI",
+        _ => "BOOTX64.EFI",
+    }
+}
+
+// ── Several kernels ───────────────────────────────────────────────────────────
+
+/// By default grub-mkconfig puts one kernel at the top and the rest under
+/// "Advanced options". Lists every kernel at the top level instead, with
+/// `default` first (`GRUB_TOP_LEVEL`, GRUB 2.12+).
+fn list_kernels(default: KernelVariant) -> Result<(), InstallerError> {
+    let Ok(conf) = std::fs::read_to_string(DEFAULT_GRUB) else {
+        if !cmd::dry_run() {
+            ui::print_warning("No /etc/default/grub — extra kernels stay under Advanced options.");
+        }
+        return Ok(());
+    };
+
+    let top_level = format!("\"/boot/vmlinuz-{}\"", default.package_name());
+    let updated = set_option(&conf, "GRUB_DISABLE_SUBMENU", "y");
+    let updated = set_option(&updated, "GRUB_TOP_LEVEL", &top_level);
+    files::edit(DEFAULT_GRUB, &updated)?;
+    Ok(())
+}
+
+/// Sets `key=value` in a `/etc/default/grub`-style file, replacing the
+/// line (commented out or not) if there is one, else appending it.
+fn set_option(conf: &str, key: &str, value: &str) -> String {
+    let line = format!("{}={}", key, value);
+    let prefix = format!("{}=", key);
+    let mut found = false;
+    let mut out: String = conf
+        .lines()
+        .map(|l| {
+            if !found && l.trim_start_matches('#').trim_start().starts_with(&prefix) {
+                found = true;
+                format!("{}\n", line)
+            } else {
+                format!("{}\n", l)
+            }
+        })
+        .collect();
+    if !found {
+        out.push_str(&format!("{}\n", line));
+    }
+    out
+}
+
+// ── Kernel command line ───────────────────────────────────────────────────────
+
+/// Asks for extra kernel parameters, e.g. `mitigations=off`, `nomodeset` or
+/// `quiet splash`. Unattended: `kernel_params`, blank by default. Quotes
+/// and backslashes are refused, since they would end the GRUB value early.
+fn ask_cmdline() -> Result<String, InstallerError> {
+    let unsafe_char = |s: &str| s.contains(['"', '\\', '\'', '$', '`']);
+    if let Some(a) = answers::get() {
+        let params = a.str("kernel_params").unwrap_or("").trim();
+        if unsafe_char(params) {
+            return Err(InstallerError::Config(
+                "kernel_params may not contain quotes, backslashes, $ or `".to_string(),
+            ));
+        }
+        return Ok(params.to_string());
+    }
+
+    loop {
+        println!();
+        let ask = "Extra kernel parameters (blank for none)";
+        let params = prompt::input("kernel_params", ask, "")?;
+        if !unsafe_char(&params) {
+            return Ok(params.trim().to_string());
+        }
+        ui::print_warning("Quotes, backslashes, $ and ` aren't allowed — try again.");
+    }
+}
+
+/// Appends `params` to `GRUB_CMDLINE_LINUX_DEFAULT` (skipping any already
+/// there) and regenerates grub.cfg if GRUB is installed. Returns `false`
+/// when GRUB isn't configured in the target or the user declined the edit —
+/// the caller should tell the user to add the parameters by hand.
+pub fn add_cmdline(params: &[&str]) -> Result<bool, InstallerError> {
+    let Ok(conf) = std::fs::read_to_string(DEFAULT_GRUB) else {
+        return Ok(false);
+    };
+
+    let key = "GRUB_CMDLINE_LINUX_DEFAULT=";
+    let mut found = false;
+    let updated: Vec<String> = conf
+        .lines()
+        .map(|line| {
+            let Some(value) = line.strip_prefix(key) else {
+                return line.to_string();
+            };
+            found = true;
+            let mut words: Vec<&str> = value.trim_matches('"').split_whitespace().collect();
+            for p in params {
+                if !words.contains(p) {
+                    words.push(p);
+                }
+            }
+            format!("{}\"{}\"", key, words.join(" "))
+        })
+        .collect();
+    let mut updated = updated.join("\n") + "\n";
+    if !found {
+        updated.push_str(&format!("{}\"{}\"\n", key, params.join(" ")));
+    }
+
+    if !files::edit(DEFAULT_GRUB, &updated)? {
+        // Unchanged means the parameters were all there already.
+        return Ok(updated == conf);
+    }
+    if std::path::Path::new(GRUB_CFG).exists() {
+        cmd::run_with_spinner(
+            "artix-chroot",
+            &["/mnt", "grub-mkconfig", "-o", "/boot/grub/grub.cfg"],
+            "Regenerating /boot/grub/grub.cfg…",
+            "GRUB configuration regenerated.",
+        )?;
+    }
+    Ok(true)
+}
+
+// ── Password protection ───────────────────────────────────────────────────────
+
+/// Offers a GRUB superuser password for kiosks and shared machines.
+/// Booting stays unrestricted; editing entries or using the GRUB shell
+/// needs the password.
+///
+/// Non-interactive mode only takes `grub.password_hash`, an existing
+/// `grub.pbkdf2.…` hash, so no plain password sits in the answer file.
+fn protect_menu() -> Result<(), InstallerError> {
+    let preset = answers::get().and_then(|a| a.str("grub.password_hash"));
+    let hash = match preset {
+        Some(h) => h.to_string(),
+        None => {
+            println!();
+            let ask = "Protect GRUB menu editing with a password?";
+            if !prompt::confirm("grub_password", ask, false)? {
+                return Ok(());
+            }
+            if answers::get().is_some() {
+                return Err(InstallerError::Config(
+                    "grub_password needs grub.password_hash (from grub-mkpasswd-pbkdf2)"
+                        .to_string(),
+                ));
+            }
+            let password = ui::ask_password("grub.password", "GRUB password")?;
+            let input = format!("{}\n{}\n", password, password);
+            let out = cmd::run_feed("artix-chroot", &["/mnt", "grub-mkpasswd-pbkdf2"], &input)?;
+            match out.split_whitespace().find(|w| w.starts_with("grub.pbkdf2.")) {
+                Some(h) => h.to_string(),
+                None if cmd::dry_run() => return Ok(()),
+                None => {
+                    return Err(InstallerError::CommandFailed(
+                        "grub-mkpasswd-pbkdf2".to_string(),
+                        0,
+                    ))
+                }
+            }
+        }
+    };
+
+    let Ok(custom) = std::fs::read_to_string(CUSTOM_SCRIPT) else {
+        ui::print_warning("No /etc/grub.d/40_custom in the target — GRUB menu left unprotected.");
+        return Ok(());
+    };
+    if !custom.contains("password_pbkdf2") {
+        let block = format!(
+            "\nset superusers=\"root\"\npassword_pbkdf2 root {}\n",
+            hash
+        );
+        files::edit(CUSTOM_SCRIPT, &(custom.clone() + &block))?;
+    }
+
+    // Without --unrestricted every entry would need the password to boot.
+    if let Ok(linux) = std::fs::read_to_string(LINUX_SCRIPT) {
+        if !linux.contains("--unrestricted") {
+            let patched = linux.replacen(
+                "CLASS=\"--class gnu-linux --class gnu --class os",
+                "CLASS=\"--class gnu-linux --class gnu --class os --unrestricted",
+                1,
+            );
+            files::edit(LINUX_SCRIPT, &patched)?;
+        }
+    }
+
+    ui::print_success("GRUB menu editing is password-protected (user: root).");
+    Ok(())
+}
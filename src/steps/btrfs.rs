@@ -0,0 +1,237 @@
+use std::{fs, path::Path};
+
+use crate::{
+    cmd::{self, Cmd},
+    config::{Config, RootFs},
+    error::InstallerError,
+    files, prompt, report, ui,
+};
+
+/// Subvolumes and where they are mounted. `@` + `@home` is the layout
+/// Timeshift expects; snapper nests its `.snapshots` inside `@`.
+pub const SUBVOLUMES: &[(&str, &str)] = &[("@", "/mnt"), ("@home", "/mnt/home")];
+
+/// Mount options for every subvolume, before `subvol=`.
+const MOUNT_OPTIONS: &str = "noatime,compress=zstd";
+
+const GRUB_BTRFSD_CONF: &str = "/mnt/etc/conf.d/grub-btrfsd";
+const SNAPPER_CRON: &str = "/mnt/etc/cron.hourly/snapper";
+
+const SNAPPER_CRON_SCRIPT: &str = "#!/bin/sh\n# Installed by artix-installer.\n\
+    snapper --no-dbus -c root create -c timeline -d timeline\n\
+    snapper --no-dbus -c root cleanup timeline\n\
+    exec snapper --no-dbus -c root cleanup number\n";
+
+/// Keeps a few days of hourly/daily snapshots and ten numbered ones.
+const SNAPPER_LIMITS: &[&str] = &[
+    "TIMELINE_LIMIT_HOURLY=5",
+    "TIMELINE_LIMIT_DAILY=7",
+    "TIMELINE_LIMIT_WEEKLY=0",
+    "TIMELINE_LIMIT_MONTHLY=0",
+    "TIMELINE_LIMIT_YEARLY=0",
+    "NUMBER_LIMIT=10",
+];
+
+// ── Layout ────────────────────────────────────────────────────────────────────
+
+/// Creates the subvolumes on a freshly formatted btrfs `device`.
+pub fn create_subvolumes(cmd: Cmd, device: &str) -> Result<(), InstallerError> {
+    cmd.run_with_spinner(
+        "mount",
+        &[device, "/mnt"],
+        &format!("Mounting {} to create subvolumes…", device),
+        &format!("{} mounted.", device),
+    )?;
+    for (name, _) in SUBVOLUMES {
+        let path = format!("/mnt/{}", name);
+        cmd.run_with_spinner(
+            "btrfs",
+            &["subvolume", "create", &path],
+            &format!("Creating subvolume {}…", name),
+            &format!("Subvolume {} created.", name),
+        )?;
+    }
+    cmd.run_with_spinner(
+        "umount",
+        &["/mnt"],
+        &format!("Unmounting {}…", device),
+        "Subvolumes ready.",
+    )
+}
+
+/// `-o` value for mounting subvolume `name`.
+pub fn mount_options(name: &str) -> String {
+    format!("{},subvol={}", MOUNT_OPTIONS, name)
+}
+
+/// btrfs-progs in the target, for `btrfs scrub`, `btrfs check` and snapshots.
+pub fn install_in_target() -> Result<(), InstallerError> {
+    println!();
+    cmd::run_transaction("basestrap", &["/mnt", "btrfs-progs"])
+}
+
+// ── Snapshots ─────────────────────────────────────────────────────────────────
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Snapper,
+    Timeshift,
+}
+
+/// btrfs root only: offers snapper or Timeshift, takes the first snapshot
+/// and enables grub-btrfs so every snapshot shows up as a boot entry.
+///
+/// snapper keeps hourly/daily timeline snapshots from a cron job; Timeshift
+/// schedules itself once its GUI or `/etc/timeshift/timeshift.json` says so.
+/// Booting a snapshot boots it read-only with the kernel on the ESP.
+pub fn snapshots(config: &Config) -> Result<(), InstallerError> {
+    if config.root_fs != RootFs::Btrfs {
+        return Ok(());
+    }
+
+    let items = [
+        "snapper    timeline snapshots from cron, rollback via snapper".to_string(),
+        "Timeshift  rsync-style snapshots, GUI and CLI".to_string(),
+        "None       no snapshots".to_string(),
+    ];
+    println!();
+    let tool = match prompt::select(
+        "snapshots",
+        "Snapshot tool",
+        &items,
+        &["snapper", "timeshift", "none"],
+        0,
+    )? {
+        0 => Tool::Snapper,
+        1 => Tool::Timeshift,
+        _ => return Ok(()),
+    };
+
+    let name = if tool == Tool::Snapper { "snapper" } else { "timeshift" };
+    let pkgs = [
+        "/mnt",
+        name,
+        "grub-btrfs",
+        "grub-btrfs-openrc",
+        "inotify-tools",
+        "cronie",
+        "cronie-openrc",
+    ];
+    println!();
+    cmd::run_transaction("basestrap", &pkgs)?;
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", "cronie", "default"],
+        "Enabling cronie…",
+        "cronie enabled at boot.",
+    )?;
+
+    match tool {
+        Tool::Snapper => setup_snapper()?,
+        Tool::Timeshift => setup_timeshift(&config.root_partition)?,
+    }
+    grub_btrfs(tool)?;
+
+    report::note("snapshots", name);
+    Ok(())
+}
+
+fn setup_snapper() -> Result<(), InstallerError> {
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "snapper", "--no-dbus", "-c", "root", "create-config", "/"],
+        "Creating the snapper config for /…",
+        "snapper config 'root' created.",
+    )?;
+    let mut args = vec!["/mnt", "snapper", "--no-dbus", "-c", "root", "set-config"];
+    args.extend(SNAPPER_LIMITS);
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &args,
+        "Setting snapshot limits…",
+        "Keeping 5 hourly, 7 daily and 10 numbered snapshots.",
+    )?;
+
+    if !cmd::dry_run() {
+        fs::create_dir_all("/mnt/etc/cron.hourly")?;
+    }
+    files::write_atomic(SNAPPER_CRON, SNAPPER_CRON_SCRIPT)?;
+    cmd::run_with_spinner(
+        "chmod",
+        &["755", SNAPPER_CRON],
+        "Installing the snapper cron job…",
+        "Hourly snapper job installed.",
+    )?;
+
+    first_snapshot(&[
+        "/mnt", "snapper", "--no-dbus", "-c", "root", "create", "-d", "Fresh install",
+    ]);
+    Ok(())
+}
+
+fn setup_timeshift(device: &str) -> Result<(), InstallerError> {
+    first_snapshot(&[
+        "/mnt",
+        "timeshift",
+        "--create",
+        "--btrfs",
+        "--snapshot-device",
+        device,
+        "--comments",
+        "Fresh install",
+    ]);
+    ui::print_info("Enable scheduled snapshots from Timeshift after the first boot.");
+    Ok(())
+}
+
+/// The first snapshot is a nice-to-have: a failure is reported, not fatal.
+fn first_snapshot(args: &[&str]) {
+    if let Err(e) = cmd::run_with_spinner(
+        "artix-chroot",
+        args,
+        "Taking the first snapshot…",
+        "Snapshot 'Fresh install' taken.",
+    ) {
+        ui::print_warning(&format!("No initial snapshot: {} — take one after boot.", e));
+    }
+}
+
+/// Enables grub-btrfsd, which regenerates the snapshot submenu whenever a
+/// snapshot appears, and builds the menu once now.
+fn grub_btrfs(tool: Tool) -> Result<(), InstallerError> {
+    if tool == Tool::Timeshift {
+        // Timeshift keeps its snapshots under /run/timeshift, not /.snapshots.
+        if let Ok(original) = fs::read_to_string(GRUB_BTRFSD_CONF) {
+            let updated: String = original
+                .lines()
+                .map(|l| {
+                    if l.starts_with("optional_args=") {
+                        "optional_args=\"--timeshift-auto\"\n".to_string()
+                    } else {
+                        format!("{}\n", l)
+                    }
+                })
+                .collect();
+            if updated != original {
+                files::edit(GRUB_BTRFSD_CONF, &updated)?;
+            }
+        }
+    }
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", "grub-btrfsd", "default"],
+        "Enabling grub-btrfsd…",
+        "grub-btrfsd enabled at boot.",
+    )?;
+
+    if !cmd::dry_run() && !Path::new("/mnt/boot/grub/grub.cfg").is_file() {
+        ui::print_warning("GRUB isn't configured — snapshots won't appear in the boot menu.");
+        return Ok(());
+    }
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "grub-mkconfig", "-o", "/boot/grub/grub.cfg"],
+        "Adding snapshots to the boot menu…",
+        "Snapshots are bootable from the GRUB menu.",
+    )
+}
@@ -1,58 +1,146 @@
+use std::{fs, path::Path};
+
 use console::style;
-use dialoguer::Confirm;
+use crate::{
+    answers, cmd,
+    config::{Config, KernelVariant},
+    error::InstallerError,
+    prompt,
+    steps::{bootloader, identity, locale, network, users},
+    ui,
+};
 
-use crate::{cmd, error::InstallerError, ui};
+/// The settings the menu can redo, in menu order.
+const ENTRIES: &[(&str, &str)] = &[
+    ("hostname", "Hostname"),
+    ("timezone", "Timezone"),
+    ("locale", "Locale"),
+    ("passwords", "Passwords"),
+    ("bootloader", "Bootloader"),
+    ("network", "Network"),
+    ("shell", "Shell"),
+    ("done", "Done"),
+];
 
-/// Optionally enters the newly installed system via `artix-chroot`.
-pub fn run() -> Result<(), InstallerError> {
-    println!();
-    ui::print_kv_box(
-        "Post-chroot checklist",
-        &[
-            ("hostname", "echo myhostname > /etc/hostname"),
-            ("timezone", "ln -sf /usr/share/zoneinfo/…  /etc/localtime"),
-            ("locale", "edit /etc/locale.gen  →  locale-gen"),
-            ("password", "passwd"),
-            ("bootloader", "grub-install  →  grub-mkconfig"),
-            ("network", "pacman -S networkmanager  →  enable it"),
-        ],
-    );
-    println!();
+/// Services that bring the network up at boot.
+const NETWORK_SERVICES: &[&str] = &["NetworkManager", "dhcpcd", "connmand"];
+
+// ── Public API ────────────────────────────────────────────────────────────────
 
+/// The post-chroot configuration menu: each entry shows whether the setting
+/// is done in the new system and redoes it there when picked — hostname,
+/// timezone, locale, the root and `user` passwords, GRUB, the network
+/// backend — or opens an `artix-chroot` shell. A failing entry is reported
+/// and the menu shown again. Skipped in non-interactive mode.
+pub fn run(
+    config: &Config,
+    kernels: &[KernelVariant],
+    user: Option<&str>,
+) -> Result<(), InstallerError> {
+    // Nobody is at the keyboard in non-interactive mode.
+    if answers::unattended() {
+        ui::print_info("Enter the new system any time:  artix-chroot /mnt");
+        return Ok(());
+    }
+    // Answers given on the review screen would otherwise be reused silently.
+    prompt::forget("hostname");
+    prompt::forget("network.backend");
+
+    let values: Vec<&str> = ENTRIES.iter().map(|(id, _)| *id).collect();
+    loop {
+        let items: Vec<String> = ENTRIES
+            .iter()
+            .map(|(id, label)| format!("{:<12}{}", label, status(id, user)))
+            .collect();
+        println!();
+        let idx = prompt::select(
+            "chroot.menu",
+            "Configure the new system",
+            &items,
+            &values,
+            items.len() - 1,
+        )?;
+
+        let result = match ENTRIES[idx].0 {
+            "hostname" => identity::hostname(),
+            "timezone" => locale::timezone(),
+            "locale" => locale::run(),
+            "passwords" => users::passwords(user),
+            "bootloader" => bootloader::run(kernels, &config.efi_partition),
+            "network" => network::run(),
+            "shell" => shell(),
+            _ => return Ok(()),
+        };
+        if let Err(e) = result {
+            ui::print_error(&format!("{}", e));
+        }
+    }
+}
+
+/// Hands the terminal to `artix-chroot`.
+fn shell() -> Result<(), InstallerError> {
+    println!();
     println!(
         "  {}",
         style("Tip: type 'exit' or press Ctrl-D to leave the chroot.")
             .dim()
             .italic()
     );
+    println!("{}", style(ui::rule(52)).dim());
+    println!();
+    cmd::run_interactive("artix-chroot", &["/mnt"])?;
     println!();
+    println!("{}", style(ui::rule(52)).dim());
+    ui::print_success("Exited chroot.");
+    Ok(())
+}
 
-    if !Confirm::new()
-        .with_prompt("Enter the new system with artix-chroot now?")
-        .default(true)
-        .interact()?
-    {
-        println!();
-        ui::print_warning("Skipping chroot.");
-        ui::print_info("Enter manually any time:  artix-chroot /mnt");
-        return Ok(());
-    }
+// ── Status ────────────────────────────────────────────────────────────────────
 
-    println!();
-    ui::print_info("Entering chroot…");
-    println!("{}", style("─".repeat(52)).dim());
-    println!();
+/// `✓ <value>` for a setting in place, `✗ <what's missing>` otherwise.
+fn status(id: &str, user: Option<&str>) -> String {
+    let (done, what) = match id {
+        "hostname" => found(fs::read_to_string("/mnt/etc/hostname").ok(), "not set"),
+        "timezone" => found(locale::current_timezone(), "not set"),
+        "locale" => found(locale::current_lang(), "not set"),
+        "passwords" => {
+            let accounts: Vec<String> = ["root"]
+                .into_iter()
+                .chain(user)
+                .map(|a| format!("{} {}", a, if has_password(a) { "set" } else { "not set" }))
+                .collect();
+            (["root"].into_iter().chain(user).all(has_password), accounts.join(", "))
+        }
+        "bootloader" => {
+            let installed = Path::new("/mnt/boot/grub/grub.cfg").is_file();
+            (installed, if installed { "GRUB" } else { "not installed" }.to_string())
+        }
+        "network" => found(network_service(), "none enabled"),
+        _ => return String::new(),
+    };
+    format!("{} {}", if done { "✓" } else { "✗" }, what)
+}
 
-    // artix-chroot is fully interactive — hand over the terminal.
-    cmd::run_interactive("artix-chroot", &["/mnt"])?;
+fn found(value: Option<String>, missing: &str) -> (bool, String) {
+    match value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+        Some(v) => (true, v),
+        None => (false, missing.to_string()),
+    }
+}
 
-    println!();
-    println!("{}", style("─".repeat(52)).dim());
-    ui::print_success("Exited chroot.");
-    println!();
-    ui::print_info("Unmount and reboot when you are ready:");
-    ui::print_info("  umount -R /mnt && reboot");
-    println!();
+/// `true` when the account has a password hash in the target's shadow file.
+fn has_password(account: &str) -> bool {
+    let shadow = fs::read_to_string("/mnt/etc/shadow").unwrap_or_default();
+    shadow.lines().any(|l| {
+        let mut f = l.split(':');
+        f.next() == Some(account) && f.next().is_some_and(|hash| hash.starts_with('$'))
+    })
+}
 
-    Ok(())
+/// The network service in the default runlevel, if any.
+fn network_service() -> Option<String> {
+    let dir = fs::read_dir("/mnt/etc/runlevels/default").ok()?;
+    dir.flatten().filter_map(|e| e.file_name().into_string().ok()).find(|name| {
+        NETWORK_SERVICES.contains(&name.as_str()) || (name.starts_with("net.") && name != "net.lo")
+    })
 }
@@ -0,0 +1,138 @@
+use std::fs;
+
+use crate::{answers, cmd, error::InstallerError, prompt, report, ui};
+
+/// Where a plain clone (and the stow source) ends up, relative to `$HOME`.
+const CHECKOUT: &str = ".dotfiles";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Method {
+    /// Clone into `~/.dotfiles` and leave the rest to the user.
+    Clone,
+    /// Clone, then `stow` every top-level directory into `$HOME`.
+    Stow,
+    /// `chezmoi init --apply`, which clones and applies in one go.
+    Chezmoi,
+}
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Optionally bootstraps `user`'s dotfiles from a git repository inside
+/// the chroot, running as that user so every file is theirs.
+///
+/// Non-interactive mode reads `dotfiles.url` (no URL, no dotfiles) and
+/// `dotfiles.method`: `clone`, `stow` or `chezmoi`.
+pub fn run(user: Option<&str>) -> Result<(), InstallerError> {
+    let Some(user) = user else {
+        return Ok(());
+    };
+
+    let Some(url) = ask_url()? else {
+        return Ok(());
+    };
+    let url = url.as_str();
+
+    let items = [
+        format!("git clone   into ~/{}", CHECKOUT),
+        format!("GNU stow    clone into ~/{}, then stow each directory", CHECKOUT),
+        "chezmoi     chezmoi init --apply".to_string(),
+    ];
+    let method = match prompt::select(
+        "dotfiles.method",
+        "Bootstrap with",
+        &items,
+        &["clone", "stow", "chezmoi"],
+        0,
+    )? {
+        0 => Method::Clone,
+        1 => Method::Stow,
+        _ => Method::Chezmoi,
+    };
+
+    let mut pkgs = vec!["/mnt", "git"];
+    match method {
+        Method::Clone => {}
+        Method::Stow => pkgs.push("stow"),
+        Method::Chezmoi => pkgs.push("chezmoi"),
+    }
+    println!();
+    cmd::run_transaction("basestrap", &pkgs)?;
+
+    let home = format!("/home/{}", user);
+    let checkout = format!("{}/{}", home, CHECKOUT);
+    if method == Method::Chezmoi {
+        as_user(user, &home, &["chezmoi", "init", "--apply", url])?;
+    } else {
+        as_user(user, &home, &["git", "clone", "--recurse-submodules", url, &checkout])?;
+    }
+    if method == Method::Stow {
+        stow(user, &home, &checkout)?;
+    }
+
+    ui::print_success(&format!("Dotfiles from {} set up for {}.", url, user));
+    report::note("dotfiles", url);
+    Ok(())
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Runs `args` in the chroot as `user` with `HOME` set — no shell
+/// involved, so the URL is never interpreted.
+fn as_user(user: &str, home: &str, args: &[&str]) -> Result<(), InstallerError> {
+    let home_env = format!("HOME={}", home);
+    let mut full = vec!["/mnt", "runuser", "-u", user, "--", "env", &home_env];
+    full.extend(args);
+    cmd::run_interactive("artix-chroot", &full)
+}
+
+/// Stows each top-level directory of the checkout into `$HOME`.
+fn stow(user: &str, home: &str, checkout: &str) -> Result<(), InstallerError> {
+    let packages: Vec<String> = fs::read_dir(format!("/mnt{}", checkout))
+        .map(|dir| {
+            dir.flatten()
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter(|name| !name.starts_with('.'))
+                .collect()
+        })
+        .unwrap_or_default();
+    if packages.is_empty() && !cmd::dry_run() {
+        ui::print_warning("No directories to stow in the repository — left as cloned.");
+        return Ok(());
+    }
+
+    let mut args = vec!["stow", "-d", checkout, "-t", home];
+    args.extend(packages.iter().map(String::as_str));
+    as_user(user, home, &args)
+}
+
+/// The repository URL, `None` when left blank. Asked again until valid; an
+/// invalid URL in the answer file is an error.
+fn ask_url() -> Result<Option<String>, InstallerError> {
+    loop {
+        let url = match answers::get() {
+            Some(a) => a.str("dotfiles.url").unwrap_or_default().to_string(),
+            None => {
+                println!();
+                prompt::input("dotfiles.url", "Dotfiles git URL (blank to skip)", "")?
+            }
+        };
+        let url = url.trim();
+        if url.is_empty() {
+            return Ok(None);
+        }
+        if valid_url(url) {
+            return Ok(Some(url.to_string()));
+        }
+        if answers::get().is_some() {
+            return Err(InstallerError::Config(format!("'{}' isn't a git URL", url)));
+        }
+        ui::print_warning(&format!("'{}' isn't a git URL (https://, ssh://, git@…).", url));
+    }
+}
+
+/// https, ssh, git and scp-style (`git@host:repo`) URLs, with no whitespace.
+fn valid_url(url: &str) -> bool {
+    let schemes = ["https://", "http://", "ssh://", "git://", "git@"];
+    schemes.iter().any(|s| url.starts_with(s)) && !url.contains(char::is_whitespace)
+}
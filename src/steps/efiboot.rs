@@ -0,0 +1,121 @@
+use std::fs;
+
+use crate::{cmd, error::InstallerError, prompt, report, ui};
+
+/// The NVRAM entry label; matches `grub-install --bootloader-id`.
+pub const LABEL: &str = "artix";
+
+/// One `BootXXXX` line of `efibootmgr` output.
+struct Entry {
+    num: String,
+    label: String,
+}
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Makes sure the firmware has a boot entry for GRUB: grub-install usually
+/// creates one, but some firmwares drop or refuse it silently. A missing
+/// entry is created for `loader` on `efi_partition`; then the boot order is
+/// shown, with an offer to put the new entry first.
+///
+/// Non-interactive mode reads `boot_first` (default: yes).
+pub fn ensure_entry(efi_partition: &str, loader: &str) -> Result<(), InstallerError> {
+    let (mut entries, mut order) = read();
+    if !entries.iter().any(|e| e.label == LABEL) {
+        create(efi_partition, loader)?;
+        (entries, order) = read();
+    }
+    let Some(ours) = entries.iter().find(|e| e.label == LABEL).map(|e| e.num.clone()) else {
+        if !cmd::dry_run() {
+            ui::print_warning(&format!(
+                "The firmware shows no '{}' boot entry — pick {} from its boot menu, \
+                 or run efibootmgr after booting.",
+                LABEL, loader
+            ));
+            report::note("boot entry", "missing from NVRAM");
+        }
+        return Ok(());
+    };
+
+    let label_of = |num: &str| {
+        entries.iter().find(|e| e.num == num).map(|e| e.label.as_str()).unwrap_or("?")
+    };
+    let rows: Vec<(String, String)> = order
+        .iter()
+        .enumerate()
+        .map(|(i, num)| (format!("{}. Boot{}", i + 1, num), label_of(num).to_string()))
+        .collect();
+    let rows: Vec<(&str, &str)> = rows.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    println!();
+    ui::print_kv_box("Firmware boot order", &rows);
+    println!();
+
+    if order.first() == Some(&ours) {
+        ui::print_success(&format!("Boot{} ({}) boots first.", ours, LABEL));
+        report::note("boot entry", &format!("Boot{} {}, first", ours, LABEL));
+        return Ok(());
+    }
+    let ask = format!("Move Boot{} ({}) to the front of the boot order?", ours, LABEL);
+    if !prompt::confirm("boot_first", &ask, true)? {
+        report::note("boot entry", &format!("Boot{} {}, not first", ours, LABEL));
+        return Ok(());
+    }
+    order.retain(|n| *n != ours);
+    order.insert(0, ours.clone());
+    cmd::run_with_spinner(
+        "efibootmgr",
+        &["--bootorder", &order.join(",")],
+        "Updating the boot order…",
+        &format!("Boot{} ({}) boots first.", ours, LABEL),
+    )?;
+    report::note("boot entry", &format!("Boot{} {}, moved first", ours, LABEL));
+    Ok(())
+}
+
+// ── efibootmgr ────────────────────────────────────────────────────────────────
+
+/// The boot entries and the boot order; both empty when efibootmgr fails.
+fn read() -> (Vec<Entry>, Vec<String>) {
+    let out = cmd::run_capture("efibootmgr", &[]).unwrap_or_default();
+    let mut entries = Vec::new();
+    let mut order = Vec::new();
+    for line in out.lines() {
+        if let Some(list) = line.strip_prefix("BootOrder:") {
+            order = list.trim().split(',').map(str::to_string).collect();
+        } else if let Some(rest) = line.strip_prefix("Boot") {
+            // `Boot0003* artix	HD(1,GPT,…)/File(\EFI\artix\grubx64.efi)`
+            let num: String = rest.chars().take_while(char::is_ascii_hexdigit).collect();
+            if num.len() != 4 {
+                continue;
+            }
+            let label = rest[4..].trim_start_matches('*').split('\t').next().unwrap_or_default();
+            entries.push(Entry { num, label: label.trim().to_string() });
+        }
+    }
+    (entries, order)
+}
+
+/// `efibootmgr --create` for `loader` on the ESP's disk and partition number.
+fn create(efi_partition: &str, loader: &str) -> Result<(), InstallerError> {
+    let Some((disk, part)) = disk_and_number(efi_partition) else {
+        if !cmd::dry_run() {
+            ui::print_warning(&format!("Can't tell which disk {} is on.", efi_partition));
+        }
+        return Ok(());
+    };
+    cmd::run_with_spinner(
+        "efibootmgr",
+        &["--create", "--disk", &disk, "--part", &part, "--label", LABEL, "--loader", loader],
+        "Creating the firmware boot entry…",
+        "Boot entry created.",
+    )
+}
+
+/// `/dev/nvme0n1p1` → (`/dev/nvme0n1`, `1`), read from sysfs.
+fn disk_and_number(partition: &str) -> Option<(String, String)> {
+    let name = fs::canonicalize(partition).ok()?.file_name()?.to_str()?.to_string();
+    let sys = fs::canonicalize(format!("/sys/class/block/{}", name)).ok()?;
+    let number = fs::read_to_string(sys.join("partition")).ok()?;
+    let disk = sys.parent()?.file_name()?.to_str()?;
+    Some((format!("/dev/{}", disk), number.trim().to_string()))
+}
@@ -0,0 +1,59 @@
+use std::fs;
+
+use crate::{cmd, error::InstallerError, prompt, ui};
+
+/// Below this many bits of available entropy, key generation (pacman-key,
+/// cryptsetup) can stall for minutes on older hardware without a hardware RNG.
+const LOW_ENTROPY: u32 = 256;
+
+/// Checks the kernel entropy pool and, if it is starved, offers to start
+/// `haveged` in the live environment. Returns `true` if the user also wants
+/// it installed in the target (see [`install_in_target`]).
+pub fn check() -> Result<bool, InstallerError> {
+    let Some(avail) = entropy_avail() else {
+        return Ok(false);
+    };
+    if avail >= LOW_ENTROPY {
+        return Ok(false);
+    }
+
+    ui::print_warning(&format!(
+        "Low entropy ({} bits) — key generation may stall for a long time.",
+        avail
+    ));
+    println!();
+
+    if !prompt::confirm("haveged", "Start the haveged entropy daemon now? (recommended)", true)? {
+        return Ok(false);
+    }
+
+    // haveged daemonizes itself; -w sets the refill threshold.
+    cmd::run_with_spinner(
+        "haveged",
+        &["-w", "1024"],
+        "Starting haveged…",
+        "haveged running in the live environment.",
+    )?;
+
+    prompt::confirm("haveged_target", "Also install and enable haveged in the new system?", true)
+}
+
+/// Installs `haveged` + its OpenRC service into `/mnt` and enables it.
+pub fn install_in_target() -> Result<(), InstallerError> {
+    println!();
+    cmd::run_transaction("basestrap", &["/mnt", "haveged", "haveged-openrc"])?;
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", "haveged", "default"],
+        "Enabling haveged…",
+        "haveged enabled at boot.",
+    )
+}
+
+fn entropy_avail() -> Option<u32> {
+    fs::read_to_string("/proc/sys/kernel/random/entropy_avail")
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,167 @@
+use std::fs;
+
+use crate::{cmd, error::InstallerError, files, prompt, report, ui};
+
+const NFTABLES_CONF: &str = "/mnt/etc/nftables.conf";
+const NFTABLES_OPENRC: &str = "/mnt/etc/conf.d/nftables";
+const UFW_CONF: &str = "/mnt/etc/ufw/ufw.conf";
+
+/// Drops everything inbound except replies, loopback and ICMP — IPv6
+/// needs the latter for neighbour discovery. `{ssh}` opens port 22.
+const NFTABLES_RULES: &str = "#!/usr/bin/nft -f
+# Written by artix-installer: default-deny inbound.
+
+flush ruleset
+
+table inet filter {
+    chain input {
+        type filter hook input priority filter; policy drop;
+        ct state established,related accept
+        ct state invalid drop
+        iif lo accept
+        meta l4proto { icmp, ipv6-icmp } accept
+{ssh}    }
+
+    chain forward {
+        type filter hook forward priority filter; policy drop;
+    }
+
+    chain output {
+        type filter hook output priority filter; policy accept;
+    }
+}
+";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Firewall {
+    Nftables,
+    Ufw,
+}
+
+impl Firewall {
+    /// The package, service and answer-file value.
+    pub fn name(self) -> &'static str {
+        match self {
+            Firewall::Nftables => "nftables",
+            Firewall::Ufw => "ufw",
+        }
+    }
+}
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Optionally installs nftables or ufw with a default-deny inbound policy
+/// and enables its OpenRC service. Port 22 stays open when `ssh` is set
+/// (sshd was installed by the SSH step).
+///
+/// Non-interactive mode reads `firewall`: `nftables`, `ufw` or `none`.
+pub fn run(ssh: bool) -> Result<(), InstallerError> {
+    let Some(firewall) = ask()? else {
+        return Ok(());
+    };
+
+    let name = firewall.name();
+    let service_pkg = format!("{}-openrc", name);
+    println!();
+    cmd::run_transaction("basestrap", &["/mnt", name, &service_pkg])?;
+
+    match firewall {
+        Firewall::Nftables => nftables(ssh)?,
+        Firewall::Ufw => ufw(ssh)?,
+    }
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", name, "default"],
+        &format!("Enabling {}…", name),
+        &format!("{} enabled at boot.", name),
+    )?;
+
+    let open = if ssh { "SSH allowed" } else { "nothing allowed" };
+    ui::print_success(&format!("Inbound traffic denied by default, {}.", open));
+    report::note("firewall", &format!("{} (default deny, {})", name, open));
+    Ok(())
+}
+
+/// The firewall to set up, `None` for none.
+pub fn ask() -> Result<Option<Firewall>, InstallerError> {
+    let items = [
+        "nftables  plain ruleset in /etc/nftables.conf".to_string(),
+        "ufw       managed with ufw allow / deny".to_string(),
+        "None      no firewall".to_string(),
+    ];
+    println!();
+    let values = ["nftables", "ufw", "none"];
+    Ok(match prompt::select("firewall", "Firewall", &items, &values, 2)? {
+        0 => Some(Firewall::Nftables),
+        1 => Some(Firewall::Ufw),
+        _ => None,
+    })
+}
+
+// ── Backends ──────────────────────────────────────────────────────────────────
+
+/// Writes the ruleset and points the service at it — it otherwise loads
+/// the state saved on the last stop, which doesn't exist yet.
+fn nftables(ssh: bool) -> Result<(), InstallerError> {
+    let allow = if ssh { "        tcp dport 22 accept\n" } else { "" };
+    files::write_atomic(NFTABLES_CONF, &NFTABLES_RULES.replace("{ssh}", allow))?;
+
+    let settings = [("NFTABLES_SAVE", "\"/etc/nftables.conf\""), ("SAVE_ON_STOP", "\"no\"")];
+    set_vars(NFTABLES_OPENRC, &settings)
+}
+
+/// Sets the policies with the ufw CLI, which only records rules while the
+/// firewall is inactive, and marks it enabled for the next boot. Enabling
+/// it here would load the rules into the live system instead.
+fn ufw(ssh: bool) -> Result<(), InstallerError> {
+    let mut rules = vec![
+        vec!["default", "deny", "incoming"],
+        vec!["default", "allow", "outgoing"],
+    ];
+    if ssh {
+        rules.push(vec!["allow", "22/tcp"]);
+    }
+    for rule in rules {
+        let mut args = vec!["/mnt", "ufw"];
+        args.extend(&rule);
+        let what = rule.join(" ");
+        if let Err(e) = cmd::run_with_spinner(
+            "artix-chroot",
+            &args,
+            &format!("ufw {}…", what),
+            &format!("ufw {} recorded.", what),
+        ) {
+            ui::print_warning(&format!("ufw {} failed: {} — run it after boot.", what, e));
+        }
+    }
+    set_vars(UFW_CONF, &[("ENABLED", "yes")])
+}
+
+/// Sets `KEY=value` lines in a shell-style config file, replacing any
+/// existing (or commented-out) assignment and appending the rest.
+fn set_vars(path: &str, vars: &[(&str, &str)]) -> Result<(), InstallerError> {
+    let original = fs::read_to_string(path).unwrap_or_default();
+    let mut pending: Vec<&(&str, &str)> = vars.iter().collect();
+    let mut updated = String::new();
+    for line in original.lines() {
+        let bare = line.trim_start().trim_start_matches('#').trim_start();
+        match pending.iter().position(|(key, _)| bare.starts_with(&format!("{}=", key))) {
+            Some(i) => {
+                let (key, value) = pending.remove(i);
+                updated.push_str(&format!("{}={}\n", key, value));
+            }
+            None => updated.push_str(&format!("{}\n", line)),
+        }
+    }
+    for (key, value) in pending {
+        updated.push_str(&format!("{}={}\n", key, value));
+    }
+
+    if original.is_empty() {
+        files::write_atomic(path, &updated)
+    } else if updated != original {
+        files::edit(path, &updated).map(|_| ())
+    } else {
+        Ok(())
+    }
+}
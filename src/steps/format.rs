@@ -1,64 +1,265 @@
 use console::style;
-use dialoguer::Confirm;
-
-use crate::{cmd, config::Config, error::InstallerError, steps::partition::part_path, ui};
+use crate::{
+    cmd::Cmd,
+    config::{Config, RootFs},
+    error::InstallerError,
+    health, lsblk, prompt,
+    steps::{btrfs, partition::part_path, reinstall, zfs},
+    ui,
+};
 
 // ── Config builder ────────────────────────────────────────────────────────────
 
-/// Derives partition roles from the disk path using the fixed layout:
-///   p1 → EFI  (FAT32)
-///   p2 → swap
-///   p3 → root (ext4)
-///
-/// Shows a summary box and asks for confirmation before returning.
-pub fn build_config(disk: &str) -> Result<Config, InstallerError> {
-    let config = Config {
-        efi_partition:  part_path(disk, 1),
-        swap_partition: Some(part_path(disk, 2)),
-        root_partition: part_path(disk, 3),
-    };
+/// Shows the partition roles from `collect_layout` and asks for
+/// confirmation before they are formatted.
+pub fn confirm_layout(config: &Config) -> Result<(), InstallerError> {
+    let root_label = format!("Root ({})", config.root_fs.name());
+    let home = config.home_partition.as_ref().map(|h| format!("{} (kept)", h));
 
     println!();
     ui::print_kv_box(
         "Partition Layout",
         &[
             ("EFI  (FAT32)", config.efi_partition.as_str()),
-            ("Swap",         config.swap_partition.as_deref().unwrap()),
-            ("Root (ext4)",  config.root_partition.as_str()),
+            ("Swap",         config.swap_partition.as_deref().unwrap_or("none")),
+            (&root_label,    config.root_partition.as_str()),
+            ("/var (ext4)",  config.var_partition.as_deref().unwrap_or("on root")),
+            ("/home",        home.as_deref().unwrap_or("on root")),
+            ("/tmp",         if config.tmpfs_tmp { "tmpfs" } else { "on root" }),
         ],
     );
     println!();
     println!(
         "  {}",
-        style("⚠  THIS WILL PERMANENTLY ERASE THE SELECTED PARTITIONS.")
-            .red()
-            .bold()
+        style(format!(
+            "{}  THIS WILL PERMANENTLY ERASE THE SELECTED PARTITIONS.",
+            ui::warn_mark()
+        ))
+        .red()
+        .bold()
     );
     println!();
 
-    if !Confirm::new()
-        .with_prompt("Format these partitions?")
-        .default(false)
-        .interact()?
-    {
+    if !prompt::confirm("format", "Format these partitions?", false)? {
         return Err(InstallerError::Cancelled);
     }
 
+    Ok(())
+}
+
+/// Lets the user assign the EFI, swap, root and (optional) /var roles, then
+/// pick the root filesystem and whether /tmp is a tmpfs. Partitions from every
+/// detected disk are offered, so e.g. the ESP can live on an NVMe drive and
+/// root on a SATA SSD — except the live medium's and mounted ones (see
+/// [`free_to_format`]). The suggested layout on `disk` is preselected:
+///   p1 → EFI  (FAT32)
+///   p2 → swap
+///   p3 → root (ext4)
+///
+/// An older Artix install with its own /home partition may be reinstalled
+/// over instead: its roles are suggested and /home is kept unformatted.
+///
+/// Falls back to that fixed layout when the partitions can't be listed.
+pub fn collect_layout(disk: &str) -> Result<Config, InstallerError> {
+    let disks = lsblk::list_disks();
+    let multi_disk = disks.iter().filter(|d| !d.partitions.is_empty()).count() > 1;
+    let live = lsblk::live_disk(&disks).map(|d| d.path.as_str());
+
+    // (path, label) — labels name the parent disk once several are involved.
+    let parts: Vec<(String, String)> = disks
+        .iter()
+        .filter(|d| Some(d.path.as_str()) != live)
+        .flat_map(|d| {
+            d.partitions.iter().filter(|p| free_to_format(p)).map(move |p| {
+                let label = if multi_disk {
+                    format!("{}   [{}]", p.display(), d.model)
+                } else {
+                    p.display()
+                };
+                (p.path.clone(), label)
+            })
+        })
+        .collect();
+
+    let listed = disks.iter().any(|d| !d.partitions.is_empty());
+    if parts.is_empty() && listed {
+        return Err(InstallerError::InvalidLayout(
+            "every partition is mounted or on the live medium — none can be formatted".to_string(),
+        ));
+    }
+    let hidden = disks.iter().flat_map(|d| &d.partitions).count() - parts.len();
+    if hidden > 0 {
+        let why = match live {
+            Some(live) => format!("mounted, or on the live medium {}", live),
+            None => "mounted".to_string(),
+        };
+        ui::print_info(&format!("{} partition(s) in use aren't offered ({}).", hidden, why));
+    }
+
+    let previous = reinstall::offer(&disks)?;
+    let mut config = if parts.is_empty() {
+        Config::new(part_path(disk, 1), Some(part_path(disk, 2)), part_path(disk, 3))
+    } else {
+        assign_roles(disk, &parts, previous.as_ref())?
+    };
+    config.root_fs = ask_root_fs()?;
+    println!();
+    config.tmpfs_tmp =
+        prompt::confirm("tmpfs_tmp", "Mount /tmp as tmpfs (in RAM, emptied at boot)?", false)?;
+    Ok(config)
+}
+
+/// `false` for a partition in use by the live system. Mounts under `/mnt`
+/// and active swap are left from an earlier attempt at this install;
+/// `run` unmounts them before formatting.
+fn free_to_format(part: &lsblk::Partition) -> bool {
+    let m = part.mountpoint.as_str();
+    m.is_empty() || m == "[SWAP]" || m == "/mnt" || m.starts_with("/mnt/")
+}
+
+/// ext4, btrfs (subvolumes, snapshots), ZFS (opt-in pool with datasets),
+/// or bcachefs behind an explicit experimental warning.
+fn ask_root_fs() -> Result<RootFs, InstallerError> {
+    let items = [
+        "ext4      mature, the default".to_string(),
+        "btrfs     subvolumes @ and @home, snapshots with snapper or Timeshift".to_string(),
+        "zfs       pool + datasets, snapshots — module built with DKMS".to_string(),
+        "bcachefs  experimental — copy-on-write, checksums, compression".to_string(),
+    ];
+    let values = ["ext4", "btrfs", "zfs", "bcachefs"];
+    match prompt::select("root_fs", "Root filesystem", &items, &values, 0)? {
+        0 => return Ok(RootFs::Ext4),
+        1 => return Ok(RootFs::Btrfs),
+        2 => {
+            ui::print_info(&format!(
+                "The root partition becomes pool {} ({} at /). zfs-dkms and zfs-utils",
+                zfs::POOL,
+                zfs::ROOT_DATASET
+            ));
+            ui::print_info("come from the archzfs repository, which must be enabled.");
+            return Ok(RootFs::Zfs);
+        }
+        _ => {}
+    }
+
+    println!();
+    ui::print_warning("bcachefs is experimental — don't use it for data you can't lose.");
+    ui::print_info("Its kernel module is built with DKMS, GRUB can't read it (the kernels");
+    ui::print_info("stay on the FAT32 ESP), and on-disk format upgrades may need newer tools.");
+    println!();
+    if !prompt::confirm("bcachefs_experimental", "Use bcachefs anyway?", false)? {
+        ui::print_info("Using ext4.");
+        return Ok(RootFs::Ext4);
+    }
+    Ok(RootFs::Bcachefs)
+}
+
+/// Asks which partition plays each role. Swap and /var may be skipped. On a
+/// reinstall the old roles are suggested, and the kept /home can't take one.
+fn assign_roles(
+    disk: &str,
+    parts: &[(String, String)],
+    previous: Option<&reinstall::Previous>,
+) -> Result<Config, InstallerError> {
+    let labels: Vec<String> = parts.iter().map(|(_, l)| l.clone()).collect();
+    let paths: Vec<&str> = parts.iter().map(|(p, _)| p.as_str()).collect();
+    let none = parts.len();
+    let position = |want: &str| paths.iter().position(|p| *p == want);
+    let suggested = |n: u8, old: Option<&str>| {
+        old.and_then(position).or_else(|| position(&part_path(disk, n))).unwrap_or(0)
+    };
+    let old = |role: fn(&reinstall::Previous) -> Option<&str>| previous.and_then(role);
+
+    println!();
+    ui::print_info("Assign a role to each partition (↑ ↓ to move, Enter to choose).");
+
+    let efi_at = suggested(1, old(|p| p.efi.as_deref()));
+    let efi = prompt::select("efi", "EFI system partition", &labels, &paths, efi_at)?;
+
+    let mut swap_labels = labels.clone();
+    swap_labels.push("none — no swap partition".to_string());
+    let mut swap_paths = paths.clone();
+    swap_paths.push("none");
+    // A reinstall over a system without swap suggests none again.
+    let swap_at = match previous {
+        Some(p) if p.swap.is_none() => none,
+        _ => suggested(2, old(|p| p.swap.as_deref())),
+    };
+    let swap = prompt::select("swap", "Swap partition", &swap_labels, &swap_paths, swap_at)?;
+    let swap = (swap < parts.len()).then_some(swap);
+
+    let root_at = suggested(3, old(|p| Some(p.root.as_str())));
+    let root = prompt::select("root", "Root partition", &labels, &paths, root_at)?;
+
+    let mut var_labels = labels.clone();
+    var_labels.push("none — keep /var on the root partition".to_string());
+    let mut var_paths = paths.clone();
+    var_paths.push("none");
+    let var_at = old(|p| p.var.as_deref()).and_then(position).unwrap_or(none);
+    let var = prompt::select("var", "Separate /var partition", &var_labels, &var_paths, var_at)?;
+    let var = (var < none).then_some(var);
+
+    let mut roles = vec![efi, root];
+    roles.extend(swap);
+    roles.extend(var);
+    if roles.iter().enumerate().any(|(i, r)| roles[..i].contains(r)) {
+        return Err(InstallerError::InvalidLayout(
+            "each role needs its own partition".to_string(),
+        ));
+    }
+    let home = previous.map(|p| p.home.as_str());
+    if let Some(home) = home.filter(|h| roles.iter().any(|r| paths[*r] == *h)) {
+        return Err(InstallerError::InvalidLayout(format!(
+            "{} holds the /home being kept — it can't be formatted for another role",
+            home
+        )));
+    }
+
+    let mut config = Config::new(
+        paths[efi].to_string(),
+        swap.map(|i| paths[i].to_string()),
+        paths[root].to_string(),
+    );
+    config.var_partition = var.map(|i| paths[i].to_string());
+    config.home_partition = home.map(str::to_string);
     Ok(config)
 }
 
 // ── Formatting ────────────────────────────────────────────────────────────────
 
-/// Formats each partition: FAT32 (EFI), swap, ext4 (root).
-pub fn run(config: &Config) -> Result<(), InstallerError> {
+/// Formats each partition: FAT32 (EFI), swap, ext4 (/var), ext4, btrfs
+/// (plus its subvolumes) or bcachefs (root) — or creates the ZFS pool on
+/// the root partition.
+pub fn run(cmd: Cmd, config: &Config) -> Result<(), InstallerError> {
+    // The selectors never offer eMMC boot/RPMB areas, but a disk path typed
+    // by hand could still lead to one.
+    let targets = [
+        Some(&config.efi_partition),
+        config.swap_partition.as_ref(),
+        Some(&config.root_partition),
+        config.var_partition.as_ref(),
+    ];
+    if let Some(area) = targets.into_iter().flatten().find(|p| lsblk::is_emmc_hw_area(p)) {
+        return Err(InstallerError::InvalidLayout(format!(
+            "{} is an eMMC boot/RPMB area, not a partition — refusing to format it",
+            area
+        )));
+    }
+
+    health::power()?;
+
+    if let Some(prev) = reinstall::chosen().filter(|p| p.backup_etc) {
+        reinstall::backup_etc(cmd, prev)?;
+    }
+
     // Unmount anything left from a previous run before formatting.
     // umount -R /mnt covers root + EFI (/mnt/boot) in one shot.
-    cmd::run_best_effort("umount", &["-R", "/mnt"]);
+    cmd.run_best_effort("umount", &["-R", "/mnt"]);
     if let Some(ref swap) = config.swap_partition {
-        cmd::run_best_effort("swapoff", &[swap]);
+        cmd.run_best_effort("swapoff", &[swap]);
     }
 
-    cmd::run_with_spinner(
+    cmd.run_with_spinner(
         "mkfs.fat",
         &["-F32", &config.efi_partition],
         &format!("Formatting {} as FAT32…", config.efi_partition),
@@ -66,7 +267,7 @@ pub fn run(config: &Config) -> Result<(), InstallerError> {
     )?;
 
     if let Some(ref swap) = config.swap_partition {
-        cmd::run_with_spinner(
+        cmd.run_with_spinner(
             "mkswap",
             &[swap],
             &format!("Initialising swap on {}…", swap),
@@ -74,12 +275,109 @@ pub fn run(config: &Config) -> Result<(), InstallerError> {
         )?;
     }
 
-    cmd::run_with_spinner(
-        "mkfs.ext4",
-        &[&config.root_partition],
-        &format!("Formatting {} as ext4…", config.root_partition),
-        &format!("{} formatted as ext4 (root).", config.root_partition),
+    if let Some(ref var) = config.var_partition {
+        cmd.run_with_spinner(
+            "mkfs.ext4",
+            &[var],
+            &format!("Formatting {} as ext4…", var),
+            &format!("{} formatted as ext4 (/var).", var),
+        )?;
+    }
+
+    let Some((mkfs, opts)) = config.root_fs.mkfs() else {
+        return zfs::create_pool(cmd, &config.root_partition);
+    };
+    let mut args = opts.to_vec();
+    args.push(&config.root_partition);
+    let fs = config.root_fs.name();
+    cmd.run_with_spinner(
+        mkfs,
+        &args,
+        &format!("Formatting {} as {}…", config.root_partition, fs),
+        &format!("{} formatted as {} (root).", config.root_partition, fs),
     )?;
+    if config.root_fs == RootFs::Btrfs {
+        btrfs::create_subvolumes(cmd, &config.root_partition)?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::RecordingRunner;
+
+    fn layout(root_fs: RootFs, swap: bool, var: bool) -> Config {
+        let swap = swap.then(|| "/dev/sda2".to_string());
+        let mut config = Config::new("/dev/sda1".to_string(), swap, "/dev/sda3".to_string());
+        config.root_fs = root_fs;
+        config.var_partition = var.then(|| "/dev/sda4".to_string());
+        config
+    }
+
+    fn format(config: &Config) -> Vec<String> {
+        prompt::preset("battery_continue", "yes");
+        let runner = RecordingRunner::new(&[]);
+        run(runner.cmd(), config).unwrap();
+        runner.commands()
+    }
+
+    #[test]
+    fn ext4_with_swap_and_var() {
+        assert_eq!(
+            format(&layout(RootFs::Ext4, true, true)),
+            [
+                "umount -R /mnt",
+                "swapoff /dev/sda2",
+                "mkfs.fat -F32 /dev/sda1",
+                "mkswap /dev/sda2",
+                "mkfs.ext4 /dev/sda4",
+                "mkfs.ext4 /dev/sda3",
+            ]
+        );
+    }
+
+    #[test]
+    fn btrfs_creates_subvolumes() {
+        assert_eq!(
+            format(&layout(RootFs::Btrfs, false, false)),
+            [
+                "umount -R /mnt",
+                "mkfs.fat -F32 /dev/sda1",
+                "mkfs.btrfs -f /dev/sda3",
+                "mount /dev/sda3 /mnt",
+                "btrfs subvolume create /mnt/@",
+                "btrfs subvolume create /mnt/@home",
+                "umount /mnt",
+            ]
+        );
+    }
+
+    #[test]
+    fn mounted_partitions_are_not_offered() {
+        let part = |mountpoint: &str| lsblk::Partition {
+            path: "/dev/sdb1".to_string(),
+            size: "1G".to_string(),
+            fstype: "ext4".to_string(),
+            label: String::new(),
+            uuid: String::new(),
+            mountpoint: mountpoint.to_string(),
+        };
+        assert!(free_to_format(&part("")));
+        assert!(free_to_format(&part("/mnt/boot")));
+        assert!(free_to_format(&part("[SWAP]")));
+        assert!(!free_to_format(&part("/run/artix/bootmnt")));
+        assert!(!free_to_format(&part("/mntdata")));
+    }
+
+    #[test]
+    fn refuses_emmc_boot_area() {
+        let mut config = layout(RootFs::Ext4, false, false);
+        config.efi_partition = "/dev/mmcblk0boot0".to_string();
+        let runner = RecordingRunner::new(&[]);
+        let result = run(runner.cmd(), &config);
+        assert!(matches!(result, Err(InstallerError::InvalidLayout(_))));
+        assert!(runner.commands().is_empty());
+    }
+}
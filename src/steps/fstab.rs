@@ -1,20 +1,372 @@
-use crate::{cmd, error::InstallerError, ui};
+use std::{env, path::Path};
+
+use console::style;
+
+use crate::{
+    answers, cmd::{self, Cmd},
+    config::{Config, RootFs},
+    error::InstallerError,
+    files, prompt,
+    steps::portable,
+    ui,
+};
+
+const FSTAB: &str = "/mnt/etc/fstab";
+
+/// RAM-backed `/tmp`, capped at half the memory.
+const TMPFS_TMP: &str = "tmpfs\t/tmp\ttmpfs\trw,nosuid,nodev,size=50%,mode=1777\t0 0\n";
 
 /// Generates `/mnt/etc/fstab` using UUIDs via `fstabgen`.
 ///
-/// Equivalent to: `fstabgen -U /mnt >> /mnt/etc/fstab`
-pub fn generate() -> Result<(), InstallerError> {
+/// Equivalent to: `fstabgen -U /mnt >> /mnt/etc/fstab`, but the entries
+/// are prepared first (see `entries`) and the result is written atomically
+/// so a crash can't leave a truncated fstab behind. Entries a resumed run
+/// already appended are replaced, not duplicated. The result is shown for
+/// review afterwards (see `review`).
+pub fn generate(cmd: Cmd, config: &Config) -> Result<(), InstallerError> {
     // basestrap creates /mnt/etc, but guard just in case.
-    std::fs::create_dir_all("/mnt/etc")?;
+    if !cmd::dry_run() {
+        std::fs::create_dir_all("/mnt/etc")?;
+    }
+
+    let generated = entries(cmd, config)?;
+    let existing = std::fs::read_to_string(FSTAB).unwrap_or_default();
+    let fstab = without_regenerated(&existing, &generated) + &generated;
+    files::write_atomic(FSTAB, &fstab)?;
+
+    ui::print_success("fstab written to /mnt/etc/fstab.");
+    // A dry run has no real entries to judge.
+    if cmd::dry_run() {
+        return Ok(());
+    }
+    review(config, fstab)
+}
 
+/// The entries `fstabgen` finds under `/mnt`, with the fsck pass of each
+/// reviewed (see `review_pass`). ZFS datasets are left out: the pool mounts
+/// them itself. A tmpfs `/tmp` is added when the layout asks for one.
+fn entries(cmd: Cmd, config: &Config) -> Result<String, InstallerError> {
     let pb = ui::spinner("Generating /mnt/etc/fstab (UUID-based)…");
-    let result = cmd::run_append_to_file("fstabgen", &["-U", "/mnt"], "/mnt/etc/fstab");
+    let generated = match cmd.run_capture("fstabgen", &["-U", "/mnt"]) {
+        Ok(g) => g,
+        Err(e) => {
+            pb.finish_and_clear();
+            return Err(e);
+        }
+    };
+    ui::done_spinner(pb, "fstab entries generated.");
 
-    if result.is_ok() {
-        ui::done_spinner(pb, "fstab written to /mnt/etc/fstab.");
-    } else {
-        pb.finish_and_clear();
+    let generated: String = generated
+        .lines()
+        .filter(|l| l.split_whitespace().nth(2) != Some("zfs"))
+        .map(|l| format!("{}\n", l))
+        .collect();
+    let mut generated = review_pass(&generated)?;
+    if portable::enabled() {
+        generated = portable::noatime(&generated);
     }
+    let has_tmp = generated.lines().filter_map(parse_entry).any(|e| e.file == "/tmp");
+    if config.tmpfs_tmp && !has_tmp {
+        generated.push_str("# /tmp in RAM\n");
+        generated.push_str(TMPFS_TMP);
+    }
+    Ok(generated)
+}
+
+/// `existing` minus the entries `generated` writes again (same mountpoint,
+/// or same device for swap), along with the comment line fstabgen puts
+/// above each.
+fn without_regenerated(existing: &str, generated: &str) -> String {
+    let new: Vec<Entry> = generated.lines().filter_map(parse_entry).collect();
+    let replaced = |e: &Entry| {
+        new.iter().any(|n| match e.vfstype.as_str() {
+            "swap" => n.spec == e.spec,
+            _ => n.file == e.file,
+        })
+    };
+
+    let mut out = String::new();
+    let mut comments = String::new();
+    for line in existing.lines() {
+        if line.trim_start().starts_with('#') {
+            comments.push_str(line);
+            comments.push('\n');
+            continue;
+        }
+        match parse_entry(line) {
+            Some(e) if replaced(&e) => comments.clear(),
+            _ => {
+                out.push_str(&comments);
+                comments.clear();
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+    out + &comments
+}
 
-    result
+// ── Review ────────────────────────────────────────────────────────────────────
+
+/// Something in the fstab that looks wrong: the offending line, if there is
+/// one, and why.
+struct Issue {
+    line: Option<String>,
+    why: String,
+}
+
+/// Shows the fstab with suspicious entries highlighted and offers to open
+/// it in `$VISUAL` / `$EDITOR` (nano by default) — again after each edit,
+/// until the user moves on. Unattended runs only show it.
+fn review(config: &Config, mut fstab: String) -> Result<(), InstallerError> {
+    loop {
+        let issues = suspicious(config, &fstab);
+
+        println!();
+        for line in fstab.lines() {
+            let styled = if issues.iter().any(|i| i.line.as_deref() == Some(line)) {
+                style(line).yellow().bold()
+            } else {
+                style(line).dim()
+            };
+            println!("    {}", styled);
+        }
+        println!();
+        for issue in &issues {
+            ui::print_warning(&issue.why);
+        }
+
+        if answers::unattended() {
+            return Ok(());
+        }
+        let ask = if issues.is_empty() { "edit it?" } else { "fix it in an editor?" };
+        let ask = format!("Review {} — {}", FSTAB, ask);
+        if !prompt::confirm("fstab_edit", &ask, !issues.is_empty())? {
+            return Ok(());
+        }
+        open_editor(FSTAB)?;
+        match std::fs::read_to_string(FSTAB) {
+            Ok(edited) => fstab = edited,
+            Err(_) => return Ok(()),
+        }
+    }
+}
+
+/// An ESP mounted anywhere but /boot (where the kernels and GRUB are
+/// expected), a swap partition or root without an entry, plus whatever
+/// `validate` finds.
+fn suspicious(config: &Config, fstab: &str) -> Vec<Issue> {
+    let entries: Vec<(&str, Entry)> = fstab
+        .lines()
+        .filter_map(|l| parse_entry(l).map(|e| (l, e)))
+        .collect();
+    let mut issues = validate(&entries);
+
+    let esp: Vec<_> = entries.iter().filter(|(_, e)| e.vfstype == "vfat").collect();
+    for (line, e) in esp.iter().filter(|(_, e)| e.file != "/boot") {
+        issues.push(Issue {
+            line: Some(line.to_string()),
+            why: format!("A FAT32 partition is mounted at {} — the ESP belongs at /boot.", e.file),
+        });
+    }
+    if esp.is_empty() {
+        issues.push(Issue { line: None, why: "No entry for the ESP (/boot).".to_string() });
+    }
+    if let Some(ref swap) = config.swap_partition {
+        if !entries.iter().any(|(_, e)| e.vfstype == "swap") {
+            issues.push(Issue {
+                line: None,
+                why: format!("No swap entry although {} was set up as swap.", swap),
+            });
+        }
+    }
+    if config.root_fs != RootFs::Zfs && !entries.iter().any(|(_, e)| e.file == "/") {
+        issues.push(Issue { line: None, why: "No entry for the root filesystem (/).".to_string() });
+    }
+    issues
+}
+
+/// Per-entry checks: the UUID resolves via `blkid`, the mountpoint exists
+/// in the target, and no mountpoint (or swap device) appears twice.
+fn validate(entries: &[(&str, Entry)]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    for (i, (line, e)) in entries.iter().enumerate() {
+        let flag = |why: String| Issue { line: Some(line.to_string()), why };
+
+        if let Some(uuid) = e.spec.strip_prefix("UUID=") {
+            let found = cmd::run_capture("blkid", &["-U", uuid]);
+            if !found.is_ok_and(|dev| !dev.trim().is_empty()) {
+                issues.push(flag(format!("UUID {} ({}) matches no device.", uuid, e.file)));
+            }
+        }
+        if e.vfstype != "swap" && !Path::new(&format!("/mnt{}", e.file)).is_dir() {
+            issues.push(flag(format!("Mountpoint {} doesn't exist in the new system.", e.file)));
+        }
+        let same = |o: &Entry| match e.vfstype.as_str() {
+            "swap" => o.vfstype == "swap" && o.spec == e.spec,
+            _ => o.file == e.file,
+        };
+        if entries[..i].iter().any(|(_, o)| same(o)) {
+            issues.push(flag(format!("Duplicate entry for {}.", e.file)));
+        }
+    }
+    issues
+}
+
+fn open_editor(path: &str) -> Result<(), InstallerError> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "nano".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("nano");
+    let mut args: Vec<&str> = words.collect();
+    args.push(path);
+    cmd::run_interactive(program, &args)
+}
+
+// ── dump / pass fields ────────────────────────────────────────────────────────
+
+/// One fstab entry as produced by fstabgen.
+struct Entry {
+    spec: String,
+    file: String,
+    vfstype: String,
+    options: String,
+    pass: u8,
+}
+
+/// Sets the dump field to 0 and the fsck pass of every entry to a sane
+/// default — 1 for `/`, 0 for swap, btrfs and bcachefs (which check themselves
+/// at mount time), 2 for the rest — then lets the user adjust it.
+///
+/// Non-interactive mode takes overrides from `[fstab.pass]`, keyed by
+/// mountpoint: `"/boot" = 0`.
+fn review_pass(generated: &str) -> Result<String, InstallerError> {
+    let mut out = String::new();
+    let mut entries = Vec::new();
+    for line in generated.lines() {
+        match parse_entry(line) {
+            Some(e) => entries.push(e),
+            None => {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+    }
+
+    if entries.is_empty() {
+        return Ok(out);
+    }
+
+    for e in &mut entries {
+        let key = format!("fstab.pass.{}", e.file);
+        e.pass = match answers::get().and_then(|a| a.int(&key)) {
+            Some(n) => n.clamp(0, 2) as u8,
+            None => default_pass(&e.file, &e.vfstype),
+        };
+    }
+
+    let labels: Vec<String> = entries
+        .iter()
+        .map(|e| format!("pass {}  ({})", e.pass, e.vfstype))
+        .collect();
+    let rows: Vec<(&str, &str)> = entries
+        .iter()
+        .zip(&labels)
+        .map(|(e, l)| (e.file.as_str(), l.as_str()))
+        .collect();
+
+    println!();
+    ui::print_kv_box("fstab fsck order", &rows);
+    println!();
+
+    if !prompt::confirm("fstab_pass", "Use this fsck order?", true)? {
+        let items = [
+            "0 — never check".to_string(),
+            "1 — check first (root)".to_string(),
+            "2 — check after root".to_string(),
+        ];
+        for e in entries.iter_mut().filter(|e| e.vfstype != "swap") {
+            let idx = prompt::select(
+                "fstab_pass_entry",
+                &format!("fsck pass for {}", e.file),
+                &items,
+                &["0", "1", "2"],
+                e.pass as usize,
+            )?;
+            e.pass = idx as u8;
+        }
+    }
+
+    for e in &entries {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t0 {}\n",
+            e.spec, e.file, e.vfstype, e.options, e.pass
+        ));
+    }
+    Ok(out)
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    if line.trim_start().starts_with('#') {
+        return None;
+    }
+    let f: Vec<&str> = line.split_whitespace().collect();
+    if f.len() < 4 {
+        return None;
+    }
+    Some(Entry {
+        spec: f[0].to_string(),
+        file: f[1].to_string(),
+        vfstype: f[2].to_string(),
+        options: f[3].to_string(),
+        pass: 0,
+    })
+}
+
+fn default_pass(file: &str, vfstype: &str) -> u8 {
+    match (file, vfstype) {
+        (_, "swap" | "btrfs" | "bcachefs") => 0,
+        ("/", _) => 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::RecordingRunner;
+
+    const FSTABGEN: &str = "\
+# /dev/sda3
+UUID=1111\t/\text4\trw,relatime\t0 0
+zroot/home\t/home\tzfs\trw\t0 0
+";
+
+    #[test]
+    fn entries_from_fstabgen() {
+        prompt::preset("fstab_pass", "yes");
+        let mut config = Config::new("/dev/sda1".to_string(), None, "/dev/sda3".to_string());
+        config.tmpfs_tmp = true;
+        let runner = RecordingRunner::new(&[("fstabgen", FSTABGEN)]);
+        let entries = entries(runner.cmd(), &config).unwrap();
+        assert_eq!(runner.commands(), ["fstabgen -U /mnt"]);
+        let root = "# /dev/sda3\nUUID=1111\t/\text4\trw,relatime\t0 1\n";
+        assert_eq!(entries, format!("{}# /tmp in RAM\n{}", root, TMPFS_TMP));
+    }
+
+    #[test]
+    fn regenerated_entries_replace_old_ones() {
+        let existing = "\
+# /dev/sda3
+UUID=old\t/\text4\trw\t0 1
+# /dev/sda9
+UUID=9\t/data\text4\trw\t0 2
+";
+        let generated = "UUID=new\t/\text4\trw\t0 1\n";
+        assert_eq!(
+            without_regenerated(existing, generated),
+            "# /dev/sda9\nUUID=9\t/data\text4\trw\t0 2\n"
+        );
+    }
 }
@@ -0,0 +1,387 @@
+use std::{fs, path::Path};
+
+use console::style;
+
+use crate::{
+    cmd,
+    config::{Arch, KernelVariant},
+    error::InstallerError,
+    files, prompt, report,
+    steps::{bootloader, portable},
+    ui,
+};
+
+/// Written when the discrete GPU runs on Mesa; NVIDIA's comes from `nvidia-prime`.
+const PRIME_RUN: &str = "#!/bin/sh\n\
+# Installed by artix-installer: run a program on the discrete GPU.\n\
+DRI_PRIME=1 exec \"$@\"\n";
+
+/// Keeps nouveau from claiming the card before the NVIDIA module loads.
+const NOUVEAU_BLACKLIST: &str = "# Installed by artix-installer for the NVIDIA driver.\n\
+blacklist nouveau\n\
+options nouveau modeset=0\n";
+
+/// Kernel mode setting (needed for offload) and runtime D3 power management,
+/// so the NVIDIA card sleeps while nothing is offloaded to it.
+const NVIDIA_MODPROBE: &str = "# Installed by artix-installer for PRIME render offload.\n\
+options nvidia-drm modeset=1\n\
+options nvidia NVreg_DynamicPowerManagement=0x02\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Vendor {
+    Intel,
+    Amd,
+    Nvidia,
+}
+
+impl Vendor {
+    fn from_pci_id(id: &str) -> Option<Self> {
+        match id {
+            "0x8086" => Some(Vendor::Intel),
+            "0x1002" => Some(Vendor::Amd),
+            "0x10de" => Some(Vendor::Nvidia),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Vendor::Intel => "Intel",
+            Vendor::Amd => "AMD",
+            Vendor::Nvidia => "NVIDIA",
+        }
+    }
+}
+
+/// A display controller found in `/sys/bus/pci/devices`.
+#[derive(Debug, Clone)]
+struct Gpu {
+    vendor: Vendor,
+    /// PCI device ID, like `0x9a49`.
+    device: String,
+    slot: String,
+    /// The firmware initialised this one — the integrated GPU on a laptop.
+    boot_vga: bool,
+}
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// On machines with an integrated and a discrete GPU, offers PRIME render
+/// offload: the driver pair for both GPUs plus a `prime-run` command that
+/// starts a program on the discrete one. NVIDIA also gets kernel mode
+/// setting and runtime power management so the card sleeps when idle.
+/// A lone NVIDIA GPU gets the driver flow of [`nvidia`]. Either way, the
+/// video decoding drivers for each GPU follow ([`video`]). A portable
+/// install gets the open drivers for every vendor instead.
+pub fn run(kernels: &[KernelVariant]) -> Result<(), InstallerError> {
+    if portable::enabled() {
+        return generic();
+    }
+    let gpus = detect();
+    let proprietary = match hybrid_pair(gpus.clone()) {
+        Some((igpu, dgpu)) => prime(&igpu, &dgpu, kernels)?,
+        None if gpus.len() == 1 && gpus[0].vendor == Vendor::Nvidia => nvidia(kernels)?,
+        None => false,
+    };
+    video(&gpus, proprietary)
+}
+
+/// PRIME offload for a hybrid pair; `true` when the NVIDIA driver went in.
+fn prime(igpu: &Gpu, dgpu: &Gpu, kernels: &[KernelVariant]) -> Result<bool, InstallerError> {
+    let driver = if dgpu.vendor == Vendor::Nvidia {
+        ask_nvidia_driver(kernels, false)?
+    } else {
+        vec![]
+    };
+    let packages = packages_for(igpu.vendor, dgpu.vendor, &driver);
+    let rows = [
+        ("integrated", format!("{} ({})", igpu.vendor.name(), igpu.slot)),
+        ("discrete", format!("{} ({})", dgpu.vendor.name(), dgpu.slot)),
+        ("packages", packages.join(" ")),
+        ("offload", "prime-run <program>".to_string()),
+    ];
+    let rows: Vec<(&str, &str)> = rows.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+    println!();
+    ui::print_kv_box("Hybrid graphics detected", &rows);
+    println!();
+
+    if !prompt::confirm("prime", "Set up PRIME render offload?", true)? {
+        ui::print_info("Skipping — only the integrated GPU will be used by default.");
+        return Ok(false);
+    }
+
+    let mut args = vec!["/mnt"];
+    args.extend(packages.iter().copied());
+    println!();
+    cmd::run_transaction("basestrap", &args)?;
+
+    if dgpu.vendor == Vendor::Nvidia {
+        write_file("/mnt/etc/modprobe.d/nvidia-prime.conf", NVIDIA_MODPROBE)?;
+        configure_nvidia()?;
+    } else {
+        write_file("/mnt/usr/local/bin/prime-run", PRIME_RUN)?;
+        cmd::run_with_spinner(
+            "chmod",
+            &["755", "/mnt/usr/local/bin/prime-run"],
+            "Installing prime-run…",
+            "prime-run installed.",
+        )?;
+    }
+
+    ui::print_success(&format!(
+        "PRIME offload ready — start programs on the {} GPU with {}.",
+        dgpu.vendor.name(),
+        style("prime-run <program>").cyan()
+    ));
+    Ok(dgpu.vendor == Vendor::Nvidia)
+}
+
+/// `true` when the machine has an NVIDIA GPU, alone or next to another.
+pub fn has_nvidia() -> bool {
+    detect().iter().any(|g| g.vendor == Vendor::Nvidia)
+}
+
+/// Mesa with the Vulkan and VA-API drivers for Intel, AMD and NVIDIA
+/// (nouveau), so the system has working graphics on whatever machine it
+/// boots.
+fn generic() -> Result<(), InstallerError> {
+    let mut args = vec!["/mnt", "mesa", "libva-mesa-driver"];
+    if Arch::current() == Arch::X86_64 {
+        args.extend(["vulkan-intel", "vulkan-radeon", "vulkan-nouveau", "intel-media-driver"]);
+    }
+    println!();
+    ui::print_info("Portable mode — installing the open graphics drivers for every GPU vendor.");
+    cmd::run_transaction("basestrap", &args)?;
+    ui::print_success("Generic graphics drivers installed.");
+    Ok(())
+}
+
+// ── Video acceleration ────────────────────────────────────────────────────────
+
+/// Installs the VA-API / VDPAU drivers each GPU needs for hardware video
+/// decoding — without them video plays choppy, which nobody notices until
+/// after the install: `intel-media-driver` from Broadwell on, `libva-intel-driver`
+/// before it, `libva-mesa-driver` and `mesa-vdpau` for AMD and nouveau,
+/// and `libva-nvidia-driver` next to the proprietary driver.
+///
+/// Non-interactive mode reads `video_acceleration` (default: yes).
+fn video(gpus: &[Gpu], proprietary: bool) -> Result<(), InstallerError> {
+    let mut rows: Vec<(String, String)> = Vec::new();
+    let mut packages: Vec<&str> = Vec::new();
+    for gpu in gpus {
+        let needed: &[&str] = match gpu.vendor {
+            Vendor::Intel if legacy_intel(&gpu.device) => &["libva-intel-driver"],
+            Vendor::Intel => &["intel-media-driver"],
+            // VDPAU comes with the driver's nvidia-utils.
+            Vendor::Nvidia if proprietary => &["libva-nvidia-driver"],
+            Vendor::Amd | Vendor::Nvidia => &["libva-mesa-driver", "mesa-vdpau"],
+        };
+        rows.push((format!("{} ({})", gpu.vendor.name(), gpu.slot), needed.join(" ")));
+        for pkg in needed {
+            if !packages.contains(pkg) {
+                packages.push(pkg);
+            }
+        }
+    }
+    if packages.is_empty() {
+        return Ok(());
+    }
+
+    let rows: Vec<(&str, &str)> = rows.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    println!();
+    ui::print_kv_box("Hardware video decoding", &rows);
+    println!();
+    let ask = "Install the video decoding drivers (VA-API / VDPAU)?";
+    if !prompt::confirm("video_acceleration", ask, true)? {
+        return Ok(());
+    }
+
+    let mut args = vec!["/mnt"];
+    args.extend(packages.iter().copied());
+    println!();
+    cmd::run_transaction("basestrap", &args)?;
+    ui::print_success("Hardware video decoding installed — check it with vainfo (libva-utils).");
+    report::note("graphics", &format!("video decoding: {}", packages.join(" ")));
+    Ok(())
+}
+
+/// Intel GPUs before Broadwell (Gen 8), which `intel-media-driver` doesn't
+/// support. PCI device IDs from 0x1600 on are Broadwell or newer, except
+/// the old GMA range at 0x2xxx — Cherry Trail (0x22b0–0x22b3) aside.
+fn legacy_intel(device: &str) -> bool {
+    let Ok(id) = u32::from_str_radix(device.trim_start_matches("0x"), 16) else {
+        return false;
+    };
+    id < 0x1600 || ((0x2000..0x3000).contains(&id) && !(0x22b0..=0x22b3).contains(&id))
+}
+
+// ── NVIDIA ────────────────────────────────────────────────────────────────────
+
+/// A single NVIDIA GPU: installs the driver picked by
+/// [`ask_nvidia_driver`] with `nvidia-utils`, then sets up kernel mode
+/// setting, keeps nouveau out and rebuilds the initramfs. `true` when the
+/// driver went in.
+fn nvidia(kernels: &[KernelVariant]) -> Result<bool, InstallerError> {
+    println!();
+    ui::print_info("NVIDIA GPU detected.");
+    let mut packages = ask_nvidia_driver(kernels, true)?;
+    if packages.is_empty() {
+        ui::print_info("Keeping nouveau, the open driver that comes with the kernel.");
+        return Ok(false);
+    }
+    packages.push("nvidia-utils");
+
+    println!();
+    ui::print_kv_box(
+        "NVIDIA driver",
+        &[
+            ("packages", &packages.join(" ")),
+            ("cmdline", "nvidia_drm.modeset=1"),
+            ("nouveau", "blacklisted"),
+            ("initramfs", "rebuilt"),
+        ],
+    );
+    let mut args = vec!["/mnt"];
+    args.extend(packages.iter().copied());
+    println!();
+    cmd::run_transaction("basestrap", &args)?;
+    configure_nvidia()?;
+    ui::print_success("NVIDIA driver installed.");
+    Ok(true)
+}
+
+/// The NVIDIA driver packages to install. The prebuilt module (`nvidia`,
+/// `nvidia-lts`) only exists for a lone stable or LTS kernel; zen, or
+/// several kernels at once, take `nvidia-dkms` and every kernel's headers.
+/// With `allow_none`, nouveau can be kept instead (an empty list).
+///
+/// Non-interactive mode reads `nvidia_driver` (`prebuilt` | `dkms` |
+/// `none`; default: prebuilt where it exists).
+fn ask_nvidia_driver(
+    kernels: &[KernelVariant],
+    allow_none: bool,
+) -> Result<Vec<&'static str>, InstallerError> {
+    let prebuilt = match kernels {
+        [KernelVariant::Lts] => Some("nvidia-lts"),
+        [] | [KernelVariant::Stable] => Some("nvidia"),
+        _ => None,
+    };
+    let mut dkms = vec!["nvidia-dkms"];
+    dkms.extend(kernels.iter().map(|k| k.headers_package()));
+
+    let mut items = Vec::new();
+    let mut values = Vec::new();
+    if let Some(pkg) = prebuilt {
+        items.push(format!("{:<14}{}", pkg, style("prebuilt for this kernel").dim()));
+        values.push("prebuilt");
+    }
+    let kernel_list = if kernels.len() > 1 { "the kernels" } else { "the kernel" };
+    let dkms_note = format!("built against {} on every update", kernel_list);
+    items.push(format!("{:<14}{}", "nvidia-dkms", style(dkms_note).dim()));
+    values.push("dkms");
+    if allow_none {
+        items.push(format!("{:<14}{}", "nouveau", style("open driver, no install").dim()));
+        values.push("none");
+    }
+    let idx = prompt::select("nvidia_driver", "NVIDIA driver", &items, &values, 0)?;
+    Ok(match values[idx] {
+        "prebuilt" => prebuilt.into_iter().collect(),
+        "dkms" => dkms,
+        _ => vec![],
+    })
+}
+
+/// Kernel mode setting on the command line (Wayland and PRIME need it),
+/// nouveau blacklisted, and the initramfs rebuilt so both hold from early
+/// boot.
+fn configure_nvidia() -> Result<(), InstallerError> {
+    write_file("/mnt/etc/modprobe.d/blacklist-nouveau.conf", NOUVEAU_BLACKLIST)?;
+    if !bootloader::add_cmdline(&["nvidia_drm.modeset=1"])? {
+        ui::print_warning(
+            "GRUB's command line wasn't updated — add nvidia_drm.modeset=1 to it by hand.",
+        );
+    }
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "mkinitcpio", "-P"],
+        "Regenerating the initramfs…",
+        "Initramfs regenerated.",
+    )?;
+    report::note("graphics", "NVIDIA driver, nvidia_drm.modeset=1, nouveau blacklisted");
+    Ok(())
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+fn detect() -> Vec<Gpu> {
+    let Ok(entries) = fs::read_dir("/sys/bus/pci/devices") else {
+        return vec![];
+    };
+
+    entries
+        .flatten()
+        .filter_map(|e| {
+            let path = e.path();
+            let read = |f: &str| fs::read_to_string(path.join(f)).ok();
+            // 0x0300 VGA, 0x0302 3D controller, 0x0380 other display.
+            let class = read("class")?;
+            if !["0x0300", "0x0302", "0x0380"].iter().any(|c| class.starts_with(c)) {
+                return None;
+            }
+            Some(Gpu {
+                vendor: Vendor::from_pci_id(read("vendor")?.trim())?,
+                device: read("device").unwrap_or_default().trim().to_string(),
+                slot: e.file_name().to_string_lossy().into_owned(),
+                boot_vga: read("boot_vga").is_some_and(|b| b.trim() == "1"),
+            })
+        })
+        .collect()
+}
+
+/// Splits exactly two GPUs into (integrated, discrete). The boot VGA device
+/// is the integrated one; without that hint an Intel GPU is.
+fn hybrid_pair(mut gpus: Vec<Gpu>) -> Option<(Gpu, Gpu)> {
+    if gpus.len() != 2 {
+        return None;
+    }
+    let integrated = gpus
+        .iter()
+        .position(|g| g.boot_vga)
+        .or_else(|| gpus.iter().position(|g| g.vendor == Vendor::Intel))?;
+    let igpu = gpus.remove(integrated);
+    let dgpu = gpus.remove(0);
+    (dgpu.vendor != Vendor::Intel || igpu.vendor != Vendor::Intel).then_some((igpu, dgpu))
+}
+
+/// Mesa and Vulkan for each GPU; `nvidia` is the driver from
+/// [`ask_nvidia_driver`].
+fn packages_for(igpu: Vendor, dgpu: Vendor, nvidia: &[&'static str]) -> Vec<&'static str> {
+    let mut pkgs = vec!["mesa"];
+    for vendor in [igpu, dgpu] {
+        let extra: Vec<&'static str> = match vendor {
+            Vendor::Intel => vec!["vulkan-intel"],
+            Vendor::Amd => vec!["vulkan-radeon"],
+            Vendor::Nvidia => {
+                let mut driver = nvidia.to_vec();
+                driver.extend(["nvidia-utils", "nvidia-prime"]);
+                driver
+            }
+        };
+        for pkg in &extra {
+            if !pkgs.contains(pkg) {
+                pkgs.push(pkg);
+            }
+        }
+    }
+    pkgs
+}
+
+fn write_file(path: &str, content: &str) -> Result<(), InstallerError> {
+    if !cmd::dry_run() {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+    files::write_atomic(path, content)
+}
@@ -0,0 +1,187 @@
+use std::sync::OnceLock;
+
+use crate::{answers, cmd, error::InstallerError, files, prompt, ui};
+
+const HOSTNAME: &str = "/mnt/etc/hostname";
+const HOSTS: &str = "/mnt/etc/hosts";
+
+/// Generated once, so the review screen and the identity step agree.
+static SUGGESTION: OnceLock<String> = OnceLock::new();
+const MACHINE_ID: &str = "/mnt/etc/machine-id";
+const OS_RELEASE: &str = "/mnt/etc/os-release";
+
+/// Gives the new system its identity: hostname (plus the matching
+/// `/etc/hosts`), machine-id and an optional deployment tag in os-release.
+pub fn run() -> Result<(), InstallerError> {
+    hostname()?;
+    machine_id()?;
+    deployment_tag()
+}
+
+/// Asks for the hostname and writes it with the matching `/etc/hosts`.
+pub fn hostname() -> Result<(), InstallerError> {
+    println!();
+    let hostname = ask_hostname()?;
+    files::write_atomic(HOSTNAME, &format!("{}\n", hostname))?;
+    write_hosts(&hostname)?;
+    ui::print_success(&format!("Hostname set to {}.", hostname));
+    Ok(())
+}
+
+// ── Hostname ──────────────────────────────────────────────────────────────────
+
+/// Asks for a hostname until it passes `check_hostname`. The default is
+/// generated from the machine model: `artix-<model>-<rand>`. An invalid
+/// answer in the config file is an error rather than a retry.
+pub fn ask_hostname() -> Result<String, InstallerError> {
+    let suggestion = SUGGESTION.get_or_init(suggest_hostname);
+    loop {
+        let name = prompt::input("hostname", "Hostname", suggestion)?;
+        let name = name.trim().to_string();
+        match check_hostname(&name) {
+            Ok(()) => return Ok(name),
+            Err(why) if answers::get().is_some() => {
+                return Err(InstallerError::Config(format!("hostname '{}' {}", name, why)));
+            }
+            Err(why) => {
+                ui::print_warning(&format!("'{}' {} — try e.g. {}.", name, why, suggestion));
+            }
+        }
+    }
+}
+
+/// RFC 1123: dot-separated labels of 1–63 letters, digits and hyphens, not
+/// starting or ending with a hyphen; 253 characters at most.
+fn check_hostname(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() {
+        return Err("is empty");
+    }
+    if name.len() > 253 {
+        return Err("is longer than 253 characters");
+    }
+    if name.contains(char::is_whitespace) {
+        return Err("contains spaces");
+    }
+    if name.contains('_') {
+        return Err("contains underscores");
+    }
+    for label in name.split('.') {
+        if label.is_empty() {
+            return Err("has an empty label (two dots in a row or a dot at the end)");
+        }
+        if label.len() > 63 {
+            return Err("has a label longer than 63 characters");
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err("has a label starting or ending with '-'");
+        }
+        if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err("may only contain letters, digits, '-' and '.'");
+        }
+    }
+    Ok(())
+}
+
+/// `artix-<model>-<4 hex digits>`, the model taken from DMI and cut to a
+/// short lowercase slug (`artix-<rand>` when there is none).
+fn suggest_hostname() -> String {
+    let model = std::fs::read_to_string("/sys/class/dmi/id/product_name").unwrap_or_default();
+    let mut slug = String::new();
+    for c in model.trim().to_ascii_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(20);
+    let slug = slug.trim_end_matches('-');
+
+    let uuid = std::fs::read_to_string("/proc/sys/kernel/random/uuid").unwrap_or_default();
+    let rand: String = uuid.chars().filter(char::is_ascii_hexdigit).take(4).collect();
+
+    let mut name = "artix".to_string();
+    for part in [slug, rand.as_str()].into_iter().filter(|p| !p.is_empty()) {
+        name.push('-');
+        name.push_str(part);
+    }
+    name
+}
+
+/// Loopback entries plus the Debian/Arch `127.0.1.1` line for the hostname,
+/// so it resolves without a network.
+fn write_hosts(hostname: &str) -> Result<(), InstallerError> {
+    let short = hostname.split('.').next().unwrap_or(hostname);
+    let fqdn = if hostname.contains('.') {
+        hostname.to_string()
+    } else {
+        format!("{}.localdomain", hostname)
+    };
+    let hosts = format!(
+        "# Static table lookup for hostnames. See hosts(5).\n\
+         127.0.0.1\tlocalhost\n\
+         ::1\t\tlocalhost\n\
+         127.0.1.1\t{}\t{}\n",
+        fqdn, short
+    );
+
+    match std::fs::read_to_string(HOSTS) {
+        Ok(current) if !current.trim().is_empty() => {
+            files::edit(HOSTS, &hosts)?;
+        }
+        _ => files::write_atomic(HOSTS, &hosts)?,
+    }
+    Ok(())
+}
+
+/// Either generates `/etc/machine-id` now or leaves it empty, so a golden
+/// image cloned to many machines gets a fresh id on each first boot.
+fn machine_id() -> Result<(), InstallerError> {
+    let items = [
+        "Generate it now".to_string(),
+        "Leave it empty — generated on first boot (golden images)".to_string(),
+    ];
+    let idx = prompt::select("machine_id", "machine-id", &items, &["generate", "first-boot"], 0)?;
+
+    if idx == 1 {
+        files::write_atomic(MACHINE_ID, "")?;
+        ui::print_success("machine-id left empty for first boot.");
+        return Ok(());
+    }
+
+    let id = std::fs::read_to_string("/proc/sys/kernel/random/uuid")?.trim().replace('-', "");
+    files::write_atomic(MACHINE_ID, &format!("{}\n", id))?;
+    ui::print_success(&format!("machine-id generated ({}).", id));
+    Ok(())
+}
+
+/// Adds `ARTIX_DEPLOYMENT="<tag>"` to os-release when a tag is given.
+/// `/etc/os-release` is a symlink into /usr/lib; writing it replaces the
+/// link with a copy, so package upgrades don't drop the field.
+fn deployment_tag() -> Result<(), InstallerError> {
+    let tag = match answers::get() {
+        Some(a) => a.str("deployment_tag").unwrap_or_default().to_string(),
+        None => prompt::input("deployment_tag", "Deployment tag (blank for none)", "")?,
+    };
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Ok(());
+    }
+
+    let Ok(current) = std::fs::read_to_string(OS_RELEASE) else {
+        if !cmd::dry_run() {
+            ui::print_warning(&format!("{} not found — deployment tag not recorded.", OS_RELEASE));
+        }
+        return Ok(());
+    };
+
+    let mut lines: Vec<String> = current
+        .lines()
+        .filter(|l| !l.starts_with("ARTIX_DEPLOYMENT="))
+        .map(str::to_string)
+        .collect();
+    lines.push(format!("ARTIX_DEPLOYMENT=\"{}\"", tag.replace('"', "")));
+
+    files::edit(OS_RELEASE, &(lines.join("\n") + "\n"))?;
+    Ok(())
+}
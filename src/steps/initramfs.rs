@@ -0,0 +1,191 @@
+use std::fs;
+
+use crate::{
+    cmd,
+    config::{Config, RootFs},
+    error::InstallerError,
+    files, lsblk, prompt, report,
+    steps::{bootloader, locale, portable, zfs},
+    ui,
+};
+
+const MKINITCPIO_CONF: &str = "/mnt/etc/mkinitcpio.conf";
+
+/// Hooks that must sit between `block` and `filesystems`, in this order:
+/// assemble the array, unlock it, activate the volume group, bring in the
+/// bcachefs tools or import the ZFS pool, then resume.
+const STORAGE_HOOKS: &[&str] = &["mdadm_udev", "encrypt", "lvm2", "bcachefs", "zfs", "resume"];
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Adds the initramfs hooks the install needs to boot and regenerates the
+/// images with `mkinitcpio -P`:
+///   mdadm_udev / encrypt / lvm2 → root sits on RAID, dm-crypt or LVM
+///   bcachefs → bcachefs root (also `rootfstype=bcachefs`)
+///   zfs     → ZFS root pool (also `root=ZFS=<root dataset>`)
+///   resume  → hibernation to the swap partition (opt-in)
+///   keymap  → the console keymap chosen earlier, for the passphrase prompt
+///   consolefont → the console font chosen at startup, likewise
+///
+/// A portable install also drops `autodetect`, so the image keeps the
+/// storage and input drivers of every machine.
+///
+/// Kernel parameters the hooks rely on (`cryptdevice=`, `resume=`, …) go onto
+/// the GRUB command line. The images are rebuilt even without new hooks
+/// when a keymap or font was chosen, since they predate `/etc/vconsole.conf`.
+pub fn run(config: &Config) -> Result<(), InstallerError> {
+    let Ok(original) = fs::read_to_string(MKINITCPIO_CONF) else {
+        ui::print_warning(&format!("{} not found — is a kernel installed?", MKINITCPIO_CONF));
+        return Ok(());
+    };
+
+    let stack = root_stack(&config.root_partition);
+    let mut wanted = Vec::new();
+    let mut params = Vec::new();
+    if stack.iter().any(|(_, kind)| kind.starts_with("raid")) {
+        wanted.push("mdadm_udev");
+    }
+    if let Some((mapper, _)) = stack.iter().find(|(_, kind)| kind == "crypt") {
+        wanted.push("encrypt");
+        // The partition holding the LUKS header is the crypt device's parent.
+        let backing = stack.iter().skip_while(|(_, k)| k != "crypt").nth(1);
+        if let Some(uuid) = backing.and_then(|(dev, _)| lsblk::uuid_of(dev)) {
+            let name = mapper.rsplit('/').next().unwrap_or("cryptroot");
+            params.push(format!("cryptdevice=UUID={}:{}", uuid, name));
+        }
+    }
+    if stack.iter().any(|(_, kind)| kind == "lvm") {
+        wanted.push("lvm2");
+    }
+    if config.root_fs == RootFs::Bcachefs {
+        wanted.push("bcachefs");
+        params.push("rootfstype=bcachefs".to_string());
+    }
+    if config.root_fs == RootFs::Zfs {
+        wanted.push("zfs");
+        params.push(format!("root=ZFS={}", zfs::ROOT_DATASET));
+    }
+    if let Some(uuid) = &config.swap_uuid {
+        println!();
+        if prompt::confirm("hibernate", "Enable hibernation to the swap partition?", false)? {
+            wanted.push("resume");
+            params.push(format!("resume=UUID={}", uuid));
+        }
+    }
+    let keymap = locale::vconsole_keymap().filter(|k| k != "us");
+    if keymap.is_some() {
+        wanted.push("keymap");
+    }
+    let font = locale::vconsole_font();
+    if font.is_some() {
+        wanted.push("consolefont");
+    }
+
+    let Some(hooks) = current_hooks(&original) else {
+        ui::print_warning("No HOOKS=(…) line in mkinitcpio.conf — leaving it alone.");
+        return Ok(());
+    };
+    let mut updated_hooks = with_hooks(&hooks, &wanted);
+    // autodetect trims the image to this machine's modules.
+    if portable::enabled() {
+        updated_hooks.retain(|h| h != "autodetect");
+    }
+    if updated_hooks == hooks && keymap.is_none() && font.is_none() {
+        return Ok(());
+    }
+
+    if updated_hooks != hooks {
+        println!();
+        ui::print_kv_box(
+            "Initramfs hooks",
+            &[
+                ("current", &hooks.join(" ")),
+                ("new", &updated_hooks.join(" ")),
+            ],
+        );
+        println!();
+
+        if !files::edit(MKINITCPIO_CONF, &set_hooks(&original, &updated_hooks))? {
+            ui::print_warning("mkinitcpio.conf left unchanged — the new system may not boot.");
+            return Ok(());
+        }
+    }
+    if !params.is_empty() {
+        let params: Vec<&str> = params.iter().map(String::as_str).collect();
+        if !bootloader::add_cmdline(&params)? {
+            ui::print_warning(&format!(
+                "GRUB's command line wasn't updated — add {} to it by hand.",
+                params.join(" ")
+            ));
+        }
+    }
+
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "mkinitcpio", "-P"],
+        "Regenerating the initramfs…",
+        "Initramfs regenerated.",
+    )?;
+    report::note("initramfs", &updated_hooks.join(" "));
+    Ok(())
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+/// `(device, type)` for the root device and each layer beneath it, e.g.
+/// `lvm` → `crypt` → `part`.
+fn root_stack(root: &str) -> Vec<(String, String)> {
+    let args = ["--inverse", "--paths", "--noheadings", "--list", "--output", "NAME,TYPE", root];
+    let Ok(out) = cmd::run_capture("lsblk", &args) else {
+        return vec![];
+    };
+    out.lines()
+        .filter_map(|l| {
+            let mut cols = l.split_whitespace();
+            Some((cols.next()?.to_string(), cols.next()?.to_string()))
+        })
+        .collect()
+}
+
+// ── HOOKS editing ─────────────────────────────────────────────────────────────
+
+fn current_hooks(conf: &str) -> Option<Vec<String>> {
+    let value = conf.lines().find_map(|l| l.strip_prefix("HOOKS=("))?;
+    let value = value.split(')').next()?;
+    Some(value.split_whitespace().map(str::to_string).collect())
+}
+
+/// Adds each missing hook from `wanted` at its place: storage hooks before
+/// `filesystems` in `STORAGE_HOOKS` order, `keymap` and `consolefont` right
+/// before `block`.
+fn with_hooks(hooks: &[String], wanted: &[&str]) -> Vec<String> {
+    let mut out = hooks.to_vec();
+    let rank = |h: &str| STORAGE_HOOKS.iter().position(|s| *s == h);
+
+    for hook in wanted {
+        if out.iter().any(|h| h == hook) {
+            continue;
+        }
+        let at = match rank(hook) {
+            Some(r) => out
+                .iter()
+                .position(|h| h == "filesystems" || rank(h).is_some_and(|o| o > r)),
+            None => out.iter().position(|h| h == "block"),
+        };
+        out.insert(at.unwrap_or(out.len()), hook.to_string());
+    }
+    out
+}
+
+fn set_hooks(conf: &str, hooks: &[String]) -> String {
+    let mut out = String::with_capacity(conf.len());
+    for line in conf.lines() {
+        if line.starts_with("HOOKS=(") {
+            out.push_str(&format!("HOOKS=({})", hooks.join(" ")));
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,384 @@
+use std::{fs, path::Path, sync::OnceLock};
+
+use crate::{answers, cmd, error::InstallerError, files, prompt, ui};
+
+const LOCALE_GEN: &str = "/mnt/etc/locale.gen";
+const LOCALE_CONF: &str = "/mnt/etc/locale.conf";
+const KEYMAPS: &str = "/mnt/etc/conf.d/keymaps";
+const VCONSOLE_CONF: &str = "/mnt/etc/vconsole.conf";
+const XKB_CONF: &str = "/mnt/etc/X11/xorg.conf.d/00-keyboard.conf";
+const CONSOLEFONT: &str = "/mnt/etc/conf.d/consolefont";
+
+/// Console fonts on offer: (setfont name, what it suits). The Terminus
+/// `-v` variants carry the most glyphs; sizes are the cell height in pixels.
+const FONTS: &[(&str, &str)] = &[
+    ("default", "kernel built-in 8×16"),
+    ("ter-v16n", "Terminus 16 — 1080p and below"),
+    ("ter-v20n", "Terminus 20 — 1440p"),
+    ("ter-v24n", "Terminus 24 — 1800p and larger 1440p panels"),
+    ("ter-v28n", "Terminus 28 — small 4K panels"),
+    ("ter-v32n", "Terminus 32 — 4K laptops"),
+];
+
+/// The console font picked at startup; `None` for the kernel default.
+static FONT: OnceLock<Option<String>> = OnceLock::new();
+
+/// Locale, timezone, console keymap and X11 layout that usually go together.
+struct Bundle {
+    id: &'static str,
+    country: &'static str,
+    lang: &'static str,
+    timezone: &'static str,
+    keymap: &'static str,
+    xkb: &'static str,
+}
+
+const fn bundle(
+    id: &'static str,
+    country: &'static str,
+    lang: &'static str,
+    timezone: &'static str,
+    keymap: &'static str,
+    xkb: &'static str,
+) -> Bundle {
+    Bundle { id, country, lang, timezone, keymap, xkb }
+}
+
+#[rustfmt::skip]
+const BUNDLES: &[Bundle] = &[
+    bundle("us", "United States",  "en_US.UTF-8", "America/New_York",    "us",        "us"),
+    bundle("gb", "United Kingdom", "en_GB.UTF-8", "Europe/London",       "uk",        "gb"),
+    bundle("de", "Germany",        "de_DE.UTF-8", "Europe/Berlin",       "de-latin1", "de"),
+    bundle("fr", "France",         "fr_FR.UTF-8", "Europe/Paris",        "fr-latin1", "fr"),
+    bundle("es", "Spain",          "es_ES.UTF-8", "Europe/Madrid",       "es",        "es"),
+    bundle("it", "Italy",          "it_IT.UTF-8", "Europe/Rome",         "it",        "it"),
+    bundle("pt", "Portugal",       "pt_PT.UTF-8", "Europe/Lisbon",       "pt-latin1", "pt"),
+    bundle("br", "Brazil",         "pt_BR.UTF-8", "America/Sao_Paulo",   "br-abnt2",  "br"),
+    bundle("mx", "Mexico",         "es_MX.UTF-8", "America/Mexico_City", "la-latin1", "latam"),
+    bundle("nl", "Netherlands",    "nl_NL.UTF-8", "Europe/Amsterdam",    "us",        "us"),
+    bundle("pl", "Poland",         "pl_PL.UTF-8", "Europe/Warsaw",       "pl",        "pl"),
+    bundle("se", "Sweden",         "sv_SE.UTF-8", "Europe/Stockholm",    "sv-latin1", "se"),
+    bundle("ru", "Russia",         "ru_RU.UTF-8", "Europe/Moscow",       "ru",        "ru"),
+    bundle("jp", "Japan",          "ja_JP.UTF-8", "Asia/Tokyo",          "jp106",     "jp"),
+];
+
+/// The four settings as they will be applied.
+struct Settings {
+    lang: String,
+    timezone: String,
+    keymap: String,
+    xkb: String,
+}
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Sets locale, timezone, console keymap and X11 keyboard layout from one
+/// country choice, then lets each item be adjusted. "Skip" leaves all four
+/// for the chroot.
+///
+/// Non-interactive mode reads `locale.bundle` (a country id such as `"es"`)
+/// and applies any of `locale.lang`, `locale.timezone`, `locale.keymap`,
+/// `locale.xkb` on top.
+pub fn run() -> Result<(), InstallerError> {
+    let mut items: Vec<String> = BUNDLES
+        .iter()
+        .map(|b| format!("{:<16}{}  ·  {}  ·  {}", b.country, b.lang, b.timezone, b.keymap))
+        .collect();
+    items.push("Skip — set these up in the chroot".to_string());
+    let mut values: Vec<&str> = BUNDLES.iter().map(|b| b.id).collect();
+    values.push("skip");
+
+    println!();
+    let prompt = "Country (locale, timezone, keyboard)";
+    let idx = prompt::select("locale.bundle", prompt, &items, &values, 0)?;
+    let Some(b) = BUNDLES.get(idx) else {
+        ui::print_info("Skipping — configure timezone and locale from the chroot.");
+        return Ok(());
+    };
+
+    let settings = adjust(Settings {
+        lang: b.lang.to_string(),
+        timezone: b.timezone.to_string(),
+        keymap: b.keymap.to_string(),
+        xkb: b.xkb.to_string(),
+    })?;
+
+    set_timezone(&settings.timezone)?;
+    set_locale(&settings.lang)?;
+    set_keymap(&settings.keymap)?;
+    set_xkb(&settings.xkb)?;
+    Ok(())
+}
+
+/// Asks for the timezone alone, defaulting to the one already set.
+pub fn timezone() -> Result<(), InstallerError> {
+    let current = current_timezone().unwrap_or_else(|| "UTC".to_string());
+    println!();
+    let tz = prompt::input("locale.timezone", "Timezone (Region/City)", &current)?;
+    set_timezone(&tz)
+}
+
+/// The target's timezone, from the `/etc/localtime` link.
+pub fn current_timezone() -> Option<String> {
+    let link = fs::read_link("/mnt/etc/localtime").ok()?;
+    let link = link.to_string_lossy();
+    link.split_once("zoneinfo/").map(|(_, tz)| tz.to_string())
+}
+
+/// The target's default locale, from `/etc/locale.conf`.
+pub fn current_lang() -> Option<String> {
+    let conf = fs::read_to_string(LOCALE_CONF).ok()?;
+    conf.lines().find_map(|l| l.strip_prefix("LANG=")).map(|l| l.trim().to_string())
+}
+
+/// Shows the bundle and lets each item be changed.
+fn adjust(mut s: Settings) -> Result<Settings, InstallerError> {
+    if let Some(a) = answers::get() {
+        let pick = |key: &str, field: &mut String| {
+            if let Some(v) = a.str(key) {
+                *field = v.to_string();
+            }
+        };
+        pick("locale.lang", &mut s.lang);
+        pick("locale.timezone", &mut s.timezone);
+        pick("locale.keymap", &mut s.keymap);
+        pick("locale.xkb", &mut s.xkb);
+    }
+
+    println!();
+    ui::print_kv_box(
+        "Locale and keyboard",
+        &[
+            ("locale", &s.lang),
+            ("timezone", &s.timezone),
+            ("console", &s.keymap),
+            ("X11 layout", &s.xkb),
+        ],
+    );
+    println!();
+
+    if answers::unattended() || !prompt::confirm("locale.adjust", "Change any of these?", false)? {
+        return Ok(s);
+    }
+    Ok(Settings {
+        lang: prompt::input("locale.lang", "Locale", &s.lang)?,
+        timezone: prompt::input("locale.timezone", "Timezone (Region/City)", &s.timezone)?,
+        keymap: prompt::input("locale.keymap", "Console keymap", &s.keymap)?,
+        xkb: prompt::input("locale.xkb", "X11 keyboard layout", &s.xkb)?,
+    })
+}
+
+// ── Console font ──────────────────────────────────────────────────────────────
+
+/// Asks for a console font before anything else and switches the live
+/// console to it with `setfont`, so the rest of the run is readable on
+/// HiDPI panels. The choice is written to the target by [`install_font`].
+///
+/// Non-interactive mode reads `console_font` (a name from the list or any
+/// font in `/usr/share/kbd/consolefonts`).
+pub fn console_font() -> Result<(), InstallerError> {
+    let mut items: Vec<String> =
+        FONTS.iter().map(|(name, what)| format!("{:<10}{}", name, what)).collect();
+    let mut values: Vec<&str> = FONTS.iter().map(|(name, _)| *name).collect();
+
+    // An answer outside the list is taken as is: offer it as an extra item.
+    let custom = answers::get()
+        .and_then(|a| a.str("console_font"))
+        .filter(|f| !values.contains(f));
+    if let Some(font) = custom {
+        items.push(font.to_string());
+        values.push(font);
+    }
+
+    println!();
+    let idx = prompt::select("console_font", "Console font", &items, &values, suggested_font())?;
+    let font = (idx > 0).then(|| values[idx].to_string());
+    let _ = FONT.set(font.clone());
+    let Some(font) = font else {
+        return Ok(());
+    };
+
+    if let Err(e) = cmd::run_with_spinner(
+        "setfont",
+        &[&font],
+        &format!("Switching the console to {}…", font),
+        &format!("Console font set to {}.", font),
+    ) {
+        ui::print_warning(&format!("setfont {} failed: {} — keeping the current font.", font, e));
+    }
+    Ok(())
+}
+
+/// Index into [`FONTS`] that fits the framebuffer width: Terminus 32 from
+/// 4K down to the kernel default below 1920 pixels.
+fn suggested_font() -> usize {
+    let width = fs::read_to_string("/sys/class/graphics/fb0/virtual_size")
+        .ok()
+        .and_then(|s| s.split(',').next()?.trim().parse::<u32>().ok())
+        .unwrap_or(0);
+    match width {
+        3200.. => 5,
+        2800.. => 4,
+        2400.. => 3,
+        1920.. => 1,
+        _ => 0,
+    }
+}
+
+/// Installs Terminus in the target and makes the chosen font permanent:
+/// `FONT=` in `/etc/vconsole.conf` for the initramfs, `consolefont` in
+/// OpenRC's boot runlevel for the console.
+pub fn install_font() -> Result<(), InstallerError> {
+    let Some(Some(font)) = FONT.get() else {
+        return Ok(());
+    };
+
+    if font.starts_with("ter-") {
+        println!();
+        cmd::run_transaction("basestrap", &["/mnt", "terminus-font"])?;
+    }
+    set_var(VCONSOLE_CONF, "FONT=", font)?;
+    set_var(CONSOLEFONT, "consolefont=", &format!("\"{}\"", font))?;
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", "consolefont", "boot"],
+        "Enabling consolefont…",
+        "consolefont enabled at boot.",
+    )?;
+    ui::print_success(&format!("Console font {} set for the new system.", font));
+    Ok(())
+}
+
+// ── Applying ──────────────────────────────────────────────────────────────────
+
+fn set_timezone(tz: &str) -> Result<(), InstallerError> {
+    let zone = format!("/usr/share/zoneinfo/{}", tz.trim());
+    if !cmd::dry_run() && !Path::new(&format!("/mnt{}", zone)).is_file() {
+        ui::print_warning(&format!("Unknown timezone '{}' — set it from the chroot.", tz));
+        return Ok(());
+    }
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "ln", "-sf", &zone, "/etc/localtime"],
+        &format!("Setting timezone {}…", tz),
+        &format!("Timezone set to {}.", tz),
+    )
+}
+
+/// Uncomments `lang` in locale.gen, generates it and makes it the default.
+fn set_locale(lang: &str) -> Result<(), InstallerError> {
+    let lang = lang.trim();
+    let original = fs::read_to_string(LOCALE_GEN).unwrap_or_default();
+    let wanted = format!("{} ", lang);
+    let mut found = false;
+    let updated: String = original
+        .lines()
+        .map(|line| {
+            let bare = line.trim_start_matches('#');
+            if !found && bare.starts_with(&wanted) {
+                found = true;
+                format!("{}\n", bare)
+            } else {
+                format!("{}\n", line)
+            }
+        })
+        .collect();
+
+    if !found && !cmd::dry_run() {
+        ui::print_warning(&format!(
+            "{} isn't listed in locale.gen — set it from the chroot.",
+            lang
+        ));
+        return Ok(());
+    }
+    // A declined edit leaves the choice to the chroot; an unchanged file is fine.
+    if found && updated != original && !files::edit(LOCALE_GEN, &updated)? {
+        ui::print_info("Skipping — configure the locale from the chroot.");
+        return Ok(());
+    }
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "locale-gen"],
+        "Generating locales…",
+        &format!("{} generated.", lang),
+    )?;
+    files::write_atomic(LOCALE_CONF, &format!("LANG={}\n", lang))?;
+    ui::print_success(&format!("Default locale set to {}.", lang));
+    Ok(())
+}
+
+/// The console keymap for the initramfs `keymap` hook, if one was set.
+pub fn vconsole_keymap() -> Option<String> {
+    vconsole_value("KEYMAP=")
+}
+
+/// The console font for the initramfs `consolefont` hook, if one was set.
+pub fn vconsole_font() -> Option<String> {
+    vconsole_value("FONT=")
+}
+
+fn vconsole_value(prefix: &str) -> Option<String> {
+    let conf = fs::read_to_string(VCONSOLE_CONF).ok()?;
+    let value = conf.lines().find_map(|l| l.trim().strip_prefix(prefix))?;
+    let value = value.trim_matches('"');
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// OpenRC reads the console keymap from `/etc/conf.d/keymaps`; the
+/// initramfs `keymap` hook reads `/etc/vconsole.conf`, so both are written.
+fn set_keymap(keymap: &str) -> Result<(), InstallerError> {
+    let keymap = keymap.trim();
+    set_var(KEYMAPS, "keymap=", &format!("\"{}\"", keymap))?;
+    files::write_atomic(VCONSOLE_CONF, &format!("KEYMAP={}\n", keymap))?;
+    ui::print_success(&format!("Console keymap set to {}.", keymap));
+    Ok(())
+}
+
+fn set_xkb(layout: &str) -> Result<(), InstallerError> {
+    let layout = layout.trim();
+    if !cmd::dry_run() {
+        fs::create_dir_all("/mnt/etc/X11/xorg.conf.d")?;
+    }
+    let conf = format!(
+        "# Written by artix-installer.\n\
+         Section \"InputClass\"\n\
+         \x20   Identifier \"system-keyboard\"\n\
+         \x20   MatchIsKeyboard \"on\"\n\
+         \x20   Option \"XkbLayout\" \"{}\"\n\
+         EndSection\n",
+        layout
+    );
+    files::write_atomic(XKB_CONF, &conf)?;
+    ui::print_success(&format!("X11 keyboard layout set to {}.", layout));
+    Ok(())
+}
+
+/// Replaces the `prefix` line of a config file (or appends one) with
+/// `prefix` + `value`, creating the file when it doesn't exist.
+fn set_var(path: &str, prefix: &str, value: &str) -> Result<(), InstallerError> {
+    let original = fs::read_to_string(path).unwrap_or_default();
+    let line = format!("{}{}", prefix, value);
+    let mut replaced = false;
+    let mut updated: String = original
+        .lines()
+        .map(|l| {
+            if l.trim_start().starts_with(prefix) {
+                replaced = true;
+                format!("{}\n", line)
+            } else {
+                format!("{}\n", l)
+            }
+        })
+        .collect();
+    if !replaced {
+        updated.push_str(&format!("{}\n", line));
+    }
+
+    if original.is_empty() {
+        files::write_atomic(path, &updated)
+    } else if updated != original {
+        files::edit(path, &updated).map(|_| ())
+    } else {
+        Ok(())
+    }
+}
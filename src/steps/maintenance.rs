@@ -0,0 +1,90 @@
+use crate::{cmd, config::Config, error::InstallerError, files, lsblk, prompt, ui};
+
+/// A periodic job dropped into the target's `/etc/cron.<period>/`.
+struct Job {
+    period: &'static str,
+    name: &'static str,
+    what: &'static str,
+    script: &'static str,
+}
+
+const FSTRIM: Job = Job {
+    period: "weekly",
+    name: "fstrim",
+    what: "TRIM free blocks on SSDs",
+    script: "#!/bin/sh\n# Installed by artix-installer.\nexec fstrim --fstab\n",
+};
+
+const BTRFS_SCRUB: Job = Job {
+    period: "monthly",
+    name: "btrfs-scrub",
+    what: "verify btrfs checksums on /",
+    script: "#!/bin/sh\n# Installed by artix-installer.\nexec btrfs scrub start -B /\n",
+};
+
+/// Offers the maintenance jobs that fit the root filesystem: a weekly
+/// fstrim when it lives on an SSD, a monthly scrub when it is btrfs.
+/// The jobs run from cronie, which is installed and enabled if accepted.
+pub fn run(config: &Config) -> Result<(), InstallerError> {
+    let disks = lsblk::list_disks();
+    let root = disks.iter().find_map(|d| {
+        d.partitions
+            .iter()
+            .find(|p| p.path == config.root_partition)
+            .map(|p| (d, p))
+    });
+    let Some((disk, part)) = root else {
+        return Ok(());
+    };
+
+    let mut jobs = Vec::new();
+    if !disk.rotational {
+        jobs.push(&FSTRIM);
+    }
+    if part.fstype == "btrfs" {
+        jobs.push(&BTRFS_SCRUB);
+    }
+    if jobs.is_empty() {
+        return Ok(());
+    }
+
+    let rows: Vec<(String, String)> = jobs
+        .iter()
+        .map(|j| (format!("{} {}", j.period, j.name), j.what.to_string()))
+        .collect();
+    let rows: Vec<(&str, &str)> = rows.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+
+    println!();
+    ui::print_kv_box("Filesystem maintenance", &rows);
+    println!();
+
+    if !prompt::confirm("maintenance", "Install these maintenance jobs (cron)?", true)? {
+        return Ok(());
+    }
+
+    println!();
+    cmd::run_transaction("basestrap", &["/mnt", "cronie", "cronie-openrc"])?;
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", "cronie", "default"],
+        "Enabling cronie…",
+        "cronie enabled at boot.",
+    )?;
+
+    for job in jobs {
+        let dir = format!("/mnt/etc/cron.{}", job.period);
+        let path = format!("{}/{}", dir, job.name);
+        if !cmd::dry_run() {
+            std::fs::create_dir_all(&dir)?;
+        }
+        files::write_atomic(&path, job.script)?;
+        cmd::run_with_spinner(
+            "chmod",
+            &["755", &path],
+            &format!("Installing {}…", path),
+            &format!("{} {} job installed.", job.period, job.name),
+        )?;
+    }
+
+    Ok(())
+}
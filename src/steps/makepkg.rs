@@ -0,0 +1,136 @@
+use crate::{config::Arch, error::InstallerError, files, prompt, steps::portable, ui};
+
+const MAKEPKG_CONF: &str = "/mnt/etc/makepkg.conf";
+
+/// Optionally tunes `/mnt/etc/makepkg.conf` for fast local (AUR) builds:
+///   MAKEFLAGS   → `-j<cores>`
+///   CFLAGS      → `-march=native -mtune=native`
+///   COMPRESSZST → zstd with one thread per core
+///
+/// A portable system gets generic x86-64 code instead (see `march`), so its
+/// builds still run on the next machine it boots on.
+pub fn run() -> Result<(), InstallerError> {
+    let cores = cpu_cores();
+    let march = march();
+    let cflags = match march {
+        Some((arch, tune)) => format!("-march={} -mtune={}", arch, tune),
+        None => "unchanged (portable system)".to_string(),
+    };
+
+    ui::print_kv_box(
+        "makepkg.conf tuning",
+        &[
+            ("MAKEFLAGS", &format!("-j{}", cores)),
+            ("CFLAGS", &cflags),
+            ("COMPRESSZST", &format!("zstd -T{}", cores)),
+        ],
+    );
+    println!();
+    ui::print_info("Recommended if you plan to build AUR packages on this machine.");
+    println!();
+
+    if !prompt::confirm("makepkg", "Apply build optimizations to makepkg.conf?", true)? {
+        ui::print_warning("Skipping makepkg tuning — default build flags kept.");
+        return Ok(());
+    }
+
+    let Ok(original) = std::fs::read_to_string(MAKEPKG_CONF) else {
+        ui::print_warning(&format!("{} not found — is base-devel installed?", MAKEPKG_CONF));
+        return Ok(());
+    };
+
+    if !files::edit(MAKEPKG_CONF, &tune(&original, cores, march))? {
+        ui::print_warning("makepkg.conf left unchanged.");
+        return Ok(());
+    }
+
+    ui::print_success(&format!("makepkg.conf tuned for {} CPU cores.", cores));
+    Ok(())
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Number of logical CPUs available to the live system (falls back to 1).
+fn cpu_cores() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// `-march` and `-mtune` values: the host CPU, or on a portable system the
+/// generic x86-64 baseline. `None` leaves a portable ARM system's flags as
+/// shipped.
+fn march() -> Option<(&'static str, &'static str)> {
+    match (portable::enabled(), Arch::current()) {
+        (false, _) => Some(("native", "native")),
+        (true, Arch::X86_64) => Some(("x86-64", "generic")),
+        (true, Arch::Aarch64) => None,
+    }
+}
+
+/// Rewrites the relevant makepkg.conf lines, leaving everything else untouched.
+fn tune(conf: &str, cores: usize, march: Option<(&str, &str)>) -> String {
+    let mut out = String::with_capacity(conf.len());
+
+    for line in conf.lines() {
+        let trimmed = line.trim_start();
+        let tuned = if trimmed.starts_with("MAKEFLAGS=") || trimmed.starts_with("#MAKEFLAGS=") {
+            format!("MAKEFLAGS=\"-j{}\"", cores)
+        } else if trimmed.starts_with("COMPRESSZST=") {
+            format!("COMPRESSZST=(zstd -c -z -q -T{} -)", cores)
+        } else if let Some((arch, tune)) =
+            march.filter(|_| line.contains("-march=") || line.contains("-mtune="))
+        {
+            set_flag(&set_flag(line, "-march=", arch), "-mtune=", tune)
+        } else {
+            line.to_string()
+        };
+        out.push_str(&tuned);
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Replaces the value of `flag` (e.g. `-march=`) up to the next space or quote.
+fn set_flag(line: &str, flag: &str, value: &str) -> String {
+    let Some(start) = line.find(flag) else {
+        return line.to_string();
+    };
+    let val_start = start + flag.len();
+    let val_end = line[val_start..]
+        .find([' ', '"', '\\'])
+        .map(|i| val_start + i)
+        .unwrap_or(line.len());
+    format!("{}{}{}", &line[..val_start], value, &line[val_end..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONF: &str = "\
+CFLAGS=\"-march=x86-64 -mtune=generic -O2 -pipe\"
+#MAKEFLAGS=\"-j2\"
+COMPRESSZST=(zstd -c -z -q -)
+";
+
+    #[test]
+    fn tunes_for_the_host() {
+        assert_eq!(
+            tune(CONF, 8, Some(("native", "native"))),
+            "\
+CFLAGS=\"-march=native -mtune=native -O2 -pipe\"
+MAKEFLAGS=\"-j8\"
+COMPRESSZST=(zstd -c -z -q -T8 -)
+"
+        );
+    }
+
+    #[test]
+    fn portable_keeps_generic_code() {
+        let tuned = tune(CONF, 8, Some(("x86-64", "generic")));
+        assert!(tuned.starts_with("CFLAGS=\"-march=x86-64 -mtune=generic -O2 -pipe\"\n"));
+        assert!(tune(CONF, 8, None).starts_with("CFLAGS=\"-march=x86-64 -mtune=generic"));
+    }
+}
@@ -1,8 +1,33 @@
+pub mod accessibility;
+pub mod bootloader;
+pub mod btrfs;
 pub mod chroot;
+pub mod dotfiles;
+pub mod efiboot;
+pub mod entropy;
+pub mod firewall;
 pub mod fstab;
 pub mod format;
+pub mod graphics;
+pub mod identity;
+pub mod initramfs;
+pub mod locale;
+pub mod maintenance;
+pub mod makepkg;
 pub mod mount;
+pub mod network;
 pub mod ntp;
+pub mod pacman;
 pub mod packages;
 pub mod partition;
+pub mod postinstall;
+pub mod portable;
+pub mod power;
+pub mod reboot;
+pub mod reinstall;
+pub mod review;
+pub mod ssh;
 pub mod uefi;
+pub mod users;
+pub mod verify;
+pub mod zfs;
@@ -1,47 +1,341 @@
-use crate::{cmd, config::Config, error::InstallerError};
+use std::{sync::Mutex, time::Duration};
+
+use crate::{
+    cmd::{self, Cmd},
+    config::{Config, RootFs},
+    error::InstallerError,
+    log,
+    steps::{btrfs, zfs},
+    ui,
+};
+
+const MOUNT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Something the installer activated, kept so it can be undone in reverse.
+#[derive(Debug, Clone)]
+enum Mounted {
+    Dir(String),
+    Swap(String),
+    /// An imported ZFS pool; undone by exporting it.
+    Pool,
+}
+
+/// Everything mounted or swapped on by this run, in order.
+static MOUNTED: Mutex<Vec<Mounted>> = Mutex::new(Vec::new());
 
 /// Mounts all partitions into the installation tree under `/mnt`.
 ///
 /// Mount order:
-///   1. Root  → /mnt
-///   2. Swap  → swapon
-///   3. Create /mnt/boot
-///   4. EFI   → /mnt/boot
-pub fn run(config: &Config) -> Result<(), InstallerError> {
+///   1. Root  → /mnt (a ZFS pool is imported with its altroot there; btrfs
+///      mounts `@` there and `@home` at /mnt/home)
+///   2. /var  → /mnt/var (optional; needs root mounted first), then a
+///      /home kept by a reinstall → /mnt/home (over btrfs' `@home`)
+///   3. Swap  → swapon
+///   4. Create /mnt/boot
+///   5. EFI   → /mnt/boot
+///
+/// A tmpfs `/tmp` is only written to fstab: the install itself doesn't
+/// need it.
+pub fn run(cmd: Cmd, config: &Config) -> Result<(), InstallerError> {
     // 1. Root
-    cmd::run_with_spinner(
-        "mount",
-        &[&config.root_partition, "/mnt"],
-        &format!("Mounting {} → /mnt…", config.root_partition),
-        &format!("{} mounted at /mnt.", config.root_partition),
-    )?;
+    if config.root_fs == RootFs::Zfs {
+        zfs::mount(cmd)?;
+        register(Mounted::Pool);
+    } else if config.root_fs == RootFs::Btrfs {
+        let kept_home = config.home_partition.is_some();
+        for (subvol, target) in btrfs::SUBVOLUMES {
+            if kept_home && *subvol == "@home" {
+                continue;
+            }
+            cmd.run_with_spinner(
+                "mkdir",
+                &["-p", target],
+                &format!("Creating {}…", target),
+                &format!("Directory {} created.", target),
+            )?;
+            mount_with_timeout(
+                cmd,
+                &config.root_partition,
+                target,
+                &["-o", &btrfs::mount_options(subvol)],
+                &format!("Mounting {} ({}) → {}…", config.root_partition, subvol, target),
+                &format!("{} mounted at {}.", subvol, target),
+            )?;
+        }
+    } else {
+        mount_with_timeout(
+            cmd,
+            &config.root_partition,
+            "/mnt",
+            &[],
+            &format!("Mounting {} → /mnt…", config.root_partition),
+            &format!("{} mounted at /mnt.", config.root_partition),
+        )?;
+    }
 
-    // 2. Swap (optional) — deactivate first in case it's already active (resume).
-    if let Some(ref swap) = config.swap_partition {
-        cmd::run_best_effort("swapoff", &[swap]);
-        cmd::run_with_spinner(
-            "swapon",
-            &[swap],
-            &format!("Activating swap on {}…", swap),
-            &format!("Swap on {} activated.", swap),
+    // 2. /var (optional)
+    if let Some(ref var) = config.var_partition {
+        cmd.run_with_spinner(
+            "mkdir",
+            &["-p", "/mnt/var"],
+            "Creating /mnt/var…",
+            "Directory /mnt/var created.",
+        )?;
+        mount_with_timeout(
+            cmd,
+            var,
+            "/mnt/var",
+            &[],
+            &format!("Mounting {} → /mnt/var…", var),
+            &format!("{} mounted at /mnt/var.", var),
+        )?;
+    }
+    if let Some(ref home) = config.home_partition {
+        cmd.run_with_spinner(
+            "mkdir",
+            &["-p", "/mnt/home"],
+            "Creating /mnt/home…",
+            "Directory /mnt/home created.",
         )?;
+        mount_with_timeout(
+            cmd,
+            home,
+            "/mnt/home",
+            &[],
+            &format!("Mounting the kept {} → /mnt/home…", home),
+            &format!("{} mounted at /mnt/home.", home),
+        )?;
+    }
+
+    // 3. Swap (optional) — may still be active from an interrupted run.
+    if let Some(ref swap) = config.swap_partition {
+        if swap_active(cmd, swap) {
+            ui::print_success(&format!("Swap on {} already active.", swap));
+        } else {
+            cmd.run_with_spinner(
+                "swapon",
+                &[swap],
+                &format!("Activating swap on {}…", swap),
+                &format!("Swap on {} activated.", swap),
+            )?;
+        }
+        register(Mounted::Swap(swap.clone()));
     }
 
-    // 3. Create the boot mount-point
-    cmd::run_with_spinner(
+    // 4. Create the boot mount-point
+    cmd.run_with_spinner(
         "mkdir",
         &["-p", "/mnt/boot"],
         "Creating /mnt/boot…",
         "Directory /mnt/boot created.",
     )?;
 
-    // 4. EFI / boot
-    cmd::run_with_spinner(
-        "mount",
-        &[&config.efi_partition, "/mnt/boot"],
+    // 5. EFI / boot
+    mount_with_timeout(
+        cmd,
+        &config.efi_partition,
+        "/mnt/boot",
+        &[],
         &format!("Mounting {} → /mnt/boot…", config.efi_partition),
         &format!("{} mounted at /mnt/boot.", config.efi_partition),
     )?;
 
     Ok(())
 }
+
+/// A mount that hangs (dying disk, stale device) shouldn't freeze the
+/// installer; on timeout the user may retry it, skip it or abort.
+///
+/// On resume `target` may still be mounted: the right device is kept (and
+/// remounted read-write if it came back read-only), anything else there is
+/// an error rather than a mount stacked on top.
+fn mount_with_timeout(
+    cmd: Cmd,
+    device: &str,
+    target: &str,
+    options: &[&str],
+    spin_msg: &str,
+    done_msg: &str,
+) -> Result<(), InstallerError> {
+    if let Some((source, options)) = mounted_at(cmd, target) {
+        if !same_device(&source, device) {
+            return Err(InstallerError::InvalidLayout(format!(
+                "{} is already mounted at {} — unmount it first",
+                source, target
+            )));
+        }
+        if options.split(',').any(|o| o == "ro") {
+            cmd.run_with_spinner(
+                "mount",
+                &["-o", "remount,rw", target],
+                &format!("Remounting {} read-write…", target),
+                &format!("{} remounted read-write.", target),
+            )?;
+        } else {
+            ui::print_success(&format!("{} already mounted at {}.", device, target));
+        }
+        register(Mounted::Dir(target.to_string()));
+        return Ok(());
+    }
+
+    let mut args = options.to_vec();
+    args.extend([device, target]);
+    if cmd.run_with_timeout("mount", &args, spin_msg, done_msg, MOUNT_TIMEOUT)? {
+        register(Mounted::Dir(target.to_string()));
+    } else {
+        ui::print_warning(&format!(
+            "Skipped: mount {} {} — make sure it is mounted.",
+            device, target
+        ));
+    }
+    Ok(())
+}
+
+/// Source device and mount options of whatever is mounted at `target`.
+/// With stacked mounts the last (visible) one wins. A btrfs subvolume's
+/// `[/@]` suffix is dropped from the source.
+fn mounted_at(cmd: Cmd, target: &str) -> Option<(String, String)> {
+    let out = cmd.run_capture("findmnt", &["-rn", "-o", "SOURCE,OPTIONS", "-M", target]).ok()?;
+    let mut fields = out.lines().last()?.split_whitespace();
+    let source = fields.next()?;
+    let source = source.split('[').next().unwrap_or(source);
+    Some((source.to_string(), fields.next().unwrap_or("").to_string()))
+}
+
+fn same_device(a: &str, b: &str) -> bool {
+    let canon = |p: &str| std::fs::canonicalize(p).unwrap_or_else(|_| p.into());
+    canon(a) == canon(b)
+}
+
+fn swap_active(cmd: Cmd, device: &str) -> bool {
+    cmd.run_capture("swapon", &["--show=NAME", "--noheadings"])
+        .is_ok_and(|out| out.lines().any(|l| same_device(l.trim(), device)))
+}
+
+// ── Mount registry ────────────────────────────────────────────────────────────
+
+fn register(m: Mounted) {
+    log::line(&format!("registered {:?}", m));
+    MOUNTED.lock().unwrap().push(m);
+}
+
+/// Undoes everything in the registry, most recent first, so `/mnt/boot`
+/// is always released before `/mnt`.
+/// Keeps going past failures; returns `false` if anything stayed active.
+pub fn unmount_all() -> bool {
+    let mounted = std::mem::take(&mut *MOUNTED.lock().unwrap());
+    let mut clean = true;
+
+    for m in mounted.iter().rev() {
+        let result = match m {
+            Mounted::Dir(target) => cmd::run_with_spinner(
+                "umount",
+                &[target],
+                &format!("Unmounting {}…", target),
+                &format!("{} unmounted.", target),
+            ),
+            Mounted::Swap(dev) => cmd::run_with_spinner(
+                "swapoff",
+                &[dev],
+                &format!("Deactivating swap on {}…", dev),
+                &format!("Swap on {} deactivated.", dev),
+            ),
+            Mounted::Pool => zfs::export(),
+        };
+        if let Err(e) = result {
+            ui::print_warning(&format!("{}", e));
+            clean = false;
+        }
+    }
+
+    clean
+}
+
+/// Failure path: releases whatever this run mounted so the next attempt
+/// doesn't hit "target is busy". Anything the registry missed is caught
+/// by a recursive unmount of `/mnt`.
+pub fn teardown() {
+    if MOUNTED.lock().unwrap().is_empty() {
+        return;
+    }
+
+    println!();
+    ui::print_info("Cleaning up — unmounting the target before exiting.");
+    if !unmount_all() {
+        cmd::run_best_effort("umount", &["-R", "/mnt"]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::RecordingRunner;
+
+    const FINDMNT: &str = "findmnt -rn -o SOURCE,OPTIONS -M";
+
+    #[test]
+    fn ext4_with_var_and_swap() {
+        let mut config = Config::new(
+            "/dev/sda1".to_string(),
+            Some("/dev/sda2".to_string()),
+            "/dev/sda3".to_string(),
+        );
+        config.var_partition = Some("/dev/sda4".to_string());
+        let runner = RecordingRunner::new(&[]);
+        run(runner.cmd(), &config).unwrap();
+        assert_eq!(
+            runner.commands(),
+            [
+                format!("{} /mnt", FINDMNT),
+                "mount /dev/sda3 /mnt".to_string(),
+                "mkdir -p /mnt/var".to_string(),
+                format!("{} /mnt/var", FINDMNT),
+                "mount /dev/sda4 /mnt/var".to_string(),
+                "swapon --show=NAME --noheadings".to_string(),
+                "swapon /dev/sda2".to_string(),
+                "mkdir -p /mnt/boot".to_string(),
+                format!("{} /mnt/boot", FINDMNT),
+                "mount /dev/sda1 /mnt/boot".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn btrfs_mounts_subvolumes() {
+        let mut config = Config::new("/dev/sda1".to_string(), None, "/dev/sda3".to_string());
+        config.root_fs = RootFs::Btrfs;
+        let runner = RecordingRunner::new(&[]);
+        run(runner.cmd(), &config).unwrap();
+        let mut expected = Vec::new();
+        for (subvol, target) in btrfs::SUBVOLUMES {
+            expected.push(format!("mkdir -p {}", target));
+            expected.push(format!("{} {}", FINDMNT, target));
+            expected.push(format!(
+                "mount -o {} /dev/sda3 {}",
+                btrfs::mount_options(subvol),
+                target
+            ));
+        }
+        expected.push("mkdir -p /mnt/boot".to_string());
+        expected.push(format!("{} /mnt/boot", FINDMNT));
+        expected.push("mount /dev/sda1 /mnt/boot".to_string());
+        assert_eq!(runner.commands(), expected);
+    }
+
+    #[test]
+    fn keeps_a_mount_already_in_place() {
+        let config = Config::new("/dev/sda1".to_string(), None, "/dev/sda3".to_string());
+        let runner = RecordingRunner::new(&[("findmnt", "/dev/sda3 rw,relatime\n")]);
+        // Everything looks mounted by /dev/sda3, so /mnt/boot is refused.
+        let result = run(runner.cmd(), &config);
+        assert!(matches!(result, Err(InstallerError::InvalidLayout(_))));
+        assert_eq!(
+            runner.commands(),
+            [
+                format!("{} /mnt", FINDMNT),
+                "mkdir -p /mnt/boot".to_string(),
+                format!("{} /mnt/boot", FINDMNT),
+            ]
+        );
+    }
+}
@@ -0,0 +1,421 @@
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    os::unix::fs::PermissionsExt,
+};
+
+use crate::{answers, cmd, error::InstallerError, files, prompt, report, ui};
+
+const DHCPCD_CONF: &str = "/mnt/etc/dhcpcd.conf";
+const NETIFRC_CONF: &str = "/mnt/etc/conf.d/net";
+const NM_CONNECTIONS: &str = "/mnt/etc/NetworkManager/system-connections";
+
+/// First line of the `interface` block `dhcpcd` adds to dhcpcd.conf.
+const DHCPCD_MARKER: &str = "# Static configuration written by artix-installer.";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    NetworkManager,
+    Dhcpcd,
+    Netifrc,
+}
+
+impl Backend {
+    /// The answer-file value; [`Backend::name`] is the service's name.
+    pub fn id(self) -> &'static str {
+        match self {
+            Backend::NetworkManager => "networkmanager",
+            Backend::Dhcpcd => "dhcpcd",
+            Backend::Netifrc => "netifrc",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::NetworkManager => "NetworkManager",
+            Backend::Dhcpcd => "dhcpcd",
+            Backend::Netifrc => "netifrc",
+        }
+    }
+
+    fn packages(self) -> &'static [&'static str] {
+        match self {
+            Backend::NetworkManager => &["networkmanager", "networkmanager-openrc"],
+            Backend::Dhcpcd => &["dhcpcd", "dhcpcd-openrc"],
+            Backend::Netifrc => &["netifrc"],
+        }
+    }
+}
+
+/// A static configuration for one interface. IPv6 is optional; without it
+/// the interface still picks up router advertisements.
+struct Static {
+    interface: String,
+    address: (Ipv4Addr, u8),
+    gateway: Ipv4Addr,
+    address6: Option<(Ipv6Addr, u8)>,
+    gateway6: Option<Ipv6Addr>,
+    dns: Vec<IpAddr>,
+}
+
+impl Static {
+    fn dns_of(&self, v6: bool) -> Vec<String> {
+        self.dns.iter().filter(|ip| ip.is_ipv6() == v6).map(IpAddr::to_string).collect()
+    }
+}
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Installs and enables the network backend, then optionally gives one
+/// interface a static IPv4 (and IPv6) address, gateway and DNS servers in
+/// that backend's own format.
+///
+/// Non-interactive mode reads `network.backend` (`networkmanager`, `dhcpcd`,
+/// `netifrc` or `none`), `network.static`, `network.interface`,
+/// `network.address`, `network.gateway`, `network.address6`,
+/// `network.gateway6` and `network.dns`.
+pub fn run() -> Result<(), InstallerError> {
+    let Some(backend) = ask_backend()? else {
+        ui::print_info("No network backend — the new system boots offline.");
+        return Ok(());
+    };
+
+    let wanted = prompt::confirm("network.static", "Configure a static IP address?", false)?;
+    let config = if wanted { Some(ask_static()?) } else { None };
+
+    let mut pkgs = vec!["/mnt"];
+    pkgs.extend(backend.packages());
+    // netifrc leaves DHCP to an external client.
+    if backend == Backend::Netifrc && config.is_none() {
+        pkgs.push("dhcpcd");
+    }
+    println!();
+    cmd::run_transaction("basestrap", &pkgs)?;
+
+    let service = match backend {
+        Backend::NetworkManager => {
+            if let Some(s) = &config {
+                network_manager(s)?;
+            }
+            backend.name().to_string()
+        }
+        Backend::Dhcpcd => {
+            if let Some(s) = &config {
+                dhcpcd(s)?;
+            }
+            backend.name().to_string()
+        }
+        Backend::Netifrc => {
+            // netifrc has no catch-all: each interface gets its own service.
+            let interface = match &config {
+                Some(s) => s.interface.clone(),
+                None => ask_interface()?,
+            };
+            netifrc(&interface, config.as_ref())?
+        }
+    };
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", &service, "default"],
+        &format!("Enabling {}…", service),
+        &format!("{} enabled at boot.", service),
+    )?;
+
+    let how = match &config {
+        Some(s) => format!("{} static on {}", service, s.interface),
+        None => format!("{} DHCP", service),
+    };
+    report::note("network", &how);
+    Ok(())
+}
+
+// ── Questions ─────────────────────────────────────────────────────────────────
+
+/// The network backend, `None` to leave networking for after boot.
+pub fn ask_backend() -> Result<Option<Backend>, InstallerError> {
+    let items = [
+        "NetworkManager  wired + Wi-Fi, nmcli / nmtui, desktop applets".to_string(),
+        "dhcpcd          lightweight DHCP client for wired machines".to_string(),
+        "netifrc         OpenRC's own per-interface scripts (servers)".to_string(),
+        "None            configure networking after boot".to_string(),
+    ];
+    let choices = [Backend::NetworkManager, Backend::Dhcpcd, Backend::Netifrc];
+    let mut values: Vec<&str> = choices.iter().map(|b| b.id()).collect();
+    values.push("none");
+    println!();
+    let idx = prompt::select("network.backend", "Network backend", &items, &values, 0)?;
+    Ok(choices.get(idx).copied())
+}
+
+fn ask_static() -> Result<Static, InstallerError> {
+    let interface = ask_interface()?;
+    let ask = "IPv4 address/prefix (e.g. 192.168.1.10/24)";
+    let address = ask_value("network.address", ask, |v| parse_cidr(v, 32))?;
+    let gateway = ask_value("network.gateway", "IPv4 gateway", |v| v.parse().ok())?;
+
+    let ask = "IPv6 address/prefix (blank for none)";
+    let address6 = optional_value("network.address6", ask, |v| parse_cidr(v, 128))?;
+    let gateway6 = match address6 {
+        Some(_) => {
+            let ask = "IPv6 gateway (blank: from router advertisements)";
+            optional_value("network.gateway6", ask, |v| v.parse().ok())?
+        }
+        None => None,
+    };
+
+    // A list in the answer file, or one string of addresses.
+    let dns = match answers::get().and_then(|a| a.list("network.dns")) {
+        Some(list) => parse_dns(&list.join(" ")).ok_or_else(|| {
+            InstallerError::Config(format!("network.dns: {:?} has an invalid address", list))
+        })?,
+        None => {
+            let ask = "DNS servers (space-separated, blank for none)";
+            ask_value("network.dns", ask, parse_dns)?
+        }
+    };
+
+    Ok(Static { interface, address, gateway, address6, gateway6, dns })
+}
+
+/// Interfaces backed by hardware, as the live system names them — the new
+/// system follows the same naming rules on the same machine.
+fn ask_interface() -> Result<String, InstallerError> {
+    let mut names: Vec<String> = fs::read_dir("/sys/class/net")
+        .map(|dir| {
+            dir.flatten()
+                .filter(|e| e.path().join("device").exists())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+
+    if let Some(name) = answers::get().and_then(|a| a.str("network.interface")) {
+        return Ok(name.to_string());
+    }
+    if names.is_empty() {
+        return prompt::input("network.interface", "Interface", "eth0");
+    }
+    let values: Vec<&str> = names.iter().map(String::as_str).collect();
+    let idx = prompt::select("network.interface", "Interface", &names, &values, 0)?;
+    Ok(names[idx].clone())
+}
+
+/// A required value, asked again until `parse` accepts it; a bad or
+/// missing answer-file value is an error.
+fn ask_value<T>(
+    key: &str,
+    ask: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<T, InstallerError> {
+    loop {
+        let value = match answers::get() {
+            Some(a) => a.str(key).unwrap_or_default().to_string(),
+            None => prompt::input(key, ask, "")?,
+        };
+        let value = value.trim();
+        if let Some(parsed) = parse(value) {
+            return Ok(parsed);
+        }
+        if answers::get().is_some() {
+            return Err(InstallerError::Config(format!("{}: '{}' isn't valid", key, value)));
+        }
+        ui::print_warning(&format!("'{}' isn't valid here.", value));
+    }
+}
+
+/// Like [`ask_value`], but blank means `None`.
+fn optional_value<T>(
+    key: &str,
+    ask: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<Option<T>, InstallerError> {
+    let blank = match answers::get() {
+        Some(a) => a.str(key).is_none_or(|v| v.trim().is_empty()),
+        None => false,
+    };
+    if blank {
+        return Ok(None);
+    }
+    ask_value(key, ask, |v| if v.is_empty() { Some(None) } else { parse(v).map(Some) })
+}
+
+/// `address/prefix`, with the prefix at most `max`.
+fn parse_cidr<T: std::str::FromStr>(value: &str, max: u8) -> Option<(T, u8)> {
+    let (addr, prefix) = value.split_once('/')?;
+    let prefix: u8 = prefix.parse().ok().filter(|p| *p <= max)?;
+    Some((addr.parse().ok()?, prefix))
+}
+
+/// Space- or comma-separated addresses, `None` if any doesn't parse.
+fn parse_dns(value: &str) -> Option<Vec<IpAddr>> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().ok())
+        .collect()
+}
+
+// ── Backends ──────────────────────────────────────────────────────────────────
+
+/// A keyfile connection profile, readable by root only as NetworkManager
+/// demands.
+fn network_manager(s: &Static) -> Result<(), InstallerError> {
+    let id = format!("static-{}", s.interface);
+    let (addr, prefix) = s.address;
+    let mut profile = format!(
+        "# Written by artix-installer.\n\
+         [connection]\nid={id}\ntype=ethernet\ninterface-name={}\n\n\
+         [ipv4]\nmethod=manual\naddress1={}/{},{}\n",
+        s.interface, addr, prefix, s.gateway,
+    );
+    let dns4 = s.dns_of(false);
+    if !dns4.is_empty() {
+        profile.push_str(&format!("dns={};\n", dns4.join(";")));
+    }
+    profile.push_str("\n[ipv6]\n");
+    match s.address6 {
+        Some((addr, prefix)) => {
+            profile.push_str(&format!("method=manual\naddress1={}/{}", addr, prefix));
+            if let Some(gw) = s.gateway6 {
+                profile.push_str(&format!(",{}", gw));
+            }
+            profile.push('\n');
+        }
+        None => profile.push_str("method=auto\n"),
+    }
+    let dns6 = s.dns_of(true);
+    if !dns6.is_empty() {
+        profile.push_str(&format!("dns={};\n", dns6.join(";")));
+    }
+
+    let path = format!("{}/{}.nmconnection", NM_CONNECTIONS, id);
+    if !cmd::dry_run() {
+        fs::create_dir_all(NM_CONNECTIONS)?;
+    }
+    files::write_atomic(&path, &profile)?;
+    if !cmd::dry_run() {
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    }
+    ui::print_success(&format!("NetworkManager profile {} written.", id));
+    Ok(())
+}
+
+/// An `interface` block at the end of dhcpcd.conf, replacing the one an
+/// earlier run wrote. dhcpcd has no static IPv6 gateway; the default route
+/// comes from router advertisements.
+fn dhcpcd(s: &Static) -> Result<(), InstallerError> {
+    let (addr, prefix) = s.address;
+    let mut block = format!(
+        "\n{}\ninterface {}\nstatic ip_address={}/{}\nstatic routers={}\n",
+        DHCPCD_MARKER, s.interface, addr, prefix, s.gateway,
+    );
+    if let Some((addr, prefix)) = s.address6 {
+        block.push_str(&format!("static ip6_address={}/{}\n", addr, prefix));
+    }
+    let dns: Vec<String> = s.dns.iter().map(IpAddr::to_string).collect();
+    if !dns.is_empty() {
+        block.push_str(&format!("static domain_name_servers={}\n", dns.join(" ")));
+    }
+
+    let original = fs::read_to_string(DHCPCD_CONF).unwrap_or_default();
+    let updated = format!("{}{}", without_static_block(&original), block);
+    if original.is_empty() {
+        files::write_atomic(DHCPCD_CONF, &updated)?;
+    } else {
+        files::edit(DHCPCD_CONF, &updated)?;
+    }
+    if s.gateway6.is_some() {
+        ui::print_info("dhcpcd takes the IPv6 default route from router advertisements.");
+    }
+    ui::print_success(&format!("Static address for {} added to dhcpcd.conf.", s.interface));
+    Ok(())
+}
+
+/// `conf` without the block after [`DHCPCD_MARKER`] — its `interface` line
+/// and the `static` lines under it — nor the blank line before it.
+fn without_static_block(conf: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut block = None;
+    for line in conf.lines() {
+        if line == DHCPCD_MARKER {
+            if lines.last() == Some(&"") {
+                lines.pop();
+            }
+            block = Some(false);
+            continue;
+        }
+        match block {
+            Some(false) if line.starts_with("interface ") => block = Some(true),
+            Some(true) if line.starts_with("static ") => {}
+            _ => {
+                block = None;
+                lines.push(line);
+            }
+        }
+    }
+    lines.iter().map(|l| format!("{}\n", l)).collect()
+}
+
+/// Writes `/etc/conf.d/net` — DHCP or the static settings — and creates
+/// the `net.<interface>` service. Returns the service name.
+fn netifrc(interface: &str, s: Option<&Static>) -> Result<String, InstallerError> {
+    // netifrc variable names replace anything outside [A-Za-z0-9_] with '_'.
+    let var = interface.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+    let mut conf = String::from("# Written by artix-installer.\n");
+    match s {
+        None => conf.push_str(&format!("config_{}=\"dhcp\"\n", var)),
+        Some(s) => {
+            let (addr, prefix) = s.address;
+            let mut addresses = vec![format!("{}/{}", addr, prefix)];
+            let mut routes = vec![format!("default via {}", s.gateway)];
+            if let Some((addr, prefix)) = s.address6 {
+                addresses.push(format!("{}/{}", addr, prefix));
+            }
+            if let Some(gw) = s.gateway6 {
+                routes.push(format!("default via {}", gw));
+            }
+            conf.push_str(&format!("config_{}=\"{}\"\n", var, addresses.join("\n")));
+            conf.push_str(&format!("routes_{}=\"{}\"\n", var, routes.join("\n")));
+            let dns: Vec<String> = s.dns.iter().map(IpAddr::to_string).collect();
+            if !dns.is_empty() {
+                conf.push_str(&format!("dns_servers_{}=\"{}\"\n", var, dns.join(" ")));
+            }
+        }
+    }
+    if fs::read_to_string(NETIFRC_CONF).is_ok() {
+        files::edit(NETIFRC_CONF, &conf)?;
+    } else {
+        files::write_atomic(NETIFRC_CONF, &conf)?;
+    }
+    ui::print_success(&format!("{} configured in /etc/conf.d/net.", interface));
+
+    let service = format!("net.{}", interface);
+    let link = format!("/etc/init.d/{}", service);
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "ln", "-sf", "net.lo", &link],
+        &format!("Creating the {} service…", service),
+        &format!("{} service created.", service),
+    )?;
+    Ok(service)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_block_is_replaced_not_repeated() {
+        let conf = format!(
+            "hostname\n\n{}\ninterface eth0\nstatic ip_address=10.0.0.2/24\n\
+             static routers=10.0.0.1\ninterface wlan0\nnohook wpa_supplicant\n",
+            DHCPCD_MARKER
+        );
+        assert_eq!(
+            without_static_block(&conf),
+            "hostname\ninterface wlan0\nnohook wpa_supplicant\n"
+        );
+        assert_eq!(without_static_block("hostname\n"), "hostname\n");
+    }
+}
@@ -1,29 +1,62 @@
-use dialoguer::Confirm;
+use std::time::Duration;
 
-use crate::{cmd, error::InstallerError, ui};
+use crate::{cmd, config::TimeDaemon, error::InstallerError, prompt, ui};
 
-/// Optionally syncs the system clock via the NTP daemon.
+const DAEMONS: [TimeDaemon; 3] = [TimeDaemon::Ntpd, TimeDaemon::Chrony, TimeDaemon::Openntpd];
+
+/// Asks which NTP daemon to use and optionally syncs the system clock with it.
 /// An incorrect clock can cause package-signature validation to fail.
-pub fn run() -> Result<(), InstallerError> {
+/// The choice is installed in the target later (see [`install_in_target`]).
+pub fn run() -> Result<TimeDaemon, InstallerError> {
     ui::print_info("An accurate clock prevents package-signature validation errors.");
     println!();
 
-    if !Confirm::new()
-        .with_prompt("Sync system time via NTP? (recommended)")
-        .default(true)
-        .interact()?
-    {
+    let items: Vec<String> = DAEMONS.iter().map(|d| d.display_name().to_string()).collect();
+    let values: Vec<&str> = DAEMONS.iter().map(|d| d.as_str()).collect();
+    let daemon = DAEMONS[prompt::select("ntp_daemon", "Time daemon", &items, &values, 0)?];
+
+    if !prompt::confirm("ntp", "Sync system time via NTP? (recommended)", true)? {
         ui::print_warning("Skipping time synchronization — beware of signature issues.");
-        return Ok(());
+        return Ok(daemon);
     }
 
-    // -g: allow large time corrections  -q: one-shot, exit after sync
-    cmd::run_with_spinner(
-        "ntpd",
-        &["-gq"],
+    // ntpd -gq: allow large corrections, exit after one sync. chronyd -q does
+    // the same with the pool given inline. OpenNTPD has no one-shot mode, so
+    // the live system uses ntpd for it. Without network both wait forever,
+    // hence the timeout.
+    let (program, args): (&str, &[&str]) = match daemon {
+        TimeDaemon::Chrony => ("chronyd", &["-q", "pool pool.ntp.org iburst"]),
+        TimeDaemon::Ntpd | TimeDaemon::Openntpd => ("ntpd", &["-gq"]),
+    };
+    if !cmd::run_with_timeout(
+        program,
+        args,
         "Syncing system clock…",
         "System clock synchronized.",
-    )?;
+        Duration::from_secs(60),
+    )? {
+        ui::print_warning("Time sync skipped — beware of signature issues.");
+    }
 
-    Ok(())
+    Ok(daemon)
+}
+
+/// Installs the chosen daemon + its OpenRC service into `/mnt`, enables it
+/// and writes the system time to the hardware clock.
+pub fn install_in_target(daemon: TimeDaemon) -> Result<(), InstallerError> {
+    let [pkg, service_pkg] = daemon.packages();
+    println!();
+    cmd::run_transaction("basestrap", &["/mnt", pkg, service_pkg])?;
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", daemon.service(), "default"],
+        &format!("Enabling {}…", daemon.service()),
+        &format!("{} enabled at boot.", daemon.service()),
+    )?;
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "hwclock", "--systohc"],
+        "Setting the hardware clock…",
+        "Hardware clock set from the system time.",
+    )
 }
@@ -1,129 +1,746 @@
+use std::{fs, sync::OnceLock};
+
 use console::style;
-use dialoguer::{Confirm, Select};
+use crate::{
+    answers, cmd,
+    config::{KernelVariant, SeatManager},
+    error::InstallerError,
+    files, prompt, report,
+    steps::{graphics, portable, verify},
+    ui,
+};
+
+/// Sets `XDG_RUNTIME_DIR` at login when elogind isn't there to.
+const RUNTIME_DIR_SCRIPT: &str = "/mnt/etc/profile.d/xdg-runtime-dir.sh";
+const RUNTIME_DIR_PROFILE: &str = "# Installed by artix-installer: seatd creates no runtime dir.\n\
+if [ -z \"$XDG_RUNTIME_DIR\" ]; then\n\
+    XDG_RUNTIME_DIR=\"/tmp/$(id -u)-runtime-dir\"\n\
+    mkdir -pm 0700 \"$XDG_RUNTIME_DIR\"\n\
+    export XDG_RUNTIME_DIR\n\
+fi\n";
 
-use crate::{cmd, config::KernelVariant, error::InstallerError, ui};
+/// Packages from `--packages-file` and `packages.extra`, checked at startup.
+static EXTRA: OnceLock<Vec<String>> = OnceLock::new();
 
 // ── Base system ───────────────────────────────────────────────────────────────
 
-/// Installs the base Artix packages via `basestrap`.
-pub fn install_base() -> Result<(), InstallerError> {
-    ui::print_kv_box(
-        "Packages to install",
-        &[
-            ("base", "core system utilities"),
-            ("base-devel", "build tools (gcc, make, …)"),
-            ("openrc", "init system"),
-            ("elogind-openrc", "session management"),
-        ],
-    );
+/// Installs the base Artix packages via `basestrap`, after showing how much
+/// they download and take up, with the session manager picked by
+/// [`ask_seat_manager`]. Returns that choice for the desktop and user steps.
+pub fn install_base() -> Result<SeatManager, InstallerError> {
+    let seat = ask_seat_manager()?;
+    let seat_pkg = seat.packages()[1];
+    let rows: Vec<(&str, &str)> = [
+        ("base", "core system utilities"),
+        ("base-devel", "build tools (gcc, make, …)"),
+        ("openrc", "init system"),
+        (seat_pkg, "session management"),
+    ]
+    .into_iter()
+    .filter(|(pkg, _)| !is_excluded(pkg))
+    .collect();
+
+    let extra: Vec<&str> = EXTRA.get().into_iter().flatten().map(String::as_str).collect();
+    let extra = without_excluded(&extra);
+    let extra_row = format!("{} package(s) from your list", extra.len());
+    let mut rows = rows;
+    if !extra.is_empty() {
+        rows.push(("extra", &extra_row));
+    }
+
+    let pkgs = without_excluded(&["base", "base-devel", "openrc", seat_pkg]);
+    let mut args = vec!["/mnt"];
+    args.extend(&pkgs);
+    args.extend(extra.iter().filter(|p| !pkgs.contains(p)));
+
+    let size = ui::spinner("Estimating the download size…");
+    let estimate = size_estimate(&args[1..]);
+    size.finish_and_clear();
+    if let Some(ref estimate) = estimate {
+        rows.push(("size", estimate));
+    }
+    ui::print_kv_box("Packages to install", &rows);
     println!();
 
-    if !Confirm::new()
-        .with_prompt("Proceed with base installation?")
-        .default(true)
-        .interact()?
-    {
+    if !prompt::confirm("base", "Proceed with base installation?", true)? {
         return Err(InstallerError::Cancelled);
     }
 
     println!();
-    // basestrap shows download progress — keep it interactive.
-    cmd::run_interactive(
-        "basestrap",
-        &["/mnt", "base", "base-devel", "openrc", "elogind-openrc"],
-    )?;
+    cmd::run_transaction("basestrap", &args)?;
+    if seat == SeatManager::Seatd {
+        setup_seatd()?;
+    }
 
     ui::print_success("Base system installed.");
+    Ok(seat)
+}
+
+/// Asks whether elogind or seatd manages sessions. Non-interactive mode
+/// reads `seat` (`elogind` | `seatd`, default: elogind).
+pub fn ask_seat_manager() -> Result<SeatManager, InstallerError> {
+    let choices = [SeatManager::Elogind, SeatManager::Seatd];
+    let items: Vec<String> = choices.iter().map(|m| m.display_name().to_string()).collect();
+    let values: Vec<&str> = choices.iter().map(|m| m.as_str()).collect();
+    println!();
+    let idx = prompt::select("seat", "Session manager", &items, &values, 0)?;
+    Ok(choices[idx])
+}
+
+/// seatd runs as a service, and without elogind nothing creates
+/// `XDG_RUNTIME_DIR` at login — a profile script does.
+fn setup_seatd() -> Result<(), InstallerError> {
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", "seatd", "default"],
+        "Enabling seatd…",
+        "seatd enabled at boot.",
+    )?;
+    files::write_atomic(RUNTIME_DIR_SCRIPT, RUNTIME_DIR_PROFILE)?;
+    report::note("session", "seatd, XDG_RUNTIME_DIR from /etc/profile.d");
     Ok(())
 }
 
 // ── Kernel ────────────────────────────────────────────────────────────────────
 
-/// Asks the user which kernel variant they want, then installs it.
-pub fn ask_kernel() -> Result<KernelVariant, InstallerError> {
+/// Asks which kernel variants to install — one or more, e.g. `linux` plus
+/// `linux-lts` as a fallback. The first one picked is the default boot entry.
+pub fn ask_kernels() -> Result<Vec<KernelVariant>, InstallerError> {
     println!();
 
+    let variants = KernelVariant::available();
+    // (description in the box, short note in the menu)
+    let about = |k: KernelVariant| match k {
+        KernelVariant::Stable => {
+            ("latest mainline kernel — best hardware support", "stable (recommended)")
+        }
+        KernelVariant::Lts => ("long-term support — stability over features", "long-term support"),
+        KernelVariant::Zen => {
+            ("performance-tuned, lower latency — gaming/desktop", "performance-optimized")
+        }
+    };
+
     // Brief description of each variant shown before the prompt.
-    ui::print_kv_box(
-        "Kernel variants",
-        &[
-            ("stable", "latest mainline kernel — best hardware support"),
-            ("lts", "long-term support — stability over features"),
-            ("zen", "performance-tuned, lower latency — gaming/desktop"),
-        ],
-    );
+    let rows: Vec<(&str, &str)> = variants.iter().map(|&k| (k.as_str(), about(k).0)).collect();
+    ui::print_kv_box("Kernel variants", &rows);
     println!();
 
-    let options = [
-        format!("{}  {}", style("linux    ").cyan().bold(), style("stable (recommended)").dim()),
-        format!("{}  {}", style("linux-lts").cyan().bold(), style("long-term support").dim()),
-        format!("{}  {}", style("linux-zen").cyan().bold(), style("performance-optimized").dim()),
-    ];
+    let width = variants.iter().map(|k| k.package_name().len()).max().unwrap_or(0);
+    let options: Vec<String> = variants
+        .iter()
+        .map(|&k| {
+            let name = format!("{:<width$}", k.package_name());
+            format!("{}  {}", style(name).cyan().bold(), style(about(k).1).dim())
+        })
+        .collect();
+    let values: Vec<&str> = variants.iter().map(|k| k.as_str()).collect();
+    let mut defaults = vec![false; variants.len()];
+    defaults[0] = true;
 
-    let selection = Select::new()
-        .with_prompt("Which kernel do you want to install?")
-        .default(0)
-        .items(&options)
-        .interact()?;
-
-    let kernel = match selection {
-        0 => KernelVariant::Stable,
-        1 => KernelVariant::Lts,
-        2 => KernelVariant::Zen,
-        _ => unreachable!(),
-    };
+    let selection = prompt::multi_select(
+        "kernel",
+        "Which kernels do you want to install?",
+        &options,
+        &values,
+        &defaults,
+    )?;
 
-    ui::print_info(&format!("Selected: {}", kernel.display_name()));
-    Ok(kernel)
+    let kernels: Vec<KernelVariant> = selection.into_iter().map(|i| variants[i]).collect();
+
+    let names: Vec<&str> = kernels.iter().map(|k| k.display_name()).collect();
+    ui::print_info(&format!("Selected: {}", names.join(", ")));
+    Ok(kernels)
 }
 
 // ── Desktop environment ───────────────────────────────────────────────────────
 
-/// Installs kitty, Hyprland, and Neovim inside the new system via artix-chroot.
-pub fn install_desktop() -> Result<(), InstallerError> {
-    println!();
-    ui::print_kv_box(
-        "Desktop packages",
+/// A desktop session the installer can set up: the window manager or
+/// compositor plus the companions that make it usable on first boot.
+pub struct DesktopSession {
+    pub id: &'static str,
+    pub name: &'static str,
+    about: &'static str,
+    pub packages: &'static [&'static str],
+    /// Started from `~/.xinitrc` with `startx`; the others are Wayland
+    /// compositors started straight from the tty.
+    pub xorg: bool,
+    /// What to type after logging in on a tty.
+    command: &'static str,
+}
+
+pub const SESSIONS: &[DesktopSession] = &[
+    DesktopSession {
+        id: "hyprland",
+        name: "Hyprland",
+        about: "tiling Wayland compositor with animations",
+        packages: &[
+            "hyprland", "kitty", "waybar", "wofi", "xdg-desktop-portal-hyprland",
+            "xdg-desktop-portal-gtk",
+        ],
+        xorg: false,
+        command: "Hyprland",
+    },
+    DesktopSession {
+        id: "sway",
+        name: "sway",
+        about: "i3-compatible Wayland compositor",
+        packages: &[
+            "sway", "swaybg", "swayidle", "swaylock", "foot", "waybar", "wmenu",
+            "xdg-desktop-portal-wlr", "xdg-desktop-portal-gtk",
+        ],
+        xorg: false,
+        command: "sway",
+    },
+    DesktopSession {
+        id: "river",
+        name: "river",
+        about: "dynamic tiling Wayland compositor",
+        packages: &[
+            "river", "foot", "waybar", "wofi", "xdg-desktop-portal-wlr", "xdg-desktop-portal-gtk",
+        ],
+        xorg: false,
+        command: "river",
+    },
+    DesktopSession {
+        id: "i3",
+        name: "i3",
+        about: "tiling window manager for Xorg",
+        packages: &["i3-wm", "i3status", "i3lock", "dmenu", "alacritty", "xdg-desktop-portal-gtk"],
+        xorg: true,
+        command: "startx",
+    },
+    DesktopSession {
+        id: "bspwm",
+        name: "bspwm",
+        about: "binary space partitioning window manager for Xorg",
+        packages: &["bspwm", "sxhkd", "polybar", "dmenu", "alacritty", "xdg-desktop-portal-gtk"],
+        xorg: true,
+        command: "startx",
+    },
+];
+
+/// The display server underneath each kind of session: Xorg started by
+/// `startx`, or the Wayland libraries, XWayland for X11 programs, the portal
+/// service and Qt's Wayland backend.
+const XORG_STACK: &[&str] = &["xorg-server", "xorg-xinit", "xorg-xrandr", "xf86-input-libinput"];
+const WAYLAND_STACK: &[&str] = &["wayland", "xorg-xwayland", "xdg-desktop-portal", "qt6-wayland"];
+
+/// Installed with every session: an editor, and a font the terminals can
+/// render with.
+const DESKTOP_COMMON: &[&str] = &["neovim", "ttf-dejavu"];
+
+/// The optional "multimedia essentials": what a fresh desktop needs to
+/// render any web page and play media without hunting for packages.
+const ESSENTIALS: &[(&str, &[&str])] = &[
+    ("fonts", &["noto-fonts", "noto-fonts-cjk", "noto-fonts-emoji"]),
+    (
+        "codecs",
         &[
-            ("kitty",    "GPU-accelerated terminal emulator"),
-            ("hyprland", "Wayland compositor / window manager"),
-            ("neovim",   "extensible text editor"),
+            "gst-plugins-base", "gst-plugins-good", "gst-plugins-bad", "gst-plugins-ugly",
+            "gst-libav", "ffmpeg",
         ],
-    );
+    ),
+    ("archives", &["p7zip", "unzip", "zip", "unrar"]),
+];
+
+impl DesktopSession {
+    /// `"Wayland"` or `"Xorg"`.
+    pub fn display(&self) -> &'static str {
+        if self.xorg { "Xorg" } else { "Wayland" }
+    }
+
+    fn stack(&self) -> &'static [&'static str] {
+        if self.xorg { XORG_STACK } else { WAYLAND_STACK }
+    }
+}
+
+/// Every package a desktop session may bring in.
+pub fn desktop_packages() -> impl Iterator<Item = &'static str> {
+    SESSIONS
+        .iter()
+        .flat_map(|s| s.packages.iter().copied())
+        .chain(XORG_STACK.iter().chain(WAYLAND_STACK).chain(DESKTOP_COMMON).copied())
+        .chain(ESSENTIALS.iter().flat_map(|(_, pkgs)| pkgs.iter().copied()))
+}
+
+/// Asks for the session type, then for a session of that type. With an
+/// NVIDIA GPU, Wayland comes with a warning: it needs the proprietary
+/// driver with kernel mode setting, and nouveau is slow there.
+///
+/// Non-interactive mode reads `display` (`wayland` | `xorg`, default: the
+/// type of `session`, else Wayland) and `session` (default: the first of
+/// that type — `hyprland` or `i3`).
+pub fn ask_session() -> Result<&'static DesktopSession, InstallerError> {
+    let nvidia = graphics::has_nvidia();
+    let answered = answers::get().and_then(|a| a.str("session"));
+    let xorg_default = SESSIONS.iter().find(|s| Some(s.id) == answered).is_some_and(|s| s.xorg);
+    let items = vec![
+        format!("{:<10}{}", "Wayland", style("sway, Hyprland, river — the modern stack").dim()),
+        format!("{:<10}{}", "Xorg", style("i3, bspwm — X11, works with every driver").dim()),
+    ];
     println!();
+    // Warned once: the review screen asks before the desktop step does.
+    let asked = prompt::given().iter().any(|(key, _)| key == "display");
+    if nvidia && !asked {
+        ui::print_warning(
+            "NVIDIA GPU detected — Wayland needs the proprietary driver (offered \
+             later, with nvidia_drm.modeset=1); on nouveau it is slow. Xorg is the safe choice.",
+        );
+    }
+    let xorg = prompt::select(
+        "display",
+        "Session type",
+        &items,
+        &["wayland", "xorg"],
+        usize::from(xorg_default),
+    )? == 1;
 
-    if !Confirm::new()
-        .with_prompt("Install desktop packages inside the new system?")
-        .default(true)
-        .interact()?
-    {
-        return Err(InstallerError::Cancelled);
+    let sessions: Vec<&DesktopSession> = SESSIONS.iter().filter(|s| s.xorg == xorg).collect();
+    let items: Vec<String> = sessions
+        .iter()
+        .map(|s| format!("{:<10}{}", s.name, style(s.about).dim()))
+        .collect();
+    let values: Vec<&str> = sessions.iter().map(|s| s.id).collect();
+    let idx = prompt::select("session", "Which desktop session?", &items, &values, 0)?;
+    let session = sessions[idx];
+    if nvidia && !xorg && !asked {
+        report::note("desktop", "Wayland session on an NVIDIA GPU");
     }
+    Ok(session)
+}
 
+/// Installs the chosen session inside the new system via artix-chroot,
+/// with the multimedia essentials if wanted, and gives it a working start:
+/// `~/.xinitrc` for the Xorg window managers, the example configs river and
+/// bspwm don't ship a default for — in `/etc/skel`, so the user created
+/// later gets them.
+pub fn install_desktop(seat: SeatManager) -> Result<(), InstallerError> {
     println!();
-    // artix-chroot runs pacman inside /mnt — streams output interactively.
-    cmd::run_interactive(
-        "artix-chroot",
-        &["/mnt", "pacman", "-Sy", "--noconfirm", "kitty", "hyprland", "neovim"],
-    )?;
+    if !prompt::confirm("desktop", "Install a desktop session inside the new system?", true)? {
+        ui::print_info("Skipping desktop packages.");
+        return Ok(());
+    }
+    let session = ask_session()?;
 
-    ui::print_success("Desktop packages installed.");
+    let mut wanted: Vec<&str> = session.stack().to_vec();
+    wanted.extend(session.packages);
+    wanted.extend(DESKTOP_COMMON);
+    let essentials = ask_essentials()?;
+    wanted.extend(&essentials);
+    let pkgs = without_excluded(&wanted);
+    if pkgs.is_empty() {
+        ui::print_info("Every desktop package is excluded — nothing to install.");
+        return Ok(());
+    }
+
+    let listed = pkgs.join(" ");
+    let start = format!("log in on a tty and run {}", session.command);
+    let mut rows = vec![
+        ("session", session.about),
+        ("display", session.display()),
+        ("packages", &listed),
+        ("start", &start),
+    ];
+    let estimate = size_estimate(&pkgs);
+    if let Some(ref estimate) = estimate {
+        rows.push(("size", estimate));
+    }
+    println!();
+    ui::print_kv_box(session.name, &rows);
+    println!();
+
+    let mut args = vec!["/mnt", "pacman", "-Sy", "--noconfirm"];
+    args.extend(&pkgs);
+
+    // artix-chroot runs pacman inside /mnt.
+    cmd::run_transaction("artix-chroot", &args)?;
+    skel(session)?;
+    if seat == SeatManager::Seatd && session.xorg {
+        // Without logind, Xorg can only reach the GPU and input as root.
+        files::write_atomic("/mnt/etc/X11/Xwrapper.config", "needs_root_rights = yes\n")?;
+    }
+
+    ui::print_success(&format!("{} installed — {}.", session.name, start));
+    report::note("desktop", &format!("{}: {}", session.name, start));
     Ok(())
 }
 
-/// Installs the chosen kernel + `linux-firmware` via `basestrap`.
-pub fn install_kernel(kernel: KernelVariant) -> Result<(), InstallerError> {
-    let pkg = kernel.package_name();
+/// Offers the multimedia essentials group — fonts with CJK and emoji,
+/// GStreamer plugins and ffmpeg, archive tools. Non-interactive mode reads
+/// `multimedia` (default: yes).
+fn ask_essentials() -> Result<Vec<&'static str>, InstallerError> {
+    let rows: Vec<(&str, String)> =
+        ESSENTIALS.iter().map(|(group, pkgs)| (*group, pkgs.join(" "))).collect();
+    let rows: Vec<(&str, &str)> = rows.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    println!();
+    ui::print_kv_box("Multimedia essentials", &rows);
+    println!();
+    let ask = "Add them, so web pages render and media plays right away?";
+    if !prompt::confirm("multimedia", ask, true)? {
+        return Ok(vec![]);
+    }
+    Ok(ESSENTIALS.iter().flat_map(|(_, pkgs)| pkgs.iter().copied()).collect())
+}
+
+/// Puts the files `session` needs to start into the new system's skeleton.
+fn skel(session: &DesktopSession) -> Result<(), InstallerError> {
+    if session.xorg {
+        files::write_atomic("/mnt/etc/skel/.xinitrc", &format!("exec {}\n", session.id))?;
+    }
+    let examples: &[(&str, &str)] = match session.id {
+        "river" => &[("river/examples/init", ".config/river/init")],
+        "bspwm" => &[
+            ("bspwm/examples/bspwmrc", ".config/bspwm/bspwmrc"),
+            ("bspwm/examples/sxhkdrc", ".config/sxhkd/sxhkdrc"),
+        ],
+        _ => &[],
+    };
+    for (example, dest) in examples {
+        let src = format!("/mnt/usr/share/doc/{}", example);
+        let dest = format!("/mnt/etc/skel/{}", dest);
+        cmd::run_with_spinner(
+            "install",
+            &["-Dm755", &src, &dest],
+            &format!("Copying the example {}…", example),
+            &format!("{} in place.", &dest["/mnt".len()..]),
+        )?;
+    }
+    if session.id == "bspwm" {
+        // The example binds Super+Return to urxvt, which isn't installed.
+        let sxhkdrc = "/mnt/etc/skel/.config/sxhkd/sxhkdrc";
+        cmd::run_with_spinner(
+            "sed",
+            &["-i", "s/urxvt/alacritty/", sxhkdrc],
+            "Pointing sxhkd at alacritty…",
+            "sxhkd opens alacritty.",
+        )?;
+    }
+    Ok(())
+}
+
+/// Installs the chosen kernels + firmware via `basestrap`, and their
+/// headers if wanted — without them DKMS modules (NVIDIA, VirtualBox, ZFS)
+/// fail to build, which only shows after the first reboot.
+///
+/// `linux-firmware` is skipped by default in a VM, where it only costs
+/// ~700 MB. Firmware outside it (`sof-firmware`, `linux-firmware-marvell`)
+/// is offered when the hardware needs it.
+pub fn install_kernels(kernels: &[KernelVariant]) -> Result<(), InstallerError> {
+    let names: Vec<&str> = kernels.iter().map(|k| k.package_name()).collect();
+    let headers: Vec<&str> = kernels.iter().map(|k| k.headers_package()).collect();
+
+    println!();
+    let mut pkgs = names.clone();
+    pkgs.extend(ask_firmware()?);
+    if prompt::confirm(
+        "kernel_headers",
+        &format!("Also install {}? (needed for DKMS modules)", headers.join(", ")),
+        true,
+    )? {
+        pkgs.extend(&headers);
+    }
 
     ui::print_info(&format!(
-        "Installing {} + linux-firmware…",
-        style(pkg).cyan().bold()
+        "Installing {}{}…",
+        style(names.join(" + ")).cyan().bold(),
+        pkgs[names.len()..].iter().map(|p| format!(" + {}", p)).collect::<String>()
     ));
+    let mut args = vec!["/mnt"];
+    args.extend(without_excluded(&pkgs));
+    if let Some(estimate) = size_estimate(&args[1..]) {
+        ui::print_info(&format!("Size: {}.", estimate));
+    }
+    println!();
+
+    cmd::run_transaction("basestrap", &args)?;
+
+    ui::print_success(&format!("Installed {}.", names.join(", ")));
+    Ok(())
+}
+
+/// A bcachefs root needs the tools (mount helper, fsck, initramfs hook) and,
+/// since bcachefs left the mainline kernel, its DKMS module built against
+/// each installed kernel.
+pub fn install_bcachefs(kernels: &[KernelVariant]) -> Result<(), InstallerError> {
+    let mut pkgs = vec!["bcachefs-tools", "bcachefs-dkms"];
+    pkgs.extend(kernels.iter().map(|k| k.headers_package()));
+
+    ui::print_info("Installing bcachefs support for the root filesystem…");
+    println!();
+    let mut args = vec!["/mnt"];
+    args.extend(without_excluded(&pkgs));
+    cmd::run_transaction("basestrap", &args)?;
+
+    ui::print_success("bcachefs tools and kernel module installed.");
+    Ok(())
+}
+
+/// The firmware packages to install alongside the kernel.
+fn ask_firmware() -> Result<Vec<&'static str>, InstallerError> {
+    let mut pkgs = Vec::new();
+    match verify::hypervisor() {
+        Some(_) if portable::enabled() => pkgs.push("linux-firmware"),
+        Some(vm) => {
+            ui::print_info(&format!("Running in {} — linux-firmware (~700 MB) isn't needed.", vm));
+            if prompt::confirm("linux_firmware", "Install linux-firmware anyway?", false)? {
+                pkgs.push("linux-firmware");
+            }
+        }
+        None => pkgs.push("linux-firmware"),
+    }
+    for (pkg, reason) in verify::extra_firmware() {
+        let key = format!("firmware.{}", pkg);
+        if prompt::confirm(&key, &format!("{} detected — install {}?", reason, pkg), true)? {
+            pkgs.push(pkg);
+        }
+    }
+    Ok(pkgs)
+}
+
+// ── Size estimate ─────────────────────────────────────────────────────────────
+
+/// The target's package database, once basestrap has created it.
+const TARGET_LOCAL_DB: &str = "/mnt/var/lib/pacman/local";
+
+/// `"≈ 512 MiB download, 1.6 GiB installed"` for `pkgs` and the
+/// dependencies pacman would pull in, from the live system's sync
+/// databases. `None` when pacman can't tell (offline, stale databases).
+///
+/// pacman resolves against a scratch `--dbpath`: the live sync databases,
+/// and as local database the target's — empty before base is installed —
+/// so packages the live ISO happens to have are still counted.
+fn size_estimate(pkgs: &[&str]) -> Option<String> {
+    let dbpath = format!("/tmp/artix-installer-sizes.{}", std::process::id());
+    let estimate = estimate_with(&dbpath, pkgs);
+    let _ = fs::remove_dir_all(&dbpath);
+    estimate
+}
+
+fn estimate_with(dbpath: &str, pkgs: &[&str]) -> Option<String> {
+    fs::create_dir_all(dbpath).ok()?;
+    std::os::unix::fs::symlink("/var/lib/pacman/sync", format!("{}/sync", dbpath)).ok()?;
+    let local = format!("{}/local", dbpath);
+    if std::path::Path::new(TARGET_LOCAL_DB).is_dir() {
+        std::os::unix::fs::symlink(TARGET_LOCAL_DB, &local).ok()?;
+    } else {
+        fs::create_dir(&local).ok()?;
+    }
+
+    let mut args = vec!["--dbpath", dbpath, "-Sp", "--noconfirm", "--print-format", "%n %s"];
+    args.extend(pkgs);
+    let listing = cmd::run_capture("pacman", &args).ok()?;
+    let mut names = Vec::new();
+    let mut download = 0u64;
+    for line in listing.lines() {
+        let (name, bytes) = line.split_once(' ')?;
+        download += bytes.trim().parse::<u64>().ok()?;
+        names.push(name);
+    }
+    if names.is_empty() {
+        return None;
+    }
+
+    let mut args = vec!["--dbpath", dbpath, "-Si"];
+    args.extend(&names);
+    let info = cmd::run_capture("pacman", &args).unwrap_or_default();
+    let installed: f64 = info
+        .lines()
+        .filter_map(|l| l.strip_prefix("Installed Size"))
+        .filter_map(|l| {
+            let (value, unit) = l.trim_start_matches([' ', ':']).split_once(' ')?;
+            let scale = match unit.trim() {
+                "KiB" => 1024.0,
+                "MiB" => 1024.0 * 1024.0,
+                "GiB" => 1024.0 * 1024.0 * 1024.0,
+                _ => 1.0,
+            };
+            value.parse::<f64>().ok().map(|v| v * scale)
+        })
+        .sum();
+    Some(format!(
+        "≈ {} download, {} installed ({} packages)",
+        human(download as f64),
+        human(installed),
+        names.len()
+    ))
+}
+
+fn human(bytes: f64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    if bytes >= 1024.0 * MIB {
+        format!("{:.1} GiB", bytes / (1024.0 * MIB))
+    } else {
+        format!("{:.0} MiB", (bytes / MIB).max(1.0))
+    }
+}
+
+// ── Shared cache ──────────────────────────────────────────────────────────────
+
+/// `--cachedir`: basestrap reads and downloads packages in `dir` — on a USB
+/// drive or NFS share, kept from one install to the next — instead of the
+/// new system's own cache. Created when missing.
+pub fn use_cache(dir: &str) -> Result<(), InstallerError> {
+    if !cmd::dry_run() {
+        fs::create_dir_all(dir)
+            .map_err(|e| InstallerError::Config(format!("can't use {} as cache: {}", dir, e)))?;
+    }
+    let cached = packages_in(dir);
+    let bytes: u64 = cached.iter().map(|(_, size)| size).sum();
+    if cached.is_empty() {
+        ui::print_info(&format!("Package cache: {} (empty — filled as packages download).", dir));
+    } else {
+        let size = human(bytes as f64);
+        ui::print_info(&format!("Package cache: {} ({} package(s), {}).", dir, cached.len(), size));
+    }
+    cmd::set_cache_dir(dir);
+    Ok(())
+}
+
+/// Offers to copy the packages the new system downloaded itself — the
+/// desktop, post-install extras — to the shared cache, so the next install
+/// finds them too. Does nothing without `--cachedir`.
+///
+/// Non-interactive mode reads `cachedir.update` (default: yes).
+pub fn update_cache() -> Result<(), InstallerError> {
+    let Some(dir) = cmd::cache_dir() else {
+        return Ok(());
+    };
+    let cached = packages_in(dir);
+    let new: Vec<(String, u64)> = packages_in(cmd::PACMAN_CACHE)
+        .into_iter()
+        .filter(|(name, _)| !cached.iter().any(|(c, _)| c == name))
+        .collect();
+    if new.is_empty() {
+        ui::print_info(&format!("The package cache on {} is up to date.", dir));
+        return Ok(());
+    }
+
+    let bytes: u64 = new.iter().map(|(_, size)| size).sum();
     println!();
+    let ask = format!(
+        "Copy {} new package(s) ({}) to {} for the next install?",
+        new.len(),
+        human(bytes as f64),
+        dir
+    );
+    if !prompt::confirm("cachedir.update", &ask, true)? {
+        return Ok(());
+    }
+    let paths: Vec<String> =
+        new.iter().map(|(name, _)| format!("{}/{}", cmd::PACMAN_CACHE, name)).collect();
+    let mut args = vec!["-n"];
+    args.extend(paths.iter().map(String::as_str));
+    args.push(dir);
+    cmd::run_with_spinner(
+        "cp",
+        &args,
+        "Updating the package cache…",
+        &format!("{} package(s) added to {}.", new.len(), dir),
+    )
+}
 
-    // basestrap streams download output — keep it interactive.
-    cmd::run_interactive("basestrap", &["/mnt", pkg, "linux-firmware"])?;
+/// Package files (and their signatures) in a cache directory, with sizes.
+fn packages_in(dir: &str) -> Vec<(String, u64)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            let size = e.metadata().ok().filter(|m| m.is_file())?.len();
+            name.contains(".pkg.tar").then_some((name, size))
+        })
+        .collect()
+}
 
-    ui::print_success(&format!("Kernel '{}' installed.", pkg));
+// ── Extra packages ────────────────────────────────────────────────────────────
+
+/// Reads the extra package list — `path` (one or more names per line, `#`
+/// starts a comment) plus the answer file's `packages.extra` — and checks
+/// every name against the live system's repositories, so a typo stops the
+/// run before anything is touched. Groups such as `xorg` count as known.
+pub fn load_extra(path: Option<&str>) -> Result<(), InstallerError> {
+    let mut listed: Vec<String> = Vec::new();
+    if let Some(path) = path {
+        let content = fs::read_to_string(path)
+            .map_err(|e| InstallerError::Config(format!("can't read {}: {}", path, e)))?;
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            listed.extend(line.split_whitespace().map(str::to_string));
+        }
+    }
+    if let Some(list) = answers::get().and_then(|a| a.list("packages.extra")) {
+        listed.extend(list.iter().cloned());
+    }
+    let mut names: Vec<String> = Vec::new();
+    for name in listed {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    if names.is_empty() {
+        return Ok(());
+    }
+
+    let invalid: Vec<&str> =
+        names.iter().map(String::as_str).filter(|n| !valid_name(n)).collect();
+    if !invalid.is_empty() {
+        return Err(InstallerError::Config(format!(
+            "not valid package names: {}",
+            invalid.join(", ")
+        )));
+    }
+
+    // Without pacman databases (a dry run on another distro) the check is
+    // skipped; basestrap reports unknown names itself.
+    let repo = cmd::run_capture("pacman", &["-Slq"]).unwrap_or_default();
+    let groups = cmd::run_capture("pacman", &["-Sgq"]).unwrap_or_default();
+    if !repo.trim().is_empty() {
+        let unknown: Vec<&str> = names
+            .iter()
+            .map(String::as_str)
+            .filter(|n| !repo.lines().chain(groups.lines()).any(|l| l == *n))
+            .collect();
+        if !unknown.is_empty() {
+            return Err(InstallerError::Config(format!(
+                "not in the enabled repositories: {}",
+                unknown.join(", ")
+            )));
+        }
+    }
+
+    ui::print_success(&format!("{} extra package(s) will be installed with base.", names.len()));
+    let _ = EXTRA.set(names);
     Ok(())
 }
+
+/// pacman's rules: lowercase letters, digits and `@._+-`, not starting
+/// with `-` or `.`.
+fn valid_name(name: &str) -> bool {
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || "@._+-".contains(c);
+    !name.starts_with(['-', '.']) && name.chars().all(allowed)
+}
+
+// ── Exclusions ────────────────────────────────────────────────────────────────
+
+/// `true` if the answer file lists `pkg` under `packages.no_install`.
+fn is_excluded(pkg: &str) -> bool {
+    answers::get()
+        .and_then(|a| a.list("packages.no_install"))
+        .is_some_and(|list| list.iter().any(|p| p == pkg))
+}
+
+/// Removes excluded packages from a package set, mentioning each one.
+fn without_excluded<'a>(pkgs: &[&'a str]) -> Vec<&'a str> {
+    pkgs.iter()
+        .copied()
+        .filter(|pkg| {
+            let skip = is_excluded(pkg);
+            if skip {
+                ui::print_info(&format!("Skipping {} (packages.no_install).", pkg));
+            }
+            !skip
+        })
+        .collect()
+}
@@ -0,0 +1,152 @@
+use std::{collections::HashSet, path::Path};
+
+use crate::{answers, cmd, error::InstallerError, files, prompt, report, ui};
+
+const PACMAN_CONF: &str = "/mnt/etc/pacman.conf";
+const LIVE_PACMAN_CONF: &str = "/etc/pacman.conf";
+
+/// Optionally holds packages back from upgrades via `IgnorePkg` /
+/// `IgnoreGroup` in the target's pacman.conf (e.g. pin the kernel).
+///
+/// Names that weren't actually installed are dropped with a warning — an
+/// ignore rule for a typo would silently do nothing.
+///
+/// Non-interactive mode reads `pacman.ignore_pkg` / `pacman.ignore_group`
+/// arrays from the answer file.
+pub fn ignore() -> Result<(), InstallerError> {
+    let (pkgs, groups) = match answers::get() {
+        Some(a) => {
+            let list = |key| a.list(key).map(<[String]>::to_vec).unwrap_or_default();
+            (list("pacman.ignore_pkg"), list("pacman.ignore_group"))
+        }
+        None => {
+            println!();
+            let hold = "Hold any packages back from upgrades (IgnorePkg)?";
+            if !prompt::confirm("ignore", hold, false)? {
+                return Ok(());
+            }
+            let words = |s: String| s.split_whitespace().map(str::to_string).collect();
+            (
+                words(prompt::input("ignore_pkg", "Packages to ignore (e.g. linux)", "")?),
+                words(prompt::input("ignore_group", "Groups to ignore", "")?),
+            )
+        }
+    };
+    if pkgs.is_empty() && groups.is_empty() {
+        return Ok(());
+    }
+
+    let installed: HashSet<String> = cmd::run_capture("pacman", &["--root", "/mnt", "-Qq"])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let installed_groups: HashSet<String> = cmd::run_capture("pacman", &["--root", "/mnt", "-Qg"])?
+        .lines()
+        .filter_map(|l| l.split_whitespace().next())
+        .map(str::to_string)
+        .collect();
+
+    let pkgs = keep_installed(pkgs, &installed, "package");
+    let groups = keep_installed(groups, &installed_groups, "group");
+    if pkgs.is_empty() && groups.is_empty() {
+        ui::print_warning("Nothing left to ignore — pacman.conf unchanged.");
+        return Ok(());
+    }
+
+    let Ok(original) = std::fs::read_to_string(PACMAN_CONF) else {
+        ui::print_warning(&format!("{} not found — is pacman installed?", PACMAN_CONF));
+        return Ok(());
+    };
+
+    let mut conf = original;
+    if !pkgs.is_empty() {
+        conf = set_option(&conf, "IgnorePkg", &pkgs.join(" "));
+    }
+    if !groups.is_empty() {
+        conf = set_option(&conf, "IgnoreGroup", &groups.join(" "));
+    }
+
+    if files::edit(PACMAN_CONF, &conf)? {
+        ui::print_success("pacman will hold these back on upgrades.");
+    }
+    Ok(())
+}
+
+/// `--download-limit`: points the live system's pacman at curl (or wget)
+/// with a rate cap through `XferCommand`, so basestrap and the package
+/// checks download at most `rate` bytes/s. An external downloader also
+/// turns off `ParallelDownloads`, so the cap holds for the whole transfer.
+/// The target's own pacman.conf is left alone.
+pub fn limit_downloads(rate: &str) -> Result<(), InstallerError> {
+    let command = if Path::new("/usr/bin/curl").exists() {
+        format!("/usr/bin/curl --limit-rate {} -L -C - -f -s -S -o %o %u", rate)
+    } else if Path::new("/usr/bin/wget").exists() {
+        format!("/usr/bin/wget --limit-rate={} -c -q -O %o %u", rate)
+    } else {
+        return Err(InstallerError::CommandNotFound("curl".to_string()));
+    };
+    let Ok(conf) = std::fs::read_to_string(LIVE_PACMAN_CONF) else {
+        ui::print_warning(&format!("{} not found — downloads are not capped.", LIVE_PACMAN_CONF));
+        return Ok(());
+    };
+    files::write_atomic(LIVE_PACMAN_CONF, &set_option(&conf, "XferCommand", &command))?;
+    ui::print_info(&format!("pacman downloads capped at {}/s.", rate));
+    report::note("downloads", &format!("capped at {}/s", rate));
+    Ok(())
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Filters `names` down to those in `installed`, warning about the rest.
+fn keep_installed(names: Vec<String>, installed: &HashSet<String>, kind: &str) -> Vec<String> {
+    names
+        .into_iter()
+        .filter(|n| {
+            let ok = installed.contains(n);
+            if !ok {
+                ui::print_warning(&format!("{} is not an installed {} — skipped.", n, kind));
+            }
+            ok
+        })
+        .collect()
+}
+
+/// Sets `key = value` in the `[options]` section, replacing an existing or
+/// commented-out line, or adding one right after the section header.
+fn set_option(conf: &str, key: &str, value: &str) -> String {
+    let setting = format!("{:<12}= {}", key, value);
+    let is_key = |line: &str| {
+        let l = line.trim_start().trim_start_matches('#').trim_start();
+        l.strip_prefix(key).is_some_and(|r| r.trim_start().starts_with('='))
+    };
+
+    let mut out: Vec<String> = Vec::new();
+    let mut done = false;
+    let mut in_options = false;
+    for line in conf.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_options = trimmed == "[options]";
+        }
+        if in_options && !done && is_key(line) {
+            out.push(setting.clone());
+            done = true;
+        } else {
+            out.push(line.to_string());
+        }
+    }
+
+    if !done {
+        match out.iter().position(|l| l.trim() == "[options]") {
+            Some(i) => out.insert(i + 1, setting),
+            None => {
+                out.insert(0, setting);
+                out.insert(0, "[options]".to_string());
+            }
+        }
+    }
+
+    let mut s = out.join("\n");
+    s.push('\n');
+    s
+}
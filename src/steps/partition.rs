@@ -1,12 +1,12 @@
 use console::style;
-use dialoguer::{Confirm, Input, Select};
-
-use crate::{cmd, error::InstallerError, lsblk, ui};
+use crate::{answers, cmd, error::InstallerError, health, lsblk, prompt, steps::portable, ui};
 
 /// Shows available disks with arrow-key selection, then launches `cfdisk`.
 /// Returns the chosen disk path (e.g. `/dev/sda`).
 pub fn run() -> Result<String, InstallerError> {
     let disk = select_disk()?;
+    health::smart(&disk.path)?;
+    portable::ask(&disk)?;
 
     let p1 = part_path(&disk.path, 1);
     let p2 = part_path(&disk.path, 2);
@@ -45,11 +45,15 @@ pub fn run() -> Result<String, InstallerError> {
     ));
     println!();
 
-    if !Confirm::new()
-        .with_prompt(&format!("Launch cfdisk on {}?", disk.path))
-        .default(true)
-        .interact()?
-    {
+    if answers::unattended() {
+        ui::print_info(&format!(
+            "Non-interactive mode — keeping the existing partition table on {}.",
+            disk.path
+        ));
+        return Ok(disk.path);
+    }
+
+    if !prompt::confirm("cfdisk", &format!("Launch cfdisk on {}?", disk.path), true)? {
         return Err(InstallerError::Cancelled);
     }
 
@@ -75,7 +79,7 @@ pub fn part_path(disk: &str, n: u8) -> String {
 
 /// Returns `true` when the system booted in UEFI mode.
 fn is_uefi() -> bool {
-    std::path::Path::new("/sys/firmware/efi").exists()
+    cmd::dry_run() || std::path::Path::new("/sys/firmware/efi").exists()
 }
 
 /// Computes the leftover size after reserving 1 GiB (EFI/boot) + 10 GiB (swap).
@@ -120,34 +124,47 @@ fn select_disk() -> Result<lsblk::Disk, InstallerError> {
     if disks.is_empty() {
         // lsblk unavailable — fall back to manual input.
         ui::print_warning("Could not detect disks automatically.");
-        let path: String = Input::new()
-            .with_prompt("Enter disk path (e.g. /dev/sda)")
-            .default("/dev/sda".to_string())
-            .interact_text()?;
+        let path = prompt::input("disk", "Enter disk path (e.g. /dev/sda)", "/dev/sda")?;
         return Ok(lsblk::Disk {
             path,
             size: "?".to_string(),
             model: "—".to_string(),
+            tran: String::new(),
+            rotational: false,
+            removable: false,
+            partitions: vec![],
         });
     }
 
     println!();
-    ui::print_info("Use ↑ ↓ arrow keys to select the target disk, then press Enter.");
-    println!(
-        "  {:<12}  {:>8}   {}",
-        style("DISK").dim(),
-        style("SIZE").dim(),
-        style("MODEL").dim()
-    );
-    println!("  {}", style("─".repeat(44)).dim());
+    // The column header only makes sense above an arrow-key menu.
+    if !ui::accessible() {
+        ui::print_info("Use ↑ ↓ arrow keys to select the target disk, then press Enter.");
+        println!(
+            "  {:<12}  {:>8}   {}",
+            style("DISK").dim(),
+            style("SIZE").dim(),
+            style("MODEL").dim()
+        );
+        println!("  {}", style(ui::rule(44)).dim());
+    }
 
     let labels: Vec<String> = disks.iter().map(|d| d.display()).collect();
 
-    let idx = Select::new()
-        .with_prompt("Target disk")
-        .items(&labels)
-        .default(0)
-        .interact()?;
+    let paths: Vec<&str> = disks.iter().map(|d| d.path.as_str()).collect();
+
+    // Preselect the first fixed disk so a flash drive is never the default.
+    let default = disks.iter().position(|d| !d.is_removable()).unwrap_or(0);
+    let idx = prompt::select("disk", "Target disk", &labels, &paths, default)?;
+
+    let disk = &disks[idx];
+    if disk.is_removable() {
+        println!();
+        ui::print_warning(&format!(
+            "{} is a removable/USB device — make sure this is not your install medium.",
+            disk.path
+        ));
+    }
 
-    Ok(disks[idx].clone())
+    Ok(disk.clone())
 }
@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{error::InstallerError, lsblk, prompt, report, ui};
+
+static PORTABLE: AtomicBool = AtomicBool::new(false);
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Asks whether to install a portable system that boots on any machine: the
+/// suggested answer is yes for a removable `disk`. Portable mode means GRUB
+/// only at the removable-media path with no NVRAM entry, `noatime` mounts,
+/// an initramfs without `autodetect`, both CPU microcodes, all firmware and
+/// the generic graphics drivers instead of the ones this machine needs.
+///
+/// Non-interactive mode reads `portable`.
+pub fn ask(disk: &lsblk::Disk) -> Result<(), InstallerError> {
+    println!();
+    let ask = format!("Install a portable system on {} (boots on other machines)?", disk.path);
+    let on = prompt::confirm("portable", &ask, disk.is_removable())?;
+    set(on);
+    if on {
+        ui::print_info("Portable mode: generic drivers, fewer writes, no firmware boot entry.");
+        report::note("portable", "yes");
+    }
+    Ok(())
+}
+
+/// Restores the choice on resume.
+pub fn set(on: bool) {
+    PORTABLE.store(on, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    PORTABLE.load(Ordering::Relaxed)
+}
+
+/// Adds `noatime` to each mount in an fstab, replacing the other atime
+/// options: reads otherwise turn into writes on flash with few to spare.
+pub fn noatime(fstab: &str) -> String {
+    let atime = ["atime", "relatime", "strictatime", "noatime"];
+    fstab
+        .lines()
+        .map(|line| {
+            let mut fields: Vec<&str> = line.split_whitespace().collect();
+            if line.trim_start().starts_with('#') || fields.len() < 4 || fields[2] == "swap" {
+                return format!("{}\n", line);
+            }
+            let mut options: Vec<&str> =
+                fields[3].split(',').filter(|o| !atime.contains(o)).collect();
+            options.push("noatime");
+            let options = options.join(",");
+            fields[3] = &options;
+            format!("{}\n", fields.join("\t"))
+        })
+        .collect()
+}
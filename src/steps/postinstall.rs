@@ -0,0 +1,66 @@
+use crate::{answers, cmd, error::InstallerError, prompt, steps, ui};
+
+/// Components that can be re-entered after the main flow, while `/mnt` is
+/// still mounted. Order matches the menu.
+const ENTRIES: &[(&str, &str)] = &[
+    ("packages", "Install additional packages"),
+    ("makepkg", "Re-run makepkg.conf tuning"),
+    ("verify", "Re-check microcode / firmware"),
+    ("shell", "Open a shell in the new system (artix-chroot)"),
+    ("finish", "Finish installation"),
+];
+
+/// Offers last-minute tweaks in a loop until the user picks "Finish".
+/// A failing component is reported but doesn't end the menu.
+/// Skipped in non-interactive mode.
+pub fn menu() -> Result<(), InstallerError> {
+    if answers::unattended() {
+        return Ok(());
+    }
+
+    let items: Vec<String> = ENTRIES.iter().map(|(_, label)| label.to_string()).collect();
+    let values: Vec<&str> = ENTRIES.iter().map(|(id, _)| *id).collect();
+
+    loop {
+        println!();
+        let idx = prompt::select(
+            "post_install",
+            "Anything else before finishing?",
+            &items,
+            &values,
+            items.len() - 1,
+        )?;
+
+        let result = match ENTRIES[idx].0 {
+            "packages" => install_extra(),
+            "makepkg" => steps::makepkg::run(),
+            "verify" => steps::verify::run(),
+            "shell" => cmd::run_interactive("artix-chroot", &["/mnt"]),
+            _ => return Ok(()),
+        };
+
+        if let Err(e) = result {
+            ui::print_error(&format!("{}", e));
+        }
+    }
+}
+
+/// Installs user-chosen packages inside the new system.
+fn install_extra() -> Result<(), InstallerError> {
+    let line = prompt::input("extra_packages", "Packages to install (space-separated)", "")?;
+    let pkgs: Vec<&str> = line.split_whitespace().collect();
+    if pkgs.is_empty() {
+        ui::print_info("Nothing to install.");
+        return Ok(());
+    }
+
+    let mut args = vec!["/mnt", "pacman", "-S", "--needed"];
+    args.extend(&pkgs);
+
+    println!();
+    // pacman asks for confirmation itself — keep it interactive.
+    cmd::run_interactive("artix-chroot", &args)?;
+
+    ui::print_success(&format!("Installed: {}.", pkgs.join(" ")));
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::fs;
+
+use crate::{
+    cmd,
+    error::InstallerError,
+    files, prompt, report,
+    steps::{bootloader, verify},
+    ui,
+};
+
+const TLP_DROP_IN: &str = "/mnt/etc/tlp.d/50-artix-installer.conf";
+
+/// One hardware-specific default, shown before asking and noted in the report.
+struct Tweak {
+    what: &'static str,
+    setting: &'static str,
+}
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// On laptops, offers opt-in power tuning: TLP plus defaults that fit the
+/// detected hardware — audio codec power saving, PCIe ASPM on battery when
+/// the kernel controls ASPM, and the CPU vendor's P-state driver on the
+/// kernel command line. Each applied setting is noted in the report.
+pub fn run() -> Result<(), InstallerError> {
+    if !has_battery() {
+        return Ok(());
+    }
+
+    let mut tlp_lines = Vec::new();
+    let mut tweaks = Vec::new();
+    if module_loaded("snd_hda_intel") {
+        tlp_lines.push("SOUND_POWER_SAVE_ON_BAT=1");
+        tweaks.push(Tweak { what: "audio", setting: "HDA codec power save on battery" });
+    }
+    if kernel_controls_aspm() {
+        tlp_lines.push("PCIE_ASPM_ON_BAT=powersupersave");
+        tweaks.push(Tweak { what: "PCIe ASPM", setting: "powersupersave on battery" });
+    }
+    let pstate = match verify::cpu_vendor().as_deref() {
+        Some("GenuineIntel") => Some("intel_pstate=active"),
+        Some("AuthenticAMD") => Some("amd_pstate=active"),
+        _ => None,
+    };
+    if let Some(param) = pstate {
+        tweaks.push(Tweak { what: "CPU", setting: param });
+    }
+
+    let mut rows = vec![("tlp", "install and enable at boot")];
+    rows.extend(tweaks.iter().map(|t| (t.what, t.setting)));
+
+    println!();
+    ui::print_kv_box("Power tuning (laptop detected)", &rows);
+    println!();
+
+    if !prompt::confirm("power_tuning", "Apply power tuning for this laptop?", false)? {
+        return Ok(());
+    }
+
+    println!();
+    cmd::run_transaction("basestrap", &["/mnt", "tlp", "tlp-openrc"])?;
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", "tlp", "default"],
+        "Enabling tlp…",
+        "tlp enabled at boot.",
+    )?;
+    report::note("power", "tlp installed and enabled");
+
+    if !tlp_lines.is_empty() {
+        if !cmd::dry_run() {
+            fs::create_dir_all("/mnt/etc/tlp.d")?;
+        }
+        let content = format!(
+            "# Installed by artix-installer (power tuning).\n{}\n",
+            tlp_lines.join("\n")
+        );
+        files::write_atomic(TLP_DROP_IN, &content)?;
+        ui::print_success(&format!("TLP defaults written to {}.", TLP_DROP_IN));
+    }
+
+    let mut cmdline_set = false;
+    if let Some(param) = pstate {
+        cmdline_set = bootloader::add_cmdline(&[param])?;
+        if !cmdline_set {
+            ui::print_warning(&format!(
+                "GRUB isn't configured — add {} to the kernel command line by hand.",
+                param
+            ));
+        }
+    }
+
+    for t in tweaks.iter().filter(|t| cmdline_set || Some(t.setting) != pstate) {
+        report::note("power", &format!("{}: {}", t.what, t.setting));
+    }
+    ui::print_success("Power tuning applied.");
+    Ok(())
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+fn has_battery() -> bool {
+    let Ok(entries) = fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    entries
+        .flatten()
+        .any(|e| fs::read_to_string(e.path().join("type")).is_ok_and(|t| t.trim() == "Battery"))
+}
+
+fn module_loaded(name: &str) -> bool {
+    std::path::Path::new("/sys/module").join(name).exists()
+}
+
+/// The per-device `link/l1_aspm` knobs only exist when the firmware handed
+/// ASPM control to the kernel — otherwise forcing a policy is unsafe.
+fn kernel_controls_aspm() -> bool {
+    let Ok(entries) = fs::read_dir("/sys/bus/pci/devices") else {
+        return false;
+    };
+    entries.flatten().any(|e| e.path().join("link/l1_aspm").exists())
+}
@@ -0,0 +1,94 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use crate::{answers, cmd, interrupt, ui};
+
+/// Unattended only: after a successful install, reboots into the new system
+/// once `reboot.after` seconds have passed. With a TTY attached the
+/// countdown is shown and Enter or Ctrl-C cancels it. Never reboots while
+/// anything is still mounted under `/mnt`.
+pub fn after_install() {
+    let Some(secs) = answers::get().and_then(|a| a.int("reboot.after")) else {
+        return;
+    };
+    if secs < 0 {
+        return;
+    }
+
+    println!();
+    // A dry run mounted nothing, so whatever is under /mnt isn't ours.
+    if cmd::dry_run() {
+        ui::print_info(&format!("[dry-run] would reboot in {}s.", secs));
+        cmd::run_best_effort("reboot", &[]);
+        return;
+    }
+    if still_mounted() {
+        ui::print_warning("Not rebooting — the target is still mounted under /mnt.");
+        return;
+    }
+    if !countdown(secs as u64, io::stdin().is_terminal()) {
+        ui::print_info("Reboot cancelled — reboot when you are ready.");
+        return;
+    }
+    cmd::run_best_effort("reboot", &[]);
+}
+
+/// Waits `secs` seconds; `false` if the user cancelled.
+fn countdown(secs: u64, tty: bool) -> bool {
+    let hint = if tty { " — press Enter or Ctrl-C to cancel" } else { "" };
+    ui::print_info(&format!("Rebooting in {}s{}.", secs, hint));
+
+    // A blocked read can't be interrupted; the thread just dies with us.
+    let (tx, rx) = mpsc::channel();
+    if tty {
+        thread::spawn(move || {
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line).is_ok() {
+                let _ = tx.send(());
+            }
+        });
+    } else {
+        drop(tx);
+    }
+
+    let redraw = tty && !ui::accessible();
+    for left in (1..=secs).rev() {
+        if redraw {
+            print!("\r  {}s ", left);
+            let _ = io::stdout().flush();
+        }
+        let enter = match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(()) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+            // No reader (no TTY, or stdin failed) — just wait.
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(Duration::from_secs(1));
+                false
+            }
+        };
+        if enter || interrupt::interrupted() {
+            interrupt::reset();
+            if redraw {
+                println!();
+            }
+            return false;
+        }
+    }
+    if redraw {
+        println!();
+    }
+    true
+}
+
+fn still_mounted() -> bool {
+    fs::read_to_string("/proc/mounts").is_ok_and(|m| {
+        m.lines()
+            .filter_map(|l| l.split_whitespace().nth(1))
+            .any(|target| target == "/mnt" || target.starts_with("/mnt/"))
+    })
+}
@@ -0,0 +1,198 @@
+use std::{fs, sync::OnceLock};
+
+use crate::{cmd::{self, Cmd}, error::InstallerError, lsblk, prompt, report, ui};
+
+/// Where partitions are mounted read-only to look for an old system.
+const PROBE_DIR: &str = "/tmp/artix-installer-probe";
+const HOME_DIR: &str = "/tmp/artix-installer-home";
+
+/// File systems an old root can be on.
+const ROOT_FSTYPES: &[&str] = &["ext4", "btrfs", "xfs", "bcachefs"];
+
+/// The old copy of /etc, saved on the kept /home partition.
+pub const ETC_BACKUP: &str = "artix-etc-backup.tar.gz";
+
+/// An Artix install found on one of the partitions, with the roles its
+/// fstab gave the others.
+#[derive(Debug, Clone)]
+pub struct Previous {
+    pub name: String,
+    pub root: String,
+    /// `etc` inside the root file system — `@/etc` on btrfs.
+    etc: String,
+    pub efi: Option<String>,
+    pub swap: Option<String>,
+    pub var: Option<String>,
+    pub home: String,
+    pub backup_etc: bool,
+}
+
+/// The reinstall the user agreed to, decided once per run.
+static CHOSEN: OnceLock<Option<Previous>> = OnceLock::new();
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Looks for an existing Artix system (via its `os-release`) whose /home is
+/// a partition of its own, and offers to reinstall over it: the old roles
+/// become the suggested ones, root is formatted and /home is kept as it is,
+/// UUID included. Optionally the old /etc is saved to /home first. Asked
+/// once; later calls return the same answer.
+///
+/// Non-interactive mode reads `reinstall` (default: no) and
+/// `reinstall_backup_etc`.
+pub fn offer(disks: &[lsblk::Disk]) -> Result<Option<Previous>, InstallerError> {
+    if let Some(chosen) = CHOSEN.get() {
+        return Ok(chosen.clone());
+    }
+    let chosen = ask(disks)?;
+    let _ = CHOSEN.set(chosen.clone());
+    Ok(chosen)
+}
+
+/// The reinstall chosen by [`offer`], if any.
+pub fn chosen() -> Option<&'static Previous> {
+    CHOSEN.get()?.as_ref()
+}
+
+/// Copies the old /etc into the /home partition before root is formatted.
+pub fn backup_etc(cmd: Cmd, prev: &Previous) -> Result<(), InstallerError> {
+    let archive = format!("{}/{}", HOME_DIR, ETC_BACKUP);
+    for dir in [PROBE_DIR, HOME_DIR] {
+        if !cmd::dry_run() {
+            fs::create_dir_all(dir)?;
+        }
+    }
+    cmd.run_with_spinner(
+        "mount",
+        &["-o", "ro", &prev.root, PROBE_DIR],
+        &format!("Mounting the old root {}…", prev.root),
+        "Old root mounted read-only.",
+    )?;
+    let result = cmd.run_with_spinner(
+        "mount",
+        &[&prev.home, HOME_DIR],
+        &format!("Mounting {}…", prev.home),
+        "/home partition mounted.",
+    )
+    .and_then(|()| {
+        let copied = cmd.run_with_spinner(
+            "tar",
+            &["-C", PROBE_DIR, "-czf", &archive, &prev.etc],
+            "Saving the old /etc…",
+            &format!("Old /etc saved to /home/{}.", ETC_BACKUP),
+        );
+        cmd.run_best_effort("umount", &[HOME_DIR]);
+        copied
+    });
+    cmd.run_best_effort("umount", &[PROBE_DIR]);
+    result?;
+    report::note("reinstall", &format!("old /etc saved to /home/{}", ETC_BACKUP));
+    Ok(())
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+fn ask(disks: &[lsblk::Disk]) -> Result<Option<Previous>, InstallerError> {
+    // Probing mounts partitions; a dry run only prints commands.
+    if cmd::dry_run() {
+        return Ok(None);
+    }
+    let candidates = disks
+        .iter()
+        .flat_map(|d| &d.partitions)
+        .filter(|p| p.mountpoint.is_empty() && ROOT_FSTYPES.contains(&p.fstype.as_str()));
+    let Some((name, root, fstab, etc)) = candidates.into_iter().find_map(probe) else {
+        return Ok(None);
+    };
+
+    let entry = |want: &dyn Fn(&[&str]) -> bool| {
+        fstab.lines().map(|l| l.split_whitespace().collect::<Vec<_>>()).find_map(|f| {
+            (f.len() >= 3 && !f[0].starts_with('#') && want(&f)).then(|| device_of(f[0], disks))?
+        })
+    };
+    let home = entry(&|f| f[1] == "/home").filter(|h| *h != root);
+    let Some(home) = home else {
+        println!();
+        ui::print_warning(&format!(
+            "{} is on {}, with /home on the same partition — installing over it erases /home.",
+            name, root
+        ));
+        return Ok(None);
+    };
+    let efi = entry(&|f| f[2] == "vfat" && ["/boot", "/boot/efi", "/efi"].contains(&f[1]));
+    let prev = Previous {
+        efi,
+        swap: entry(&|f| f[2] == "swap"),
+        var: entry(&|f| f[1] == "/var"),
+        home,
+        name,
+        root,
+        etc,
+        backup_etc: false,
+    };
+
+    println!();
+    ui::print_kv_box(
+        "Existing installation",
+        &[
+            ("system", prev.name.as_str()),
+            ("root", prev.root.as_str()),
+            ("/home", prev.home.as_str()),
+        ],
+    );
+    println!();
+    let ask = format!("Reinstall over it — format {}, keep /home on {}?", prev.root, prev.home);
+    if !prompt::confirm("reinstall", &ask, !crate::answers::unattended())? {
+        return Ok(None);
+    }
+    let ask = format!("Save the old /etc to /home/{} first?", ETC_BACKUP);
+    let backup_etc = prompt::confirm("reinstall_backup_etc", &ask, true)?;
+    report::note("reinstall", &format!("{}, /home kept on {}", prev.name, prev.home));
+    Ok(Some(Previous { backup_etc, ..prev }))
+}
+
+/// Mounts `part` read-only and reads an Artix `os-release` and fstab from
+/// it: (`PRETTY_NAME`, device, fstab, path of etc).
+fn probe(part: &lsblk::Partition) -> Option<(String, String, String, String)> {
+    fs::create_dir_all(PROBE_DIR).ok()?;
+    cmd::run_best_effort("mount", &["-o", "ro", &part.path, PROBE_DIR]);
+    let found = ["etc", "@/etc"].iter().find_map(|etc| {
+        let os_release = fs::read_to_string(format!("{}/{}/os-release", PROBE_DIR, etc)).ok()?;
+        let value = |key: &str| {
+            os_release
+                .lines()
+                .find_map(|l| l.strip_prefix(key))
+                .map(|v| v.trim_matches('"').to_string())
+        };
+        if value("ID=")? != "artix" {
+            return None;
+        }
+        let fstab = fs::read_to_string(format!("{}/{}/fstab", PROBE_DIR, etc)).ok()?;
+        let name = value("PRETTY_NAME=").unwrap_or_else(|| "Artix Linux".to_string());
+        Some((name, part.path.clone(), fstab, etc.to_string()))
+    });
+    cmd::run_best_effort("umount", &[PROBE_DIR]);
+    found
+}
+
+/// An fstab device spec (`UUID=…`, `LABEL=…`, `PARTUUID=…` or a path) as
+/// its current device node. UUIDs are looked up in the lsblk listing first,
+/// which doesn't depend on udev having created the by-uuid links.
+fn device_of(spec: &str, disks: &[lsblk::Disk]) -> Option<String> {
+    let link = |dir: &str, name: &str| {
+        fs::canonicalize(format!("/dev/disk/{}/{}", dir, name))
+            .ok()
+            .map(|p| p.to_string_lossy().into_owned())
+    };
+    if let Some(uuid) = spec.strip_prefix("UUID=") {
+        let parts = disks.iter().flat_map(|d| &d.partitions);
+        let listed = parts.into_iter().find(|p| p.uuid == uuid).map(|p| p.path.clone());
+        listed.or_else(|| lsblk::device_for_uuid(uuid))
+    } else if let Some(label) = spec.strip_prefix("LABEL=") {
+        link("by-label", label)
+    } else if let Some(partuuid) = spec.strip_prefix("PARTUUID=") {
+        link("by-partuuid", partuuid)
+    } else {
+        spec.starts_with("/dev/").then(|| spec.to_string())
+    }
+}
@@ -0,0 +1,147 @@
+use console::style;
+
+use crate::{
+    answers,
+    config::{Config, SeatManager},
+    error::InstallerError,
+    health, prompt,
+    steps::{self, firewall, network, packages, ssh, users},
+    ui,
+};
+
+/// What the review settled: the partition layout, and the answers preset for
+/// the later steps as `(prompt key, value)` pairs.
+pub struct Reviewed {
+    pub layout: Config,
+    pub presets: Vec<(String, String)>,
+}
+
+/// Asks everything the installation needs up front — partition roles,
+/// kernel, session manager, desktop, bootloader, hostname, the user with
+/// groups and shell, network backend, SSH and firewall — and shows it on
+/// one screen with a single confirmation, before anything is formatted.
+/// Passwords, SSH keys and static addresses are still asked by their steps.
+///
+/// The answers are preset for the later steps, which then run without
+/// asking again, and returned with the layout for the session to keep.
+/// Declining cancels the installation with the disk untouched (beyond the
+/// partition table written in cfdisk). Unattended runs only show the
+/// summary: the answer file already decides, `format` included.
+pub fn run(disk: &str) -> Result<Reviewed, InstallerError> {
+    println!();
+    ui::print_info("Answer the questions below; nothing is changed until you confirm.");
+
+    let layout = steps::format::collect_layout(disk)?;
+    let kernels = steps::packages::ask_kernels()?;
+    let kernel_names: Vec<&str> = kernels.iter().map(|k| k.package_name()).collect();
+    let seat = packages::ask_seat_manager()?;
+    println!();
+    let desktop = prompt::confirm("desktop", "Install a desktop session?", true)?;
+    let session = if desktop { Some(packages::ask_session()?) } else { None };
+    let session_row = session.map(|s| format!("{} ({})", s.name, s.display()));
+    let bootloader = prompt::confirm("bootloader", "Install the GRUB bootloader?", true)?;
+    let hostname = steps::identity::ask_hostname()?;
+    let user = match users::ask_username()? {
+        Some(name) => Some((name, users::ask_groups(seat)?, users::ask_shell()?)),
+        None => None,
+    };
+    let user_row = user.as_ref().map(|(name, groups, ((shell, ..), _))| {
+        let mut groups = groups.clone();
+        if seat == SeatManager::Seatd {
+            groups.push("seat");
+        }
+        format!("{} ({}), {}", name, groups.join(", "), shell)
+    });
+    let backend = network::ask_backend()?;
+    let sshd = ssh::ask()?;
+    let firewall = firewall::ask()?;
+    health::requirements(&layout.root_partition, layout.var_partition.as_deref(), desktop)?;
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+    let swap = layout.swap_partition.as_deref().unwrap_or("none");
+    let var = layout.var_partition.as_deref().unwrap_or("on root");
+    let home = layout.home_partition.as_ref().map(|h| format!("{} (kept)", h));
+    let root_label = format!("Root ({})", layout.root_fs.name());
+    println!();
+    ui::print_kv_box(
+        "Review",
+        &[
+            ("Disk",         disk),
+            ("EFI  (FAT32)", &layout.efi_partition),
+            ("Swap",         swap),
+            (&root_label,    &layout.root_partition),
+            ("/var (ext4)",  var),
+            ("/home",        home.as_deref().unwrap_or("on root")),
+            ("/tmp",         if layout.tmpfs_tmp { "tmpfs" } else { "on root" }),
+            ("Kernel",       &kernel_names.join(", ")),
+            ("Seat manager", seat.as_str()),
+            ("Desktop",      session_row.as_deref().unwrap_or("none")),
+            ("Bootloader",   if bootloader { "GRUB" } else { "none" }),
+            ("Hostname",     hostname.trim()),
+            ("User",         user_row.as_deref().unwrap_or("none")),
+            ("Network",      backend.map_or("none", |b| b.name())),
+            ("SSH server",   if sshd { "sshd" } else { "none" }),
+            ("Firewall",     firewall.map_or("none", |f| f.name())),
+        ],
+    );
+    println!();
+
+    if answers::unattended() {
+        return Ok(Reviewed { layout, presets: vec![] });
+    }
+
+    let mut erased = vec![layout.efi_partition.as_str()];
+    erased.extend(layout.swap_partition.as_deref());
+    erased.push(&layout.root_partition);
+    erased.extend(layout.var_partition.as_deref());
+    println!(
+        "  {}",
+        style(format!("{}  CONFIRMING ERASES {}.", ui::warn_mark(), erased.join(", ")))
+            .red()
+            .bold()
+    );
+    println!();
+
+    if !prompt::confirm("review", "Install Artix with these settings?", false)? {
+        return Err(InstallerError::Cancelled);
+    }
+
+    let mut presets = Vec::new();
+    let mut set = |key: &str, value: &str| presets.push((key.to_string(), value.to_string()));
+    // The layout itself goes to the format step as it is.
+    set("format", "yes");
+    let kernel_ids: Vec<&str> = kernels.iter().map(|k| k.as_str()).collect();
+    set("kernel", &kernel_ids.join(","));
+    set("seat", seat.as_str());
+    set("desktop", yes_no(desktop));
+    if let Some(session) = session {
+        set("display", if session.xorg { "xorg" } else { "wayland" });
+        set("session", session.id);
+    }
+    set("bootloader", yes_no(bootloader));
+    set("hostname", hostname.trim());
+    match &user {
+        Some((name, groups, ((shell, _, _, config), starter))) => {
+            set("user.name", name);
+            set("user.groups", &groups.join(","));
+            set("user.shell", shell);
+            if config.is_some() {
+                set("user.shell_config", yes_no(*starter));
+            }
+        }
+        None => set("user.name", ""),
+    }
+    set("network.backend", backend.map_or("none", |b| b.id()));
+    set("ssh.enable", yes_no(sshd));
+    set("firewall", firewall.map_or("none", |f| f.name()));
+    apply(&presets);
+    Ok(Reviewed { layout, presets })
+}
+
+/// Presets the reviewed answers, so the later steps don't ask again. Also
+/// called on resume, with the answers saved in the session.
+pub fn apply(presets: &[(String, String)]) {
+    for (key, value) in presets {
+        prompt::preset(key, value);
+    }
+}
@@ -0,0 +1,169 @@
+use std::{fs, os::unix::fs::PermissionsExt};
+
+use crate::{answers, cmd, error::InstallerError, files, prompt, report, ui};
+
+const DROP_IN_DIR: &str = "/mnt/etc/ssh/sshd_config.d";
+const DROP_IN: &str = "/mnt/etc/ssh/sshd_config.d/50-artix-installer.conf";
+
+/// Key types accepted in `authorized_keys`.
+const KEY_PREFIXES: &[&str] = &["ssh-", "ecdsa-", "sk-"];
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Optionally installs the OpenSSH server and enables `sshd` at boot, then
+/// imports public keys for `user` from a file, a URL or a GitHub account.
+/// Password logins can be turned off once at least one key is in place.
+/// Returns `true` when sshd was set up (the firewall opens port 22 then).
+///
+/// Non-interactive mode reads `ssh.enable`, one of `ssh.keys_file`,
+/// `ssh.keys_url` or `ssh.github`, and `ssh.no_passwords`.
+pub fn run(user: Option<&str>) -> Result<bool, InstallerError> {
+    if !ask()? {
+        return Ok(false);
+    }
+
+    println!();
+    cmd::run_transaction("basestrap", &["/mnt", "openssh", "openssh-openrc"])?;
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "rc-update", "add", "sshd", "default"],
+        "Enabling sshd…",
+        "sshd enabled at boot.",
+    )?;
+    report::note("ssh", "sshd enabled");
+
+    let Some(user) = user else {
+        ui::print_info("No user to import keys for — add them to ~/.ssh/authorized_keys later.");
+        return Ok(true);
+    };
+    let keys = import_keys()?;
+    if keys.is_empty() {
+        return Ok(true);
+    }
+    install_keys(user, &keys)?;
+
+    println!();
+    let ask = "Disable password logins over SSH (keys only)?";
+    if prompt::confirm("ssh.no_passwords", ask, true)? {
+        if !cmd::dry_run() {
+            fs::create_dir_all(DROP_IN_DIR)?;
+        }
+        files::write_atomic(
+            DROP_IN,
+            "# Written by artix-installer.\n\
+             PasswordAuthentication no\n\
+             KbdInteractiveAuthentication no\n",
+        )?;
+        ui::print_success("sshd accepts keys only.");
+        report::note("ssh", "password authentication disabled");
+    }
+    Ok(true)
+}
+
+/// Whether to set up sshd at all.
+pub fn ask() -> Result<bool, InstallerError> {
+    println!();
+    prompt::confirm("ssh.enable", "Install and enable the OpenSSH server?", false)
+}
+
+// ── authorized_keys ───────────────────────────────────────────────────────────
+
+/// Where the keys come from; empty when none was given or nothing usable
+/// was found.
+fn import_keys() -> Result<Vec<String>, InstallerError> {
+    let source = match answers::get() {
+        Some(a) => {
+            if let Some(path) = a.str("ssh.keys_file") {
+                Some(read_file(path))
+            } else if let Some(url) = a.str("ssh.keys_url") {
+                Some(fetch(url))
+            } else {
+                a.str("ssh.github").map(|u| fetch(&github_url(u)))
+            }
+        }
+        None => {
+            let items = [
+                "None — add keys later".to_string(),
+                "From a file on this live system".to_string(),
+                "From a URL".to_string(),
+                "From a GitHub account (github.com/<user>.keys)".to_string(),
+            ];
+            let values = ["none", "file", "url", "github"];
+            println!();
+            match prompt::select("ssh.keys", "Import SSH public keys", &items, &values, 0)? {
+                1 => Some(read_file(&prompt::input("ssh.keys_file", "Path", "")?)),
+                2 => Some(fetch(&prompt::input("ssh.keys_url", "URL", "")?)),
+                3 => Some(fetch(&github_url(&prompt::input("ssh.github", "GitHub user", "")?))),
+                _ => None,
+            }
+        }
+    };
+    let Some(text) = source else {
+        return Ok(vec![]);
+    };
+
+    let keys: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| KEY_PREFIXES.iter().any(|p| l.starts_with(p)))
+        .map(str::to_string)
+        .collect();
+    if keys.is_empty() && !cmd::dry_run() {
+        ui::print_warning("No public keys found there — add them to authorized_keys later.");
+    }
+    Ok(keys)
+}
+
+fn read_file(path: &str) -> String {
+    fs::read_to_string(path.trim()).unwrap_or_else(|e| {
+        ui::print_warning(&format!("Can't read {}: {}", path.trim(), e));
+        String::new()
+    })
+}
+
+/// Downloads over HTTPS with curl from the live system; a failure just
+/// means no keys.
+fn fetch(url: &str) -> String {
+    let url = url.trim();
+    cmd::run_capture("curl", &["-fsSL", "--max-time", "30", "--proto", "=https", url])
+        .unwrap_or_else(|e| {
+            ui::print_warning(&format!("Couldn't download {}: {}", url, e));
+            String::new()
+        })
+}
+
+fn github_url(user: &str) -> String {
+    format!("https://github.com/{}.keys", user.trim())
+}
+
+/// Writes `~/.ssh/authorized_keys` for `user` (keeping keys already there)
+/// with the ownership and modes sshd insists on.
+fn install_keys(user: &str, keys: &[String]) -> Result<(), InstallerError> {
+    let dir = format!("/home/{}/.ssh", user);
+    let path = format!("{}/authorized_keys", dir);
+
+    let existing = fs::read_to_string(format!("/mnt{}", path)).unwrap_or_default();
+    let mut content = existing.clone();
+    for key in keys.iter().filter(|k| !existing.lines().any(|l| l.trim() == k.as_str())) {
+        content.push_str(key);
+        content.push('\n');
+    }
+
+    if !cmd::dry_run() {
+        fs::create_dir_all(format!("/mnt{}", dir))?;
+    }
+    files::write_atomic(&format!("/mnt{}", path), &content)?;
+    if !cmd::dry_run() {
+        fs::set_permissions(format!("/mnt{}", dir), fs::Permissions::from_mode(0o700))?;
+        fs::set_permissions(format!("/mnt{}", path), fs::Permissions::from_mode(0o600))?;
+    }
+    // The user only exists inside the target, so chown runs there.
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "chown", "-R", &format!("{}:{}", user, user), &dir],
+        &format!("Handing {} to {}…", dir, user),
+        &format!("{} belongs to {}.", dir, user),
+    )?;
+    ui::print_success(&format!("{} key(s) authorized for {}.", keys.len(), user));
+    Ok(())
+}
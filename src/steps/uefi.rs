@@ -1,15 +1,24 @@
-use std::path::Path;
+use std::{fs, path::Path};
 
-use crate::{error::InstallerError, ui};
+use crate::{cmd, error::InstallerError, ui};
 
 /// Asserts that the system booted in UEFI mode.
-/// Aborts with `BiosNotSupported` if not.
+/// Aborts with `BiosNotSupported` if not. A dry run simulates UEFI.
 pub fn check() -> Result<(), InstallerError> {
-    if Path::new("/sys/firmware/efi/efivars").exists() {
+    if cmd::dry_run() || Path::new("/sys/firmware/efi/efivars").exists() {
         ui::print_success("UEFI mode detected — EFI system partition required.");
+        if is_ia32() {
+            ui::print_info("32-bit UEFI on a 64-bit CPU — GRUB will be installed for i386-efi.");
+        }
         Ok(())
     } else {
         ui::print_error("BIOS/Legacy mode detected. This installer only supports UEFI.");
         Err(InstallerError::BiosNotSupported)
     }
 }
+
+/// `true` on 32-bit UEFI firmware (IA32). The kernel reports the firmware's
+/// word size, not the CPU's.
+pub fn is_ia32() -> bool {
+    fs::read_to_string("/sys/firmware/efi/fw_platform_size").is_ok_and(|s| s.trim() == "32")
+}
@@ -0,0 +1,272 @@
+use std::fs;
+
+use crate::{answers, cmd, config::SeatManager, error::InstallerError, files, prompt, report, ui};
+
+const SUDOERS: &str = "/mnt/etc/sudoers";
+
+/// Supplementary groups offered for the new user: (group, what it grants).
+/// `wheel` is preselected and also unlocks sudo.
+pub const GROUPS: &[(&str, &str)] = &[
+    ("wheel", "administration via sudo"),
+    ("video", "direct GPU / backlight access"),
+    ("audio", "direct sound-card access (elogind grants it per session)"),
+    ("input", "raw input devices (gamepads, remappers)"),
+    ("storage", "removable drives without a password"),
+    ("libvirt", "manage virtual machines"),
+    ("docker", "run containers — equivalent to root"),
+];
+
+/// A login shell: (name, package, path, starter config package).
+pub type Shell = (&'static str, &'static str, &'static str, Option<&'static str>);
+
+/// Login shells on offer.
+pub const SHELLS: &[Shell] = &[
+    ("bash", "bash", "/bin/bash", None),
+    ("zsh", "zsh", "/usr/bin/zsh", Some("grml-zsh-config")),
+    ("fish", "fish", "/usr/bin/fish", None),
+];
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Sets the root password and creates the regular user, both optional.
+/// Returns the user's name, if one was created.
+///
+/// With seatd the user joins `seat`, and `video` / `audio` are preselected:
+/// nothing grants those devices per session as elogind does.
+///
+/// Non-interactive mode reads `root_password`, `user.name`,
+/// `user.password`, `user.groups` and `user.shell`; a missing name skips
+/// the user, a missing password leaves that account for `passwd` in the
+/// chroot.
+pub fn run(seat: SeatManager) -> Result<Option<String>, InstallerError> {
+    root_password()?;
+    create_user(seat)
+}
+
+/// Sets the root password and, if there is one, `user`'s — asked without
+/// the "now?" question, for the post-chroot menu.
+pub fn passwords(user: Option<&str>) -> Result<(), InstallerError> {
+    for account in ["root"].into_iter().chain(user) {
+        println!();
+        let key = if account == "root" { "root_password" } else { "user.password" };
+        let password = ui::ask_password(key, &format!("Password for {}", account))?;
+        set_password(account, &password)?;
+    }
+    Ok(())
+}
+
+fn root_password() -> Result<(), InstallerError> {
+    println!();
+    let ask = "Set the root password now?";
+    let Some(password) = ask_secret("root_password", "Root password", ask)? else {
+        return Ok(());
+    };
+    set_password("root", &password)
+}
+
+fn create_user(seat: SeatManager) -> Result<Option<String>, InstallerError> {
+    let Some(user) = ask_username()? else {
+        ui::print_info("No user created — add one from the chroot with useradd.");
+        return Ok(None);
+    };
+    let name = user.as_str();
+
+    if exists(&["id", "-u", name]) {
+        ui::print_success(&format!("User {} already exists.", name));
+    } else {
+        cmd::run_with_spinner(
+            "artix-chroot",
+            &["/mnt", "useradd", "-m", name],
+            &format!("Creating user {}…", name),
+            &format!("User {} created.", name),
+        )?;
+    }
+
+    let mut groups = ask_groups(seat)?;
+    if seat == SeatManager::Seatd {
+        groups.push("seat");
+    }
+    add_to_groups(name, &groups)?;
+    if groups.contains(&"wheel") {
+        enable_wheel_sudo()?;
+    }
+    let shell = login_shell(name)?;
+
+    let what = format!("Password for {}", name);
+    if let Some(password) = ask_secret("user.password", &what, &format!("{}?", what))? {
+        set_password(name, &password)?;
+    }
+
+    report::note("user", &format!("{} ({}), shell {}", name, groups.join(", "), shell));
+    Ok(Some(user))
+}
+
+/// `true` if the lookup (`id`, `getent`) finds the account in the target.
+fn exists(lookup: &[&str]) -> bool {
+    let mut args = vec!["/mnt"];
+    args.extend(lookup);
+    cmd::run_capture("artix-chroot", &args).is_ok_and(|out| !out.trim().is_empty())
+}
+
+// ── Groups ────────────────────────────────────────────────────────────────────
+
+/// The supplementary groups to join, from [`GROUPS`].
+pub fn ask_groups(seat: SeatManager) -> Result<Vec<&'static str>, InstallerError> {
+    let items: Vec<String> = GROUPS.iter().map(|(g, what)| format!("{:<8}  {}", g, what)).collect();
+    let values: Vec<&str> = GROUPS.iter().map(|(g, _)| *g).collect();
+    let preselected: &[&str] = match seat {
+        SeatManager::Elogind => &["wheel"],
+        SeatManager::Seatd => &["wheel", "video", "audio"],
+    };
+    let defaults: Vec<bool> = GROUPS.iter().map(|(g, _)| preselected.contains(g)).collect();
+
+    println!();
+    let picked = prompt::multi_select("user.groups", "Groups", &items, &values, &defaults)?;
+    Ok(picked.into_iter().map(|i| values[i]).collect())
+}
+
+/// `usermod -aG` in the chroot. Groups that only appear with their package
+/// (libvirt, docker) are created first as system groups; the package
+/// reuses them when it is installed later.
+fn add_to_groups(user: &str, groups: &[&str]) -> Result<(), InstallerError> {
+    for group in groups {
+        if !exists(&["getent", "group", group]) {
+            cmd::run_with_spinner(
+                "artix-chroot",
+                &["/mnt", "groupadd", "-r", group],
+                &format!("Creating group {}…", group),
+                &format!("Group {} created.", group),
+            )?;
+        }
+    }
+    let list = groups.join(",");
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "usermod", "-aG", &list, user],
+        &format!("Adding {} to {}…", user, list),
+        &format!("{} is in {}.", user, list),
+    )
+}
+
+/// Uncomments the `%wheel ALL=(ALL:ALL) ALL` line shipped in sudoers.
+fn enable_wheel_sudo() -> Result<(), InstallerError> {
+    let Ok(original) = fs::read_to_string(SUDOERS) else {
+        if !cmd::dry_run() {
+            ui::print_warning("sudo isn't installed — wheel members can't use sudo.");
+        }
+        return Ok(());
+    };
+    let updated: String = original
+        .lines()
+        .map(|l| match l.trim_start().strip_prefix('#').map(str::trim_start) {
+            Some(rule) if rule == "%wheel ALL=(ALL:ALL) ALL" => format!("{}\n", rule),
+            _ => format!("{}\n", l),
+        })
+        .collect();
+    if updated != original {
+        files::edit(SUDOERS, &updated)?;
+    }
+    Ok(())
+}
+
+// ── Shell ─────────────────────────────────────────────────────────────────────
+
+/// The login shell from [`SHELLS`], and whether to add its starter
+/// configuration. For zsh, grml's configuration is offered as a starting
+/// point — it applies system-wide and each user's `.zshrc` still overrides it.
+pub fn ask_shell() -> Result<(&'static Shell, bool), InstallerError> {
+    let items: Vec<String> = SHELLS.iter().map(|(name, ..)| name.to_string()).collect();
+    let values: Vec<&str> = SHELLS.iter().map(|(name, ..)| *name).collect();
+    println!();
+    let shell = &SHELLS[prompt::select("user.shell", "Login shell", &items, &values, 0)?];
+    let Some(starter) = shell.3 else {
+        return Ok((shell, false));
+    };
+    let ask = format!("Add {} as a starting configuration?", starter);
+    Ok((shell, prompt::confirm("user.shell_config", &ask, true)?))
+}
+
+/// Sets the login shell from [`ask_shell`], installing it first unless it
+/// is bash (part of base).
+fn login_shell(user: &str) -> Result<&'static str, InstallerError> {
+    let (&(name, pkg, path, config), starter) = ask_shell()?;
+    if name == "bash" {
+        return Ok(name);
+    }
+
+    let mut pkgs = vec!["/mnt", pkg];
+    pkgs.extend(config.filter(|_| starter));
+    println!();
+    cmd::run_transaction("basestrap", &pkgs)?;
+    cmd::run_with_spinner(
+        "artix-chroot",
+        &["/mnt", "chsh", "-s", path, user],
+        &format!("Setting the login shell of {}…", user),
+        &format!("{} logs in with {}.", user, name),
+    )?;
+    Ok(name)
+}
+
+// ── Passwords ─────────────────────────────────────────────────────────────────
+
+/// A password from `ui::ask_password`, or `None` when it is left for later:
+/// declined interactively, or absent from the answer file.
+fn ask_secret(key: &str, prompt: &str, ask: &str) -> Result<Option<String>, InstallerError> {
+    let wanted = match answers::get() {
+        Some(a) => a.str(key).is_some(),
+        None => prompt::confirm(&format!("{}_now", key), ask, true)?,
+    };
+    if !wanted {
+        ui::print_info("Skipping — set it from the chroot with passwd.");
+        return Ok(None);
+    }
+    ui::ask_password(key, prompt).map(Some)
+}
+
+/// `chpasswd` reads `user:password` from stdin, so the secret never shows
+/// up in a process listing or the log.
+fn set_password(user: &str, password: &str) -> Result<(), InstallerError> {
+    cmd::run_feed("artix-chroot", &["/mnt", "chpasswd"], &format!("{}:{}\n", user, password))?;
+    ui::print_success(&format!("Password set for {}.", user));
+    Ok(())
+}
+
+/// The login name, `None` when left blank. Asked again until valid; an
+/// invalid name in the answer file is an error.
+pub fn ask_username() -> Result<Option<String>, InstallerError> {
+    loop {
+        let name = match answers::get() {
+            Some(a) => a.str("user.name").unwrap_or_default().to_string(),
+            None => prompt::input("user.name", "Username (blank for none)", "")?,
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            return Ok(None);
+        }
+        match username_problem(name) {
+            None => return Ok(Some(name.to_string())),
+            Some(why) if answers::get().is_some() => {
+                return Err(InstallerError::Config(format!("username '{}' {}", name, why)));
+            }
+            Some(why) => ui::print_warning(&format!("'{}' {}.", name, why)),
+        }
+    }
+}
+
+/// Portable login names: lowercase letter or `_` first, then lowercase
+/// letters, digits, `_` or `-`; 32 characters at most.
+fn username_problem(name: &str) -> Option<&'static str> {
+    let mut chars = name.chars();
+    let first_ok = chars.next().is_some_and(|c| c.is_ascii_lowercase() || c == '_');
+    let rest_ok =
+        chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if name == "root" {
+        Some("is reserved")
+    } else if name.len() > 32 {
+        Some("is longer than 32 characters")
+    } else if !first_ok || !rest_ok {
+        Some("may only use lowercase letters, digits, '_' and '-', starting with a letter")
+    } else {
+        None
+    }
+}
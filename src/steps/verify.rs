@@ -0,0 +1,216 @@
+use std::{collections::HashSet, fs};
+
+use console::style;
+use crate::{cmd, config::Arch, error::InstallerError, prompt, steps::portable, ui};
+
+// ── Hardware → package table ──────────────────────────────────────────────────
+
+/// A package the detected hardware needs in the target, and why.
+struct Requirement {
+    package: &'static str,
+    reason: String,
+}
+
+/// PCI vendor ID → firmware package for network controllers (class 0x02).
+const NET_FIRMWARE: &[(&str, &str, &str)] = &[
+    ("0x14c3", "MediaTek", "linux-firmware-mediatek"),
+    ("0x168c", "Qualcomm Atheros", "linux-firmware-atheros"),
+    ("0x17cb", "Qualcomm", "linux-firmware-atheros"),
+    ("0x14e4", "Broadcom", "linux-firmware-broadcom"),
+    ("0x10ec", "Realtek", "linux-firmware-realtek"),
+    ("0x8086", "Intel", "linux-firmware-intel"),
+];
+
+/// PCI vendor ID → firmware package for display controllers (class 0x03).
+const GPU_FIRMWARE: &[(&str, &str, &str)] = &[
+    ("0x1002", "AMD", "linux-firmware-amdgpu"),
+    ("0x10de", "NVIDIA", "linux-firmware-nvidia"),
+];
+
+/// Firmware outside the default set, offered in the kernel step when the
+/// matching hardware is present: (PCI vendor, class prefix, what, package).
+const EXTRA_FIRMWARE: &[(&str, &str, &str, &str)] = &[
+    ("0x8086", "0x0401", "Intel audio DSP", "sof-firmware"),
+    ("0x8086", "0x0403", "Intel audio DSP", "sof-firmware"),
+    ("0x1022", "0x0480", "AMD audio coprocessor", "sof-firmware"),
+    ("0x11ab", "0x02", "Marvell network chipset", "linux-firmware-marvell"),
+    ("0x1b4b", "0x02", "Marvell network chipset", "linux-firmware-marvell"),
+];
+
+/// DMI `sys_vendor` values of common hypervisors → display name.
+const HYPERVISORS: &[(&str, &str)] = &[
+    ("QEMU", "QEMU/KVM"),
+    ("innotek GmbH", "VirtualBox"),
+    ("VMware, Inc.", "VMware"),
+    ("Microsoft Corporation", "Hyper-V"),
+    ("Xen", "Xen"),
+    ("Parallels Software International Inc.", "Parallels"),
+];
+
+// ── Public API ────────────────────────────────────────────────────────────────
+
+/// Checks that the detected CPU microcode and device firmware packages are
+/// installed in `/mnt`, listing anything missing and offering to install it.
+pub fn run() -> Result<(), InstallerError> {
+    let required = detect_requirements();
+    if required.is_empty() {
+        ui::print_success("No microcode or firmware requirements detected.");
+        return Ok(());
+    }
+
+    let installed: HashSet<String> = cmd::run_capture("pacman", &["--root", "/mnt", "-Qq"])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+
+    let missing: Vec<&Requirement> = required
+        .iter()
+        .filter(|r| !installed.contains(r.package))
+        .collect();
+
+    for r in required.iter().filter(|r| installed.contains(r.package)) {
+        ui::print_success(&format!("{} — {} installed.", r.reason, r.package));
+    }
+
+    if missing.is_empty() {
+        ui::print_success("All detected hardware has its firmware/microcode installed.");
+        return Ok(());
+    }
+
+    println!();
+    for r in &missing {
+        ui::print_warning(&format!(
+            "{} needs {}",
+            r.reason,
+            style(r.package).cyan().bold()
+        ));
+    }
+    println!();
+
+    if !prompt::confirm(
+        "install_missing_firmware",
+        "Install the missing packages before rebooting?",
+        true,
+    )? {
+        ui::print_warning("Skipping — the hardware above may not work after reboot.");
+        return Ok(());
+    }
+
+    let mut args = vec!["/mnt"];
+    args.extend(missing.iter().map(|r| r.package));
+
+    println!();
+    cmd::run_transaction("basestrap", &args)?;
+
+    ui::print_success("Missing firmware/microcode installed.");
+    Ok(())
+}
+
+// ── Detection ─────────────────────────────────────────────────────────────────
+
+fn detect_requirements() -> Vec<Requirement> {
+    let mut reqs = Vec::new();
+
+    // A portable system may boot on either vendor's CPU.
+    if portable::enabled() && Arch::current() == Arch::X86_64 {
+        for (package, cpus) in [("intel-ucode", "Intel"), ("amd-ucode", "AMD")] {
+            reqs.push(Requirement { package, reason: format!("portable: {} CPUs", cpus) });
+        }
+    } else if let Some(vendor) = cpu_vendor() {
+        match vendor.as_str() {
+            "GenuineIntel" => reqs.push(Requirement {
+                package: "intel-ucode",
+                reason: "Intel CPU".to_string(),
+            }),
+            "AuthenticAMD" => reqs.push(Requirement {
+                package: "amd-ucode",
+                reason: "AMD CPU".to_string(),
+            }),
+            _ => {}
+        }
+    }
+
+    // Emulated devices (e.g. QEMU's e1000) need no firmware.
+    if hypervisor().is_some() {
+        return reqs;
+    }
+
+    for (vendor, class) in pci_devices() {
+        let (table, kind) = match class.get(..4) {
+            Some("0x02") => (NET_FIRMWARE, "network chipset"),
+            Some("0x03") => (GPU_FIRMWARE, "GPU"),
+            _ => continue,
+        };
+
+        if let Some((_, name, pkg)) = table.iter().find(|(id, _, _)| *id == vendor) {
+            if !reqs.iter().any(|r| r.package == *pkg) {
+                reqs.push(Requirement {
+                    package: pkg,
+                    reason: format!("{} {}", name, kind),
+                });
+            }
+        }
+    }
+
+    reqs
+}
+
+/// `(package, reason)` for each firmware package in `EXTRA_FIRMWARE` that the
+/// detected hardware needs, without duplicates.
+pub fn extra_firmware() -> Vec<(&'static str, &'static str)> {
+    let mut found: Vec<(&str, &str)> = Vec::new();
+    for (vendor, class) in pci_devices() {
+        for (v, prefix, what, pkg) in EXTRA_FIRMWARE {
+            if vendor == *v && class.starts_with(prefix) && !found.iter().any(|(p, _)| p == pkg) {
+                found.push((pkg, what));
+            }
+        }
+    }
+    found
+}
+
+/// The hypervisor's name when running in a virtual machine: a known DMI
+/// vendor, else the CPU's `hypervisor` flag.
+pub fn hypervisor() -> Option<&'static str> {
+    let vendor = fs::read_to_string("/sys/class/dmi/id/sys_vendor").unwrap_or_default();
+    if let Some((_, name)) = HYPERVISORS.iter().find(|(v, _)| vendor.trim() == *v) {
+        // Hyper-V shares its vendor string with Surface and other hardware.
+        let product = fs::read_to_string("/sys/class/dmi/id/product_name").unwrap_or_default();
+        if *name != "Hyper-V" || product.trim() == "Virtual Machine" {
+            return Some(name);
+        }
+    }
+    let flagged = fs::read_to_string("/proc/cpuinfo").is_ok_and(|info| {
+        info.lines()
+            .find(|l| l.starts_with("flags"))
+            .is_some_and(|l| l.split_whitespace().any(|f| f == "hypervisor"))
+    });
+    flagged.then_some("a virtual machine")
+}
+
+/// Returns the `vendor_id` field of the first CPU in `/proc/cpuinfo`.
+pub fn cpu_vendor() -> Option<String> {
+    fs::read_to_string("/proc/cpuinfo")
+        .ok()?
+        .lines()
+        .find(|l| l.starts_with("vendor_id"))
+        .and_then(|l| l.split(':').nth(1))
+        .map(|v| v.trim().to_string())
+}
+
+/// Returns `(vendor, class)` hex strings for every PCI device, e.g. `("0x8086", "0x028000")`.
+fn pci_devices() -> Vec<(String, String)> {
+    let Ok(entries) = fs::read_dir("/sys/bus/pci/devices") else {
+        return vec![];
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let path = e.path();
+            let vendor = fs::read_to_string(path.join("vendor")).ok()?;
+            let class = fs::read_to_string(path.join("class")).ok()?;
+            Some((vendor.trim().to_string(), class.trim().to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,165 @@
+use crate::{cmd::{self, Cmd}, config::KernelVariant, error::InstallerError, prompt, ui};
+
+pub const POOL: &str = "zroot";
+/// The boot environment mounted at `/`.
+pub const ROOT_DATASET: &str = "zroot/ROOT/artix";
+
+/// Datasets below the pool: (name, properties). `/home` is separate so a
+/// new boot environment can be rolled back without touching user data.
+const DATASETS: &[(&str, &[&str])] = &[
+    ("zroot/ROOT", &["-o", "mountpoint=none"]),
+    (ROOT_DATASET, &["-o", "mountpoint=/", "-o", "canmount=noauto"]),
+    ("zroot/home", &["-o", "mountpoint=/home"]),
+];
+
+// ── Live environment ──────────────────────────────────────────────────────────
+
+/// Loads the ZFS module, offering to install `zfs-dkms` + `zfs-utils` on
+/// the live system first when it's missing. They come from a third-party
+/// repository (archzfs), which must already be enabled. Also makes sure a
+/// host id exists, since the pool records it.
+fn prepare_live(cmd: Cmd) -> Result<(), InstallerError> {
+    if cmd.run_capture("modprobe", &["zfs"]).is_ok() {
+        cmd.run_best_effort("zgenhostid", &[]);
+        return Ok(());
+    }
+
+    ui::print_warning("The ZFS kernel module isn't available in the live system.");
+    println!();
+    if !prompt::confirm("zfs_live", "Build and load ZFS in the live system now? (slow)", true)? {
+        return Err(InstallerError::Cancelled);
+    }
+    cmd.run_interactive("pacman", &["-Sy", "--needed", "--noconfirm", "zfs-dkms", "zfs-utils"])?;
+    cmd.run_with_spinner("modprobe", &["zfs"], "Loading ZFS…", "ZFS module loaded.")?;
+    cmd.run_best_effort("zgenhostid", &[]);
+    Ok(())
+}
+
+/// Creates the pool on `device` with the altroot at `/mnt`, then the
+/// datasets. The pool only uses features GRUB can read, so `grub-probe`
+/// and `grub-mkconfig` keep working.
+pub fn create_pool(cmd: Cmd, device: &str) -> Result<(), InstallerError> {
+    prepare_live(cmd)?;
+
+    // Left imported by an earlier attempt — gone once the disk is reused.
+    cmd.run_best_effort("zpool", &["export", POOL]);
+
+    #[rustfmt::skip]
+    let args = [
+        "create", "-f",
+        "-o", "ashift=12",
+        "-o", "compatibility=grub2",
+        "-O", "acltype=posixacl",
+        "-O", "xattr=sa",
+        "-O", "relatime=on",
+        "-O", "compression=lz4",
+        "-O", "mountpoint=none",
+        "-O", "canmount=off",
+        "-R", "/mnt",
+        POOL, device,
+    ];
+    cmd.run_with_spinner(
+        "zpool",
+        &args,
+        &format!("Creating ZFS pool {} on {}…", POOL, device),
+        &format!("Pool {} created on {}.", POOL, device),
+    )?;
+
+    for (name, props) in DATASETS {
+        let mut args = vec!["create"];
+        args.extend(props.iter());
+        args.push(name);
+        cmd.run_with_spinner(
+            "zfs",
+            &args,
+            &format!("Creating dataset {}…", name),
+            &format!("Dataset {} created.", name),
+        )?;
+    }
+    cmd.run_with_spinner(
+        "zpool",
+        &["set", &format!("bootfs={}", ROOT_DATASET), POOL],
+        "Marking the boot environment…",
+        &format!("{} is the boot dataset.", ROOT_DATASET),
+    )?;
+
+    // Exported so the mount step imports it the same way on a fresh run
+    // and on resume.
+    cmd.run_with_spinner(
+        "zpool",
+        &["export", POOL],
+        "Exporting the pool…",
+        "ZFS root ready.",
+    )
+}
+
+/// Imports the pool under `/mnt` (unless already imported) and mounts the
+/// root dataset, then the rest.
+pub fn mount(cmd: Cmd) -> Result<(), InstallerError> {
+    prepare_live(cmd)?;
+    if cmd.run_capture("zpool", &["list", "-H", "-o", "name", POOL]).is_err() {
+        cmd.run_with_spinner(
+            "zpool",
+            &["import", "-N", "-R", "/mnt", POOL],
+            &format!("Importing ZFS pool {}…", POOL),
+            &format!("Pool {} imported at /mnt.", POOL),
+        )?;
+    }
+    cmd.run_best_effort("zfs", &["mount", ROOT_DATASET]);
+    cmd.run_with_spinner(
+        "zfs",
+        &["mount", "-a"],
+        "Mounting ZFS datasets…",
+        &format!("{} mounted at /mnt.", ROOT_DATASET),
+    )
+}
+
+/// Unmounts every dataset and exports the pool, so the new system can
+/// import it on first boot.
+pub fn export() -> Result<(), InstallerError> {
+    cmd::run_with_spinner(
+        "zpool",
+        &["export", POOL],
+        &format!("Exporting ZFS pool {}…", POOL),
+        &format!("Pool {} exported.", POOL),
+    )
+}
+
+// ── Target ────────────────────────────────────────────────────────────────────
+
+/// Installs ZFS into the target — the DKMS module for every kernel plus
+/// the userland — and hands over the pool cache and host id, without
+/// which the initramfs refuses to import a pool last used elsewhere.
+pub fn install_in_target(kernels: &[KernelVariant]) -> Result<(), InstallerError> {
+    let mut args = vec!["/mnt", "zfs-dkms", "zfs-utils"];
+    args.extend(kernels.iter().map(|k| k.headers_package()));
+
+    ui::print_info("Installing ZFS support for the root pool…");
+    println!();
+    cmd::run_transaction("basestrap", &args)?;
+
+    cmd::run_with_spinner(
+        "zpool",
+        &["set", "cachefile=/etc/zfs/zpool.cache", POOL],
+        "Writing the pool cache…",
+        "Pool cache written.",
+    )?;
+    if !cmd::dry_run() {
+        std::fs::create_dir_all("/mnt/etc/zfs")?;
+    }
+    cmd::run_with_spinner(
+        "cp",
+        &["/etc/zfs/zpool.cache", "/mnt/etc/zfs/zpool.cache"],
+        "Copying the pool cache…",
+        "Pool cache copied to the new system.",
+    )?;
+    cmd::run_with_spinner(
+        "cp",
+        &["/etc/hostid", "/mnt/etc/hostid"],
+        "Copying the host id…",
+        "Host id copied to the new system.",
+    )?;
+
+    ui::print_success("ZFS installed in the new system.");
+    Ok(())
+}
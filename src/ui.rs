@@ -1,6 +1,27 @@
 use console::{style, Term};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::time::Duration;
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crate::{answers, error::InstallerError, prompt, steps::locale};
+
+/// Plain-ASCII output: no box drawing, braille or status glyphs.
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Screen-reader mode: no spinners or screen clearing, one line per action.
+static ACCESSIBLE: AtomicBool = AtomicBool::new(false);
+
+/// The keyboard-layout note before password prompts is shown once per run.
+static LAYOUT_NOTED: AtomicBool = AtomicBool::new(false);
+
+/// Shortest password `ask_password` accepts.
+const MIN_PASSWORD_LEN: usize = 8;
+
+/// Estimated entropy, in bits, below which a password is refused.
+const MIN_PASSWORD_BITS: f64 = 40.0;
 
 // ── Terminal helpers ──────────────────────────────────────────────────────────
 
@@ -8,11 +29,119 @@ fn term_width() -> usize {
     Term::stdout().size().1.max(60) as usize
 }
 
+// ── Output mode ───────────────────────────────────────────────────────────────
+
+/// Sets colour, glyph and accessibility mode for the rest of the run.
+/// Colour is also off when `NO_COLOR` is set; ASCII is also on when the
+/// locale isn't UTF-8 (`LANG=C` on a serial console, for instance) and in
+/// accessible mode, where glyphs would be read out as noise.
+pub fn configure(no_color: bool, ascii: bool, accessible: bool) {
+    let no_color = no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if no_color {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+    ASCII.store(ascii || accessible || !utf8_locale(), Ordering::Relaxed);
+    ACCESSIBLE.store(accessible, Ordering::Relaxed);
+}
+
+/// `true` with `--accessible`: prompts become numbered text questions.
+pub fn accessible() -> bool {
+    ACCESSIBLE.load(Ordering::Relaxed)
+}
+
+/// The first of `LC_ALL`, `LC_CTYPE`, `LANG` that is set decides; with none
+/// set the terminal is trusted.
+fn utf8_locale() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|k| std::env::var(k).ok().filter(|v| !v.is_empty()));
+    match locale {
+        Some(l) => {
+            let l = l.to_ascii_lowercase();
+            l.contains("utf-8") || l.contains("utf8")
+        }
+        None => true,
+    }
+}
+
+fn glyph(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if ASCII.load(Ordering::Relaxed) {
+        ascii
+    } else {
+        unicode
+    }
+}
+
+/// A horizontal rule `width` columns wide; empty in accessible mode.
+pub fn rule(width: usize) -> String {
+    if accessible() {
+        return String::new();
+    }
+    glyph("─", "-").repeat(width)
+}
+
+/// Status mark: a glyph, its ASCII stand-in, or a spoken word.
+fn mark(unicode: &'static str, ascii: &'static str, word: &'static str) -> &'static str {
+    if accessible() {
+        word
+    } else {
+        glyph(unicode, ascii)
+    }
+}
+
+/// The warning sign used in front of loud, all-caps notices.
+pub fn warn_mark() -> &'static str {
+    mark("⚠", "!", "Warning:")
+}
+
+/// Message text as it should be printed: in ASCII mode the punctuation the
+/// messages use (dashes, ellipses, arrows) is spelled out.
+pub fn text(s: &str) -> Cow<'_, str> {
+    if !ASCII.load(Ordering::Relaxed) || s.is_ascii() {
+        return Cow::Borrowed(s);
+    }
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '—' | '–' | '─' => out.push('-'),
+            '…' => out.push_str("..."),
+            '→' => out.push_str("->"),
+            '←' => out.push_str("<-"),
+            '↑' => out.push('^'),
+            '↓' => out.push('v'),
+            '·' => out.push('*'),
+            '≥' => out.push_str(">="),
+            '✓' => out.push('+'),
+            '✗' => out.push('x'),
+            '⚠' => out.push('!'),
+            '│' => out.push('|'),
+            c if c.is_ascii() => out.push(c),
+            _ => out.push('?'),
+        }
+    }
+    Cow::Owned(out)
+}
+
 // ── Banner ────────────────────────────────────────────────────────────────────
 
 pub fn print_banner() {
+    if accessible() {
+        println!("Artix Linux Installer, OpenRC Edition, version 0.1.0.");
+        return;
+    }
     let _ = Term::stdout().clear_screen();
 
+    if ASCII.load(Ordering::Relaxed) {
+        println!();
+        println!("{}", style("   ARTIX").cyan().bold());
+        println!("{}", style("   Linux Installer  *  OpenRC Edition  *  v0.1.0").dim());
+        println!();
+        println!("{}", style(rule(term_width().min(52))).dim());
+        println!();
+        return;
+    }
+
     // ASCII-art title in block letters (fits in ~50 columns)
     let logo = [
         r"   ░█████╗░██████╗░████████╗██╗██╗░░██╗",
@@ -35,7 +164,7 @@ pub fn print_banner() {
             .italic()
     );
     println!();
-    println!("{}", style("─".repeat(term_width().min(52))).dim());
+    println!("{}", style(rule(term_width().min(52))).dim());
     println!();
 }
 
@@ -44,32 +173,56 @@ pub fn print_banner() {
 /// Prints a visually distinct numbered step header.
 pub fn print_step(step: u8, total: u8, title: &str) {
     println!();
+    if accessible() {
+        println!("Step {} of {}: {}.", step, total, text(title));
+        return;
+    }
     let tag = style(format!(" {}/{} ", step, total)).black().on_cyan().bold();
-    let heading = style(format!("  {}", title)).white().bold();
+    let heading = style(format!("  {}", text(title))).white().bold();
     println!("{}{}", tag, heading);
-    println!("{}", style("─".repeat(term_width().min(52))).dim());
+    println!("{}", style(rule(term_width().min(52))).dim());
 }
 
 // ── Feedback messages ─────────────────────────────────────────────────────────
 
 /// Green ✓ — operation completed successfully.
 pub fn print_success(msg: &str) {
-    println!("  {}  {}", style("✓").green().bold(), style(msg).green());
+    let mark = mark("✓", "+", "Done:");
+    println!("  {}  {}", style(mark).green().bold(), style(text(msg)).green());
 }
 
 /// Blue → — neutral info / progress note.
 pub fn print_info(msg: &str) {
-    println!("  {}  {}", style("→").blue().bold(), msg);
+    let mark = mark("→", ">", "Note:");
+    println!("  {}  {}", style(mark).blue().bold(), text(msg));
 }
 
 /// Yellow ⚠  — non-fatal notice.
 pub fn print_warning(msg: &str) {
-    println!("  {}  {}", style("⚠").yellow().bold(), style(msg).yellow());
+    let mark = mark("⚠", "!", "Warning:");
+    println!("  {}  {}", style(mark).yellow().bold(), style(text(msg)).yellow());
 }
 
 /// Red ✗ — error (written to stderr).
 pub fn print_error(msg: &str) {
-    eprintln!("  {}  {}", style("✗").red().bold(), style(msg).red());
+    let mark = mark("✗", "x", "Error:");
+    eprintln!("  {}  {}", style(mark).red().bold(), style(text(msg)).red());
+}
+
+// ── Key legend ────────────────────────────────────────────────────────────────
+
+/// One dim line of `key action` pairs, printed above an interactive prompt.
+///
+/// ```text
+///   ↑/↓ select  ·  Enter confirm  ·  Ctrl-C save & quit
+/// ```
+pub fn print_legend(keys: &[(&str, &str)]) {
+    let line = keys
+        .iter()
+        .map(|(key, action)| format!("{} {}", style(*key).bold(), action))
+        .collect::<Vec<_>>()
+        .join("  ·  ");
+    println!("  {}", style(text(&line)).dim());
 }
 
 // ── Info box ──────────────────────────────────────────────────────────────────
@@ -77,51 +230,212 @@ pub fn print_error(msg: &str) {
 /// Renders a bordered key→value box in the terminal.
 ///
 /// ```text
-/// ┌─ Partition Layout ────────────────┐
-/// │  EFI/Boot    /dev/sda1            │
-/// │  Swap        /dev/sda2            │
-/// │  Root        /dev/sda3            │
-/// └───────────────────────────────────┘
+/// ┌─ Partition Layout ────────────────────┐
+/// │  EFI/Boot     /dev/sda1               │
+/// │  Swap         /dev/sda2               │
+/// │  Root         /dev/sda3               │
+/// └───────────────────────────────────────┘
 /// ```
+///
+/// The box grows with its content up to the terminal width (never below
+/// `BOX_MIN` columns); values that still don't fit are cut with `…`.
+///
+/// In ASCII mode the corners are `+` and the sides `-` / `|`; in
+/// accessible mode it is a titled list of `key: value` lines.
 pub fn print_kv_box(title: &str, rows: &[(&str, &str)]) {
-    const BOX_INNER: usize = 38;
+    const BOX_MIN: usize = 40;
+    const KEY_MIN: usize = 13;
+
+    if accessible() {
+        println!("  {}:", text(title));
+        for (key, val) in rows {
+            println!("  {}: {}", text(key.trim()), text(val));
+        }
+        return;
+    }
+
+    let title = text(title);
+    let rows: Vec<(Cow<str>, Cow<str>)> = rows.iter().map(|(k, v)| (text(k), text(v))).collect();
+    let width = |s: &str| console::measure_text_width(s);
 
-    let dashes = "─".repeat(BOX_INNER.saturating_sub(title.chars().count() + 2));
+    // Inner width: between the two side borders, 2 + key column + value + 1.
+    let max_inner = term_width().saturating_sub(4).max(BOX_MIN);
+    let key_col = rows
+        .iter()
+        .map(|(k, _)| width(k) + 2)
+        .max()
+        .unwrap_or(0)
+        .clamp(KEY_MIN, max_inner / 2);
+    let widest = rows.iter().map(|(_, v)| width(v)).max().unwrap_or(0);
+    let inner = (key_col + widest + 3)
+        .max(width(&title) + 4)
+        .clamp(BOX_MIN, max_inner);
+    let val_col = inner - key_col - 3;
+    let ellipsis = text("…");
+    let fit = |s: &str, w: usize| {
+        if width(s) <= w {
+            s.to_string()
+        } else {
+            console::truncate_str(s, w, &ellipsis).into_owned()
+        }
+    };
+
+    let title = fit(&title, inner - 4);
     println!(
-        "  ┌─ {} {}┐",
-        style(title).white().bold(),
-        style(&dashes).dim()
+        "  {}{} {} {}{}",
+        glyph("┌", "+"),
+        glyph("─", "-"),
+        style(&title).white().bold(),
+        style(rule(inner - width(&title) - 3)).dim(),
+        glyph("┐", "+")
     );
 
-    for (key, val) in rows {
+    for (key, val) in &rows {
+        let key = fit(key, key_col - 1);
+        let val = fit(val, val_col);
         println!(
-            "  │  {:<13}{}",
-            style(*key).dim(),
-            style(*val).white().bold()
+            "  {}  {}{}{}{} {}",
+            glyph("│", "|"),
+            style(&key).dim(),
+            " ".repeat(key_col - width(&key)),
+            style(&val).white().bold(),
+            " ".repeat(val_col - width(&val)),
+            glyph("│", "|")
         );
     }
 
-    println!("  └{}┘", style("─".repeat(BOX_INNER + 2)).dim());
+    println!("  {}{}{}", glyph("└", "+"), style(rule(inner)).dim(), glyph("┘", "+"));
 }
 
 // ── Spinner ───────────────────────────────────────────────────────────────────
 
-/// Returns a running braille spinner.
+/// Returns a running braille spinner (`| / - \` in ASCII mode).
 /// Call `pb.finish_and_clear()` (or the `done_spinner` helper) when done.
+///
+/// In accessible mode the message is printed once and the bar is hidden.
 pub fn spinner(msg: impl Into<String>) -> ProgressBar {
+    if accessible() {
+        println!("  {}", text(&msg.into()));
+        return ProgressBar::hidden();
+    }
+    let ticks: &[&str] = if ASCII.load(Ordering::Relaxed) {
+        &["|", "/", "-", "\\", " "]
+    } else {
+        &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
+    };
     let pb = ProgressBar::new_spinner();
     pb.set_style(
         ProgressStyle::with_template("  {spinner:.cyan.bold}  {msg}")
             .unwrap()
-            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]),
+            .tick_strings(ticks),
+    );
+    pb.set_message(text(&msg.into()).into_owned());
+    pb.enable_steady_tick(Duration::from_millis(80));
+    pb
+}
+
+/// Returns a progress bar with a spinner, a message and `pos/len` — for
+/// pacman transactions. Hidden in accessible mode, like [`spinner`].
+pub fn progress_bar(msg: &str) -> ProgressBar {
+    if accessible() {
+        println!("  {}", text(msg));
+        return ProgressBar::hidden();
+    }
+    let (ticks, bar): (&[&str], &str) = if ASCII.load(Ordering::Relaxed) {
+        (&["|", "/", "-", "\\", " "], "#>-")
+    } else {
+        (&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"], "█▌░")
+    };
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::with_template(
+            "  {spinner:.cyan.bold}  [{bar:30.cyan/dim}] {pos:>3}/{len:<3}  {wide_msg}",
+        )
+        .unwrap()
+        .tick_strings(ticks)
+        .progress_chars(bar),
     );
-    pb.set_message(msg.into());
+    pb.set_message(text(msg).into_owned());
     pb.enable_steady_tick(Duration::from_millis(80));
     pb
 }
 
+/// Shows the last few lines of a command's output under the spinner,
+/// dimmed and cut to the terminal width.
+pub fn spinner_tail(pb: &ProgressBar, msg: &str, lines: &[String]) {
+    let width = term_width().saturating_sub(10);
+    let mut out = text(msg).into_owned();
+    for line in lines {
+        let cut: String = line.chars().take(width).collect();
+        out.push_str(&format!("\n     {}", style(format!("{} {}", glyph("│", "|"), cut)).dim()));
+    }
+    pb.set_message(out);
+}
+
 /// Clears the spinner and prints a success message in its place.
 pub fn done_spinner(pb: ProgressBar, msg: &str) {
     pb.finish_and_clear();
     print_success(msg);
 }
+
+// ── Passwords ─────────────────────────────────────────────────────────────────
+
+/// Hidden input with confirmation (see `prompt::password`) that refuses
+/// passwords shorter than 8 characters or estimated below 40 bits. Used for
+/// every secret the installer asks for — root, users, LUKS, GRUB.
+///
+/// The first prompt also notes which keyboard layout the password will be
+/// typed with later, since the live system's layout may not match it: a
+/// password that works here can be untypeable at the boot prompt.
+pub fn ask_password(key: &str, prompt: &str) -> Result<String, InstallerError> {
+    if !LAYOUT_NOTED.swap(true, Ordering::Relaxed) {
+        match locale::vconsole_keymap() {
+            Some(k) if k != "us" => print_warning(&format!(
+                "The new console uses the '{}' layout — check the password types the same there.",
+                k
+            )),
+            Some(_) => {}
+            None => print_info("No console keymap set yet — at boot the US layout is used."),
+        }
+    }
+
+    loop {
+        let secret = prompt::password(key, prompt)?;
+        let Some(why) = password_weakness(&secret) else {
+            return Ok(secret);
+        };
+        if answers::get().is_some() {
+            return Err(InstallerError::Config(format!("'{}' is {}", key, why)));
+        }
+        print_warning(&format!("That password is {} — choose another.", why));
+    }
+}
+
+/// Why `secret` is too weak, if it is. Entropy is estimated as length times
+/// log2 of the character classes it draws from.
+fn password_weakness(secret: &str) -> Option<String> {
+    let len = secret.chars().count();
+    if len < MIN_PASSWORD_LEN {
+        return Some(format!("shorter than {} characters", MIN_PASSWORD_LEN));
+    }
+    let has = |class: fn(&char) -> bool| secret.chars().any(|c| class(&c));
+    let mut pool = 0u32;
+    if has(char::is_ascii_lowercase) {
+        pool += 26;
+    }
+    if has(char::is_ascii_uppercase) {
+        pool += 26;
+    }
+    if has(char::is_ascii_digit) {
+        pool += 10;
+    }
+    if has(|c| !c.is_ascii_alphanumeric()) {
+        pool += 33;
+    }
+    let mut distinct: Vec<char> = secret.chars().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    // Repeats ("aaaaaaaa", "abababab") add length but little entropy.
+    let bits = distinct.len().max(len / 2) as f64 * f64::from(pool).log2();
+    (bits < MIN_PASSWORD_BITS).then(|| format!("too easy to guess (~{:.0} bits)", bits))
+}